@@ -0,0 +1,4 @@
+-- 分类支持父子层级
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS parent_id BIGINT NULL REFERENCES categories (id);
+
+CREATE INDEX IF NOT EXISTS idx_categories_parent_id ON categories (parent_id);
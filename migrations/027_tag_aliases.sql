@@ -0,0 +1,12 @@
+-- 标签别名：合并或重命名后旧的标签ID和名称仍然可以解析到新标签
+CREATE TABLE IF NOT EXISTS tag_aliases
+(
+    id           BIGSERIAL PRIMARY KEY,
+    tag_id       BIGINT       NOT NULL REFERENCES tags (id),
+    alias_tag_id BIGINT       NULL REFERENCES tags (id),
+    name         VARCHAR(255) NOT NULL,
+    create_at    TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_aliases_alias_tag_id ON tag_aliases (alias_tag_id);
+CREATE INDEX IF NOT EXISTS idx_tag_aliases_name ON tag_aliases (lower(name));
@@ -0,0 +1,10 @@
+-- 博客每日浏览量（按访客去重）
+CREATE TABLE IF NOT EXISTS blog_view_daily
+(
+    blog_id    BIGINT NOT NULL REFERENCES blogs (id),
+    view_date  DATE   NOT NULL,
+    view_count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (blog_id, view_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_blog_view_daily_date ON blog_view_daily (view_date);
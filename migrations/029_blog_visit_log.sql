@@ -0,0 +1,14 @@
+-- 博客访问记录（去重后的浏览），用于统计独立访客、访客地区和客户端平台
+CREATE TABLE IF NOT EXISTS blog_visit_logs
+(
+    id        BIGSERIAL PRIMARY KEY,
+    blog_id   BIGINT       NOT NULL REFERENCES blogs (id),
+    visitor   VARCHAR(32)  NOT NULL,
+    ip        TEXT         NOT NULL,
+    city      TEXT         NOT NULL,
+    platform  TEXT         NOT NULL,
+    create_at TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_blog_visit_logs_create_at ON blog_visit_logs (create_at);
+CREATE INDEX IF NOT EXISTS idx_blog_visit_logs_blog_id ON blog_visit_logs (blog_id);
@@ -0,0 +1,10 @@
+-- 访问来源：外部来源页面及其域名，以及推广链接上的 utm_* 参数
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS referrer        TEXT         NULL;
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS referrer_domain VARCHAR(255) NULL;
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS utm_source      VARCHAR(100) NULL;
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS utm_medium      VARCHAR(100) NULL;
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS utm_campaign    VARCHAR(100) NULL;
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS utm_term        VARCHAR(100) NULL;
+ALTER TABLE blog_visit_logs ADD COLUMN IF NOT EXISTS utm_content     VARCHAR(100) NULL;
+
+CREATE INDEX IF NOT EXISTS idx_blog_visit_logs_referrer_domain ON blog_visit_logs (referrer_domain);
@@ -0,0 +1,15 @@
+-- 收到的 Webmention，异步验证来源页面确实链接到博客后 status 改为 verified
+CREATE TABLE IF NOT EXISTS webmentions
+(
+    id          BIGSERIAL PRIMARY KEY,
+    blog_id     BIGINT      NOT NULL REFERENCES blogs (id),
+    source      TEXT        NOT NULL,
+    target      TEXT        NOT NULL,
+    status      VARCHAR(16) NOT NULL DEFAULT 'pending',
+    title       TEXT        NULL,
+    create_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    verified_at TIMESTAMPTZ NULL,
+    UNIQUE (source, blog_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webmentions_blog_id ON webmentions (blog_id, status);
@@ -0,0 +1,32 @@
+-- Webhook 配置，events 为订阅的事件，* 表示全部事件
+CREATE TABLE IF NOT EXISTS webhooks
+(
+    id          BIGSERIAL PRIMARY KEY,
+    url         TEXT         NOT NULL,
+    secret      VARCHAR(128) NOT NULL,
+    events      TEXT[]       NOT NULL,
+    enabled     BOOLEAN      NOT NULL DEFAULT TRUE,
+    description TEXT         NULL,
+    create_at   TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    update_at   TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+-- Webhook 投递记录，失败后按 next_attempt_at 退避重试
+CREATE TABLE IF NOT EXISTS webhook_deliveries
+(
+    id              BIGSERIAL PRIMARY KEY,
+    webhook_id      BIGINT      NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+    event           VARCHAR(64) NOT NULL,
+    payload         TEXT        NOT NULL,
+    status          VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts        INT         NOT NULL DEFAULT 0,
+    response_status INT         NULL,
+    response_body   TEXT        NULL,
+    error           TEXT        NULL,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    create_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    update_at       TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_pending ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id, id DESC);
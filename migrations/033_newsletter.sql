@@ -0,0 +1,28 @@
+-- 邮件订阅，category_ids 和 topic_ids 都为空时订阅全部博客
+-- 新的订阅范围先保存在 pending_* 中，确认后才生效
+CREATE TABLE IF NOT EXISTS newsletter_subscribers
+(
+    id                BIGSERIAL PRIMARY KEY,
+    email             VARCHAR(255) NOT NULL UNIQUE,
+    status            VARCHAR(16)  NOT NULL DEFAULT 'pending',
+    confirm_token     VARCHAR(64)  NOT NULL UNIQUE,
+    unsubscribe_token VARCHAR(64)  NOT NULL UNIQUE,
+    category_ids      BIGINT[]     NOT NULL DEFAULT '{}',
+    topic_ids         BIGINT[]     NOT NULL DEFAULT '{}',
+    pending_category_ids BIGINT[]  NOT NULL DEFAULT '{}',
+    pending_topic_ids    BIGINT[]  NOT NULL DEFAULT '{}',
+    create_at         TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    confirmed_at      TIMESTAMPTZ  NULL,
+    unsubscribed_at   TIMESTAMPTZ  NULL
+);
+
+-- 已经发送过通知的博客，避免重复发送
+-- last_subscriber_id 为已经加入邮件队列的最后一个订阅者，服务重启后从这里继续发送，finished_at 为空表示还没有发送完
+CREATE TABLE IF NOT EXISTS newsletter_issues
+(
+    blog_id            BIGINT PRIMARY KEY REFERENCES blogs (id),
+    sent_count         BIGINT      NOT NULL DEFAULT 0,
+    last_subscriber_id BIGINT      NOT NULL DEFAULT 0,
+    create_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at        TIMESTAMPTZ NULL
+);
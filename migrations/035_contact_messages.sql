@@ -0,0 +1,17 @@
+-- 联系我留言，先保存再发送邮件
+CREATE TABLE IF NOT EXISTS contact_messages
+(
+    id          BIGSERIAL PRIMARY KEY,
+    name        VARCHAR(255) NOT NULL,
+    email       VARCHAR(255) NOT NULL,
+    subject     TEXT         NOT NULL,
+    content     TEXT         NOT NULL,
+    ip          TEXT         NOT NULL,
+    is_read     BOOLEAN      NOT NULL DEFAULT FALSE,
+    archived    BOOLEAN      NOT NULL DEFAULT FALSE,
+    create_at   TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    read_at     TIMESTAMPTZ  NULL,
+    deleted_at  TIMESTAMPTZ  NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_messages_create_at ON contact_messages (create_at DESC) WHERE deleted_at IS NULL;
@@ -0,0 +1,23 @@
+-- 待发送邮件队列，发送失败后按 next_attempt_at 退避重试，超过最大次数或超过 expire_at 后状态为 dead
+CREATE TABLE IF NOT EXISTS email_queue
+(
+    id              BIGSERIAL PRIMARY KEY,
+    to_email        VARCHAR(255) NOT NULL,
+    subject         TEXT         NOT NULL,
+    html            TEXT         NOT NULL,
+    text            TEXT         NOT NULL,
+    status          VARCHAR(16)  NOT NULL DEFAULT 'pending',
+    attempts        INT          NOT NULL DEFAULT 0,
+    last_error      TEXT         NULL,
+    next_attempt_at TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    expire_at       TIMESTAMPTZ  NULL,
+    create_at       TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    update_at       TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    sent_at         TIMESTAMPTZ  NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_queue_pending ON email_queue (next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_email_queue_status ON email_queue (status, id DESC);
+
+-- 联系留言记录对应的队列邮件，发送状态从队列中获取
+ALTER TABLE contact_messages ADD COLUMN IF NOT EXISTS email_id BIGINT NULL REFERENCES email_queue (id) ON DELETE SET NULL;
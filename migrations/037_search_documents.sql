@@ -0,0 +1,12 @@
+-- PostgreSQL 全文搜索使用的文档表，search_vector 由程序切分中文后使用 simple 配置生成
+CREATE TABLE IF NOT EXISTS search_documents
+(
+    index_uid     VARCHAR(64) NOT NULL,
+    id            BIGINT      NOT NULL,
+    document      JSONB       NOT NULL,
+    search_vector TSVECTOR    NOT NULL,
+    update_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (index_uid, id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_documents_vector ON search_documents USING GIN (search_vector);
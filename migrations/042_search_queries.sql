@@ -0,0 +1,12 @@
+-- 搜索关键字记录，keyword 为去掉首尾空格并转为小写后的关键字
+CREATE TABLE IF NOT EXISTS search_queries
+(
+    id           BIGSERIAL PRIMARY KEY,
+    keyword      VARCHAR(100) NOT NULL,
+    result_count BIGINT       NOT NULL DEFAULT 0,
+    page         BIGINT       NOT NULL DEFAULT 1,
+    create_at    TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_queries_create_at ON search_queries (create_at);
+CREATE INDEX IF NOT EXISTS idx_search_queries_keyword ON search_queries (keyword, create_at);
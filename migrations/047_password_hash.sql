@@ -0,0 +1,2 @@
+-- 密码改为 Argon2id PHC 字符串，旧的 MD5 哈希在用户下次登录时升级
+ALTER TABLE users ALTER COLUMN password TYPE VARCHAR(255);
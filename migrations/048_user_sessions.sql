@@ -0,0 +1,19 @@
+-- 用户登录会话，每次登录创建一条记录，刷新令牌只保存 SHA-256 哈希，
+-- previous_refresh_token_hash 为上一个刷新令牌的哈希，用于发现已轮换的令牌被重复使用
+CREATE TABLE IF NOT EXISTS user_sessions
+(
+    id                 VARCHAR(32)  PRIMARY KEY,
+    user_id            BIGINT       NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    refresh_token_hash VARCHAR(64)  NOT NULL,
+    previous_refresh_token_hash VARCHAR(64) NULL,
+    device             VARCHAR(255) NOT NULL,
+    ip                 VARCHAR(64)  NOT NULL,
+    city               VARCHAR(255) NOT NULL,
+    create_at          TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    last_active_at     TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    expire_at          TIMESTAMPTZ  NOT NULL,
+    revoked_at         TIMESTAMPTZ  NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user ON user_sessions (user_id, last_active_at DESC) WHERE revoked_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_user_sessions_expire ON user_sessions (expire_at);
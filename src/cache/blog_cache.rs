@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 
 use r2d2_redis::redis::{Commands, RedisError, RedisResult};
+use r2d2_redis::redis;
 
 use crate::common::redis_keys::{
-    BLOG_MAP_KEY, EYE_COUNT_MAP, HOT_BLOG_KEY, HOT_BLOG_KEY_EXPIRE, LATEST_BLOG_KEY,
-    LATEST_BLOG_KEY_EXPIRE, RECOMMEND_BLOG_KEY, SAVE_BLOG_MAP,
+    BLOG_MAP_KEY, BLOG_VIEW_VISITOR_KEY, EYE_COUNT_MAP, HOT_BLOG_KEY, HOT_BLOG_KEY_EXPIRE,
+    LATEST_BLOG_KEY, LATEST_BLOG_KEY_EXPIRE, RECOMMEND_BLOG_KEY, SAVE_BLOG_MAP,
 };
 use crate::conf::redis_config::get_pool_connection;
 use crate::models::blogs::{BlogContentVo, RecommendBlogVo, SimpleBlogVo};
@@ -96,6 +97,30 @@ impl BlogCache {
         return count;
     }
 
+    // 获取博客当前的浏览次数（不增加）
+    pub fn get_view_count(&self, default_count: i64, id: i64) -> i64 {
+        return get_pool_connection()
+            .hget::<&str, i64, i64>(EYE_COUNT_MAP, id)
+            .unwrap_or(default_count);
+    }
+
+    // 标记访客浏览过该博客，在去重时间内首次浏览返回 true
+    pub fn mark_visitor(&self, id: i64, visitor: &str, expire: usize) -> bool {
+        let key = format!("{}{}:{}", BLOG_VIEW_VISITOR_KEY, id, visitor);
+        let result: RedisResult<Option<String>> = redis::cmd("SET")
+            .arg(key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(expire)
+            .query(&mut *get_pool_connection());
+        return match result {
+            Ok(r) => r.is_some(),
+            // Redis 出错时不影响浏览，按新访客处理
+            Err(_) => true,
+        };
+    }
+
     // 从 Redis 获取博客信息
     pub fn get_blog_info(&self, id: i64) -> Result<Option<BlogContentVo>, RedisError> {
         let result: RedisResult<String> = get_pool_connection().hget(BLOG_MAP_KEY, id);
@@ -8,6 +8,7 @@ use crate::common::redis_keys::{
 use crate::conf::redis_config::get_pool_connection;
 
 pub mod blog_cache;
+pub(crate) mod session_cache;
 pub(crate) mod user_cache;
 
 // 清除页面信息的 Redis 键
@@ -49,3 +50,14 @@ pub fn clear_user_info(username: &String) {
         .del::<String, i64>(USER_INFO_KEY.to_owned() + username)
         .unwrap();
 }
+
+// 计数限流，返回窗口内的请求次数，第一次请求时设置窗口的过期时间
+pub fn incr_rate_limit(key: String, expire: usize) -> i64 {
+    let count = get_pool_connection()
+        .incr::<&String, i64, i64>(&key, 1)
+        .unwrap_or(0);
+    if count == 1 {
+        let _ = get_pool_connection().expire::<&String, i64>(&key, expire);
+    }
+    return count;
+}
@@ -0,0 +1,43 @@
+use r2d2_redis::redis::Commands;
+
+use crate::common::redis_keys::{USER_SESSION_KEY, USER_SESSION_KEY_EXPIRE};
+use crate::conf::redis_config::get_pool_connection;
+
+// 登录会话缓存，只缓存有效的会话，撤销会话时删除
+pub struct SessionCache {}
+
+impl SessionCache {
+    pub fn new() -> SessionCache {
+        return SessionCache {};
+    }
+
+    // 缓存有效会话所属的用户 ID
+    pub fn set_session(&self, session_id: &String, user_id: i64) -> bool {
+        return get_pool_connection()
+            .set_ex::<String, i64, String>(
+                USER_SESSION_KEY.to_owned() + session_id,
+                user_id,
+                USER_SESSION_KEY_EXPIRE,
+            )
+            .is_ok();
+    }
+
+    // 从 Redis 获取会话所属的用户 ID
+    pub fn get_session(&self, session_id: &String) -> Option<i64> {
+        return get_pool_connection()
+            .get::<String, i64>(USER_SESSION_KEY.to_owned() + session_id)
+            .ok();
+    }
+
+    // 从 Redis 删除会话
+    pub fn remove_sessions(&self, session_ids: &Vec<String>) -> bool {
+        if session_ids.is_empty() {
+            return true;
+        }
+        let keys: Vec<String> = session_ids
+            .iter()
+            .map(|id| USER_SESSION_KEY.to_owned() + id)
+            .collect();
+        return get_pool_connection().del::<Vec<String>, i64>(keys).is_ok();
+    }
+}
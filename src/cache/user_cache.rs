@@ -1,15 +1,16 @@
+use r2d2_redis::redis;
 use r2d2_redis::redis::{Commands, RedisError};
 
 use crate::common::redis_keys::{
-    BLOG_WEB_CONFIG, DAY, EMAIL_CODE_KEY, EMAIL_CODE_KEY_EXPIRE, USER_INFO_KEY,
-    USER_INFO_KEY_EXPIRE, USER_TOKEN_KEY,
+    BLOG_WEB_CONFIG, EMAIL_CODE_ATTEMPT_EXPIRE, EMAIL_CODE_ATTEMPT_KEY, EMAIL_CODE_SEND_EXPIRE,
+    EMAIL_CODE_SEND_KEY, USER_INFO_KEY, USER_INFO_KEY_EXPIRE,
 };
-use crate::conf::config::CONFIG;
 use crate::conf::redis_config::get_pool_connection;
 use crate::models::user::UserVo;
 use crate::response::website_info::BlogConfigInfo;
 
 // 用户缓存结构
+#[derive(Clone)]
 pub struct UserCache {}
 
 impl UserCache {
@@ -38,26 +39,66 @@ impl UserCache {
         }
     }
 
-    // 设置邮箱验证码到 Redis
-    pub fn set_email_code(&self, ip: &String, code: &String) -> bool {
+    // 设置邮箱验证码到 Redis，key 区分注册、重置密码等不同用途，不会重置错误次数
+    pub fn set_email_code(&self, key: &str, email: &String, code: &String, expire: usize) -> bool {
         return get_pool_connection()
-            .set_ex::<String, &String, String>(
-                EMAIL_CODE_KEY.to_owned() + ip,
-                code,
-                EMAIL_CODE_KEY_EXPIRE,
-            )
+            .set_ex::<String, &String, String>(key.to_owned() + email, code, expire)
             .is_ok();
     }
 
     // 从 Redis 获取邮箱验证码
-    pub fn get_email_code(&self, email: &String) -> Result<String, RedisError> {
-        let result = get_pool_connection().get::<String, String>(EMAIL_CODE_KEY.to_owned() + email);
+    pub fn get_email_code(&self, key: &str, email: &String) -> Result<String, RedisError> {
+        let result = get_pool_connection().get::<String, String>(key.to_owned() + email);
         return match result {
             Ok(r) => Ok(r),
             Err(e) => Err(e),
         };
     }
 
+    // 从 Redis 删除邮箱验证码和错误次数
+    pub fn remove_email_code(&self, key: &str, email: &String) -> bool {
+        return get_pool_connection()
+            .del::<Vec<String>, i64>(vec![
+                key.to_owned() + email,
+                EMAIL_CODE_ATTEMPT_KEY.to_owned() + key + email,
+            ])
+            .is_ok();
+    }
+
+    // 获取邮箱验证码累计的错误次数
+    pub fn get_email_code_attempts(&self, key: &str, email: &String) -> i64 {
+        return get_pool_connection()
+            .get::<String, Option<i64>>(EMAIL_CODE_ATTEMPT_KEY.to_owned() + key + email)
+            .unwrap_or(Some(i64::MAX))
+            .unwrap_or(0);
+    }
+
+    // 记录一次验证码错误，返回累计的错误次数，错误次数按邮箱单独计算过期时间
+    pub fn incr_email_code_attempts(&self, key: &str, email: &String) -> i64 {
+        let attempt_key = EMAIL_CODE_ATTEMPT_KEY.to_owned() + key + email;
+        let attempts = get_pool_connection()
+            .incr::<&String, i64, i64>(&attempt_key, 1)
+            .unwrap_or(i64::MAX);
+        if attempts == 1 {
+            let _ = get_pool_connection()
+                .expire::<&String, i64>(&attempt_key, EMAIL_CODE_ATTEMPT_EXPIRE);
+        }
+        return attempts;
+    }
+
+    // 限制同一邮箱验证码的发送频率，返回 false 表示发送太频繁
+    pub fn lock_email_code_send(&self, key: &str, email: &String) -> bool {
+        let result: Option<String> = redis::cmd("SET")
+            .arg(EMAIL_CODE_SEND_KEY.to_owned() + key + email)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(EMAIL_CODE_SEND_EXPIRE)
+            .query(&mut *get_pool_connection())
+            .unwrap_or(Some(String::from("OK")));
+        return result.is_some();
+    }
+
     // 从 Redis 获取网站配置
     pub fn get_website_config(&self) -> Option<BlogConfigInfo> {
         let result = get_pool_connection().get::<String, String>(BLOG_WEB_CONFIG.to_owned());
@@ -76,33 +117,4 @@ impl UserCache {
             )
             .is_ok();
     }
-
-    // 设置用户令牌到 Redis
-    pub fn set_token(&self, username: &String, token: &String) -> bool {
-        return get_pool_connection()
-            .set_ex::<String, String, String>(
-                USER_TOKEN_KEY.to_owned() + username,
-                token.to_owned(),
-                (CONFIG.token.expire as usize) * DAY,
-            )
-            .is_ok();
-    }
-
-    // 从 Redis 删除用户令牌
-    pub fn remove_user_token(&self, username: &String) -> bool {
-        return get_pool_connection()
-            .del::<String, String>(USER_TOKEN_KEY.to_owned() + username)
-            .is_ok();
-    }
-
-    // 从 Redis 获取用户令牌
-    pub fn get_token(&self, username: &String) -> Option<String> {
-        return if let Ok(r) =
-            get_pool_connection().get::<String, String>(USER_TOKEN_KEY.to_owned() + username)
-        {
-            Some(r)
-        } else {
-            None
-        };
-    }
 }
@@ -25,6 +25,18 @@ pub const USER_TOP_BLOG_PAGE_SIZE: i64 = 10;
 // 搜索博客页面大小
 pub const SEARCH_BLOG_PAGE_SIZE: i64 = 10;
 
+// 搜索博客页面大小的上限
+pub const SEARCH_BLOG_MAX_PAGE_SIZE: i64 = 50;
+
+// 搜索结果正文片段的默认长度和上限
+pub const SEARCH_CROP_LENGTH: i64 = 50;
+pub const SEARCH_MAX_CROP_LENGTH: i64 = 200;
+
+// 搜索结果默认的高亮标签，自定义 <span> 高亮标签的 class 最大长度
+pub const SEARCH_HIGHLIGHT_PRE_TAG: &str = "<b>";
+pub const SEARCH_HIGHLIGHT_POST_TAG: &str = "</b>";
+pub const SEARCH_HIGHLIGHT_CLASS_MAX_LENGTH: usize = 32;
+
 // 随机标签列表数量
 pub const TAG_RANDOM_LIST_COUNT: usize = 20;
 
@@ -46,7 +58,82 @@ pub const TOPIC_ADMIN_PAGE_COUNT: i64 = 15;
 // 后台管理文件页面数量
 pub const FILE_ADMIN_PAGE_COUNT: i64 = 15;
 
+// 博客每日浏览量最多查询天数
+pub const MAX_VIEW_RANGE_DAYS: i64 = 366;
+
+// 统计面板默认统计天数
+pub const DASHBOARD_DEFAULT_DAYS: i64 = 30;
+
+// 统计面板热门博客、地区、平台默认数量
+pub const DASHBOARD_TOP_COUNT: i64 = 10;
+
+// 统计面板每月发布博客统计的月数
+pub const DASHBOARD_MONTH_COUNT: i32 = 12;
+
+// 访问来源 utm 参数保存的最大长度
+pub const MAX_UTM_LENGTH: usize = 100;
+
+// 访问来源页面和域名保存的最大长度
+pub const MAX_REFERRER_LENGTH: usize = 255;
+
+// 后台管理 Webhook 投递记录页面数量
+pub const WEBHOOK_DELIVERY_PAGE_COUNT: i64 = 20;
+
+// 每次处理的待投递 Webhook 数量
+pub const WEBHOOK_DELIVERY_BATCH_SIZE: i64 = 50;
+
+// 后台管理联系留言页面数量
+pub const CONTACT_ADMIN_PAGE_COUNT: i64 = 15;
+
+// 后台管理邮件队列页面数量
+pub const EMAIL_QUEUE_PAGE_COUNT: i64 = 20;
+
+// 搜索文档中正文摘录的最大字符数
+pub const SEARCH_CONTENT_MAX_LENGTH: usize = 2000;
+
+// 对比搜索索引时每次从数据库读取的文档数量
+pub const SEARCH_RECONCILE_BATCH_SIZE: i64 = 500;
+
+// 搜索建议中每一类结果的最大数量
+pub const SEARCH_SUGGEST_COUNT: usize = 5;
+
+// 全站搜索每种结果的数量
+pub const SEARCH_ALL_GROUP_SIZE: i64 = 5;
+
+// 搜索建议关键字的最大字符数
+pub const SEARCH_SUGGEST_MAX_CHARS: usize = 50;
+
+// 不超过这个字符数的搜索建议会被缓存，短前缀的请求最多
+pub const SEARCH_SUGGEST_CACHE_CHARS: usize = 3;
+
+// 记录的搜索关键字的最大字符数
+pub const SEARCH_QUERY_MAX_CHARS: usize = 100;
+
+// 搜索记录保留的天数
+pub const SEARCH_QUERY_RETENTION_DAYS: i64 = 180;
+
 // 默认页数（用于分页）
 pub fn default_page() -> i64 {
     1
 }
+
+// 过期或撤销的登录会话保留天数，超过后定时删除
+pub const SESSION_RETENTION_DAYS: i64 = 30;
+
+// 登录会话记录的设备信息最大长度
+pub const SESSION_DEVICE_MAX_LENGTH: usize = 255;
+
+// 邮件验证码最多可以输错的次数，超过后该邮箱的验证码在一段时间内都不能使用
+pub const EMAIL_CODE_MAX_ATTEMPTS: i64 = 5;
+
+// 用户昵称最大长度
+pub const NICK_NAME_MAX_LENGTH: usize = 32;
+
+// Webmention 抓取页面时最多跟随的重定向次数
+pub const WEBMENTION_MAX_REDIRECTS: usize = 5;
+
+// 同一 IP 每小时最多提交的订阅请求数量
+pub const NEWSLETTER_SUBSCRIBE_IP_LIMIT: i64 = 10;
+
+// 同一 IP 每分钟最多提交的 Webmention 数量
+pub const WEBMENTION_RATE_LIMIT: i64 = 10;
@@ -27,3 +27,9 @@ pub fn time_stamp_to_date(time_stamp: i64) -> DateTime<Local> {
     let time = NaiveDateTime::from_timestamp_millis(time_stamp).unwrap();
     return Local.from_utc_datetime(&time);
 }
+
+// 用户传入的时间戳可能超出范围，超出时返回 None
+pub fn try_time_stamp_to_date(time_stamp: i64) -> Option<DateTime<Local>> {
+    let time = NaiveDateTime::from_timestamp_millis(time_stamp)?;
+    return Some(Local.from_utc_datetime(&time));
+}
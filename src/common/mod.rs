@@ -21,6 +21,16 @@ lazy_static! {
     static ref IMAGE_REG: String = String::from(r#"(?i)\.(jpg|jpeg|png|gif|bmp)$"#);
 }
 
+// 去除 Markdown 和 HTML 标记，用于生成搜索文档中的纯文本
+lazy_static! {
+    static ref MD_CODE_BLOCK_REG: Regex = Regex::new(r"(?s)```.*?```").unwrap();
+    static ref MD_IMAGE_REG: Regex = Regex::new(r"!\[[^\]]*\]\([^)]*\)").unwrap();
+    static ref MD_LINK_REG: Regex = Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+    static ref HTML_TAG_REG: Regex = Regex::new(r"<[^>]+>").unwrap();
+    static ref MD_MARK_REG: Regex = Regex::new(r"(?m)^\s{0,3}(#{1,6}|>|[-*+]|\d+\.)\s+|[*`~|]+").unwrap();
+    static ref WHITESPACE_REG: Regex = Regex::new(r"\s+").unwrap();
+}
+
 // 创建 IP2Region 搜索器实例
 lazy_static! {
     static ref SEARCHER: ip2region::Searcher =
@@ -123,6 +133,50 @@ pub fn get_client_platform_info(user_agent: &str) -> String {
     }
 }
 
+// 转义 HTML 特殊字符，用户输入的内容放进邮件等 HTML 之前需要转义
+pub fn escape_html(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    return result;
+}
+
+// 把 Markdown 内容转换为纯文本，代码块会被去掉，最多保留 max_chars 个字符
+pub fn markdown_to_text(content: &str, max_chars: usize) -> String {
+    let text = MD_CODE_BLOCK_REG.replace_all(content, " ");
+    let text = MD_IMAGE_REG.replace_all(&text, " ");
+    let text = MD_LINK_REG.replace_all(&text, "$1");
+    let text = HTML_TAG_REG.replace_all(&text, " ");
+    let text = MD_MARK_REG.replace_all(&text, " ");
+    let text = WHITESPACE_REG.replace_all(&text, " ");
+    return text.trim().chars().take(max_chars).collect();
+}
+
+// 提取链接中的域名，统一小写并去掉端口和 www. 前缀
+pub fn get_url_domain(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = match url.find("://") {
+        Some(i) => &url[i + 3..],
+        None => return None,
+    };
+    let host = rest.split(|c| c == '/' || c == '?' || c == '#').next()?;
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    let host = host.trim_start_matches("www.").trim_end_matches('.');
+    if host.is_empty() {
+        return None;
+    }
+    return Some(host.to_string());
+}
+
 pub fn get_size_str(size: f64) -> String {
     if size == 0.0 {
         return "0 B".to_string();
@@ -208,3 +262,28 @@ pub fn get_ip_address(request: &HttpRequest) -> String {
         .map(|ip_address| ip_address.to_string())
         .unwrap_or_else(|| "".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_html_special_chars() {
+        assert_eq!(
+            escape_html("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+        assert_eq!(escape_html("中文 text"), "中文 text");
+    }
+
+    #[test]
+    fn markdown_to_text_strips_markup() {
+        let content = "# 标题\n\n```rust\nfn main() {}\n```\n\n> **粗体** 和 `代码`\n\n- [链接](https://a.com) ![图片](a.png) <b>html</b>";
+        assert_eq!(markdown_to_text(content, 100), "标题 粗体 和 代码 链接 html");
+    }
+
+    #[test]
+    fn markdown_to_text_truncates_chars() {
+        assert_eq!(markdown_to_text("中文内容很长", 4), "中文内容");
+    }
+}
@@ -1,7 +1,6 @@
 // 这些常量代表时间单位的秒数
 pub const MIN: usize = 60 * 1;
 pub const HOUR: usize = 60 * 60;
-pub const DAY: usize = 60 * 60 * 24;
 
 // 用户信息键
 pub const USER_INFO_KEY: &str = "USER-INFO:";
@@ -18,6 +17,18 @@ pub const BLOG_MAP_KEY: &str = "BLOG-MAP";
 // 邮件验证码键
 pub const EMAIL_CODE_KEY: &str = "EMAIL-CODE:";
 
+// 重置密码邮件验证码键
+pub const PASSWORD_RESET_CODE_KEY: &str = "PASSWORD-RESET-CODE:";
+
+// 修改邮箱验证码键，后面拼接用户 ID 和新邮箱
+pub const EMAIL_CHANGE_CODE_KEY: &str = "EMAIL-CHANGE-CODE:";
+
+// 邮件验证码错误次数键，后面拼接验证码键
+pub const EMAIL_CODE_ATTEMPT_KEY: &str = "EMAIL-CODE-ATTEMPT:";
+
+// 邮件验证码发送频率限制键，后面拼接验证码键
+pub const EMAIL_CODE_SEND_KEY: &str = "EMAIL-CODE-SEND:";
+
 // 网站配置键
 pub const BLOG_WEB_CONFIG: &str = "WEBSITE-CONFIG";
 
@@ -42,11 +53,38 @@ pub const TOPIC_MAP_KEY: &str = "TOPIC-MAP";
 // 博客浏览次数映射键
 pub const EYE_COUNT_MAP: &str = "BLOG_EYE_COUNT_MAP_KEY";
 
+// 博客访客去重键
+pub const BLOG_VIEW_VISITOR_KEY: &str = "BLOG-VIEW-VISITOR:";
+
+// 后台统计面板键
+pub const DASHBOARD_INFO_KEY: &str = "DASHBOARD-INFO:";
+
+// 上传文件存储空间统计键
+pub const STORAGE_INFO_KEY: &str = "STORAGE-INFO";
+
+// 邮件订阅确认邮件发送限制键
+pub const NEWSLETTER_SUBSCRIBE_KEY: &str = "NEWSLETTER-SUBSCRIBE:";
+
+// 同一 IP 订阅的限流键
+pub const NEWSLETTER_SUBSCRIBE_IP_KEY: &str = "NEWSLETTER-SUBSCRIBE-IP:";
+
+// 同一 IP 提交 Webmention 的限流键
+pub const WEBMENTION_RATE_KEY: &str = "WEBMENTION-RATE:";
+
+// 访问来源报表键
+pub const REFERRER_REPORT_KEY: &str = "REFERRER-REPORT:";
+
+// 搜索建议键
+pub const SEARCH_SUGGEST_KEY: &str = "SEARCH-SUGGEST:";
+
+// 搜索关键字报表键
+pub const SEARCH_REPORT_KEY: &str = "SEARCH-REPORT:";
+
 // 保存或编辑博客键
 pub const SAVE_BLOG_MAP: &str = "SAVE-BLOG-MAP";
 
-// 用户令牌键
-pub const USER_TOKEN_KEY: &str = "USER-TOKEN:";
+// 有效登录会话键，值为会话所属的用户 ID
+pub const USER_SESSION_KEY: &str = "USER-SESSION:";
 
 // 主题映射键（注意：与 TOPIC_MAP_KEY 重复）
 pub const TAG_MAP_KEY: &str = "TOPIC-MAP";
@@ -57,6 +95,9 @@ pub const BLOG_LIST_PAGE_INFO_EXPIRE: usize = HOUR * 6;
 // 用户信息键的过期时间（30分钟）
 pub const USER_INFO_KEY_EXPIRE: usize = MIN * 30;
 
+// 有效登录会话键的过期时间（10分钟），过期后重新从数据库确认会话状态
+pub const USER_SESSION_KEY_EXPIRE: usize = MIN * 10;
+
 // 热门博客键的过期时间（30分钟）
 pub const HOT_BLOG_KEY_EXPIRE: usize = MIN * 30;
 
@@ -66,5 +107,38 @@ pub const LATEST_BLOG_KEY_EXPIRE: usize = HOUR * 3;
 // 邮件验证码键的过期时间（1分钟）
 pub const EMAIL_CODE_KEY_EXPIRE: usize = MIN * 1;
 
+// 重置密码邮件验证码键的过期时间（10分钟）
+pub const PASSWORD_RESET_CODE_EXPIRE: usize = MIN * 10;
+
+// 修改邮箱验证码键的过期时间（10分钟）
+pub const EMAIL_CHANGE_CODE_EXPIRE: usize = MIN * 10;
+
+// 邮件验证码错误次数键的过期时间（1小时），期间重新发送验证码也不会重置
+pub const EMAIL_CODE_ATTEMPT_EXPIRE: usize = HOUR;
+
+// 邮件验证码发送频率限制键的过期时间（1分钟）
+pub const EMAIL_CODE_SEND_EXPIRE: usize = MIN * 1;
+
 // 第一页主题键的过期时间（8小时）
 pub const FIRST_PAGE_TOPIC_EXPIRE: usize = HOUR * 8;
+
+// 邮件订阅确认邮件发送限制键的过期时间（1分钟）
+pub const NEWSLETTER_SUBSCRIBE_EXPIRE: usize = MIN * 1;
+
+// 同一 IP 订阅限流键的过期时间（1小时）
+pub const NEWSLETTER_SUBSCRIBE_IP_EXPIRE: usize = HOUR;
+
+// Webmention 限流键的过期时间（1分钟）
+pub const WEBMENTION_RATE_EXPIRE: usize = MIN * 1;
+
+// 后台统计面板键的过期时间（10分钟）
+pub const DASHBOARD_INFO_EXPIRE: usize = MIN * 10;
+
+// 上传文件存储空间统计键的过期时间（1小时）
+pub const STORAGE_INFO_EXPIRE: usize = HOUR;
+
+// 搜索建议键的过期时间（5分钟）
+pub const SEARCH_SUGGEST_EXPIRE: usize = MIN * 5;
+
+// 搜索关键字报表键的过期时间（10分钟）
+pub const SEARCH_REPORT_EXPIRE: usize = MIN * 10;
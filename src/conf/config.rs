@@ -5,8 +5,9 @@ use serde::Deserialize;
 
 use crate::conf::db_config::DbConfig;
 use crate::conf::logger_config::LoggerConfig;
+use crate::conf::password_config::PasswordConfig;
 use crate::conf::redis_config::RedisConfig;
-use crate::conf::search_config::MeiliSearchConfig;
+use crate::conf::search_config::{MeiliSearchConfig, SearchConfig};
 use crate::conf::smtp_config::SmtpConfig;
 use crate::conf::token_config::TokenConfig;
 use crate::conf::upload_config::UploadConfig;
@@ -24,7 +25,11 @@ pub struct Config {
     pub blog_search_index:String,
     pub db: DbConfig,
     pub meilisearch: MeiliSearchConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
     pub token: TokenConfig,
+    #[serde(default)]
+    pub password: PasswordConfig,
     pub smtp: SmtpConfig,
     pub redis: RedisConfig,
     pub logger: LoggerConfig,
@@ -34,7 +39,193 @@ pub struct Config {
     pub upload: UploadConfig,
     pub gpt: GptToken,
     pub server: ServerConfig,
-    pub origin:OriginConfig
+    pub origin:OriginConfig,
+    #[serde(default)]
+    pub view: ViewConfig,
+    #[serde(default)]
+    pub webmention: WebmentionConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    #[serde(default)]
+    pub newsletter: NewsletterConfig,
+    #[serde(default)]
+    pub email_template: EmailTemplateConfig,
+    #[serde(default)]
+    pub email_queue: EmailQueueConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ViewConfig {
+    // 同一访客（IP + User-Agent）重复浏览同一篇博客的去重时间 单位分钟
+    pub dedup_window: usize,
+    // 爬虫 User-Agent 关键字，逗号分隔，不区分大小写
+    pub crawler_agents: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct WebmentionConfig {
+    // 博客页面地址模板，{id} 会被替换为博客id
+    pub blog_url: String,
+    // 抓取页面和发送通知的超时时间 单位秒
+    pub timeout: u64,
+    // 抓取页面内容的最大字节数
+    pub max_body_size: usize,
+    // 发布博客时最多通知的外部链接数量
+    pub max_links: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    // 请求超时时间 单位秒
+    pub timeout: u64,
+    // 最多尝试投递的次数，超过后标记为失败
+    pub max_attempts: i32,
+    // 第一次重试的间隔，之后每次翻倍 单位秒
+    pub retry_interval: i64,
+    // 后台检查待重试投递的间隔 单位秒
+    pub check_interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct NewsletterConfig {
+    // 确认订阅和退订链接的接口地址前缀，例如 https://blog.shuyuz.com/api/v1/newsletter
+    pub api_url: String,
+    // 每批发送的邮件数量
+    pub batch_size: i64,
+    // 每批之间的间隔 单位秒
+    pub batch_interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct EmailTemplateConfig {
+    // 覆盖内置模板的目录，文件为 名称.subject、名称.html、名称.txt，可以放在语言子目录下
+    pub dir: String,
+    // 没有指定语言时使用的语言
+    pub default_locale: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct EmailQueueConfig {
+    // 发送方式：smtp 或 file，file 会把邮件写成 .eml 文件保存到 file_dir
+    pub transport: String,
+    pub file_dir: String,
+    // 最多尝试发送的次数，超过后进入死信
+    pub max_attempts: i32,
+    // 第一次重试的间隔，之后每次翻倍 单位秒
+    pub retry_interval: i64,
+    // 后台检查待发送邮件的间隔 单位秒
+    pub check_interval: u64,
+    // 每次取出发送的邮件数量
+    pub batch_size: i64,
+}
+
+// 旧的配置文件没有 view 配置时使用下面的默认值
+impl Default for ViewConfig {
+    fn default() -> Self {
+        return ViewConfig {
+            dedup_window: 30,
+            crawler_agents: String::from(
+                "bot,spider,crawler,slurp,curl,wget,python-requests,headless,facebookexternalhit,bingpreview",
+            ),
+        };
+    }
+}
+
+// 旧的配置文件没有 webmention 配置时使用下面的默认值
+impl Default for WebmentionConfig {
+    fn default() -> Self {
+        return WebmentionConfig {
+            blog_url: String::from("https://blog.shuyuz.com/blog/{id}"),
+            timeout: 10,
+            max_body_size: 1024 * 1024,
+            max_links: 20,
+        };
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        return WebhookConfig {
+            timeout: 10,
+            max_attempts: 6,
+            retry_interval: 30,
+            check_interval: 30,
+        };
+    }
+}
+
+impl Default for NewsletterConfig {
+    fn default() -> Self {
+        return NewsletterConfig {
+            api_url: String::from("https://blog.shuyuz.com/api/v1/newsletter"),
+            batch_size: 20,
+            batch_interval: 5,
+        };
+    }
+}
+
+// 旧的配置文件没有 email_template 配置时只使用内置模板
+impl Default for EmailTemplateConfig {
+    fn default() -> Self {
+        return EmailTemplateConfig {
+            dir: String::from("email_templates"),
+            default_locale: String::from("zh-CN"),
+        };
+    }
+}
+
+// 旧的配置文件没有 email_queue 配置时使用 SMTP 发送
+impl Default for EmailQueueConfig {
+    fn default() -> Self {
+        return EmailQueueConfig {
+            transport: String::from("smtp"),
+            file_dir: String::from("mail_outbox"),
+            max_attempts: 6,
+            retry_interval: 60,
+            check_interval: 10,
+            batch_size: 20,
+        };
+    }
+}
+
+impl WebmentionConfig {
+    pub fn get_blog_url(&self, id: i64) -> String {
+        return self.blog_url.replace("{id}", &id.to_string());
+    }
+
+    // 从博客页面地址中解析出博客id，忽略协议、查询参数、锚点和末尾的 /
+    pub fn parse_blog_id(&self, url: &str) -> Option<i64> {
+        let strip = |s: &str| -> String {
+            let s = s.split(|c| c == '#' || c == '?').next().unwrap_or("");
+            let s = s.split_once("://").map(|(_, r)| r).unwrap_or(s);
+            return s.trim_end_matches('/').to_lowercase();
+        };
+        let template = strip(&self.blog_url);
+        let url = strip(url);
+        let (prefix, suffix) = template.split_once("{id}")?;
+        let id = url.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        return id.parse::<i64>().ok().filter(|id| *id > 0);
+    }
+}
+
+impl ViewConfig {
+    pub fn is_crawler(&self, user_agent: &str) -> bool {
+        let user_agent = user_agent.to_lowercase();
+        if user_agent.trim().is_empty() {
+            return true;
+        }
+        return self
+            .crawler_agents
+            .split(",")
+            .map(|s| s.trim().to_lowercase())
+            .any(|s| !s.is_empty() && user_agent.contains(&s));
+    }
 }
 
 #[derive(Debug, Deserialize)]
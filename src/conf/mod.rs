@@ -1,6 +1,7 @@
 pub mod config;
 mod db_config;
-mod token_config;
+pub mod password_config;
+pub mod token_config;
 
 pub mod redis_config;
 
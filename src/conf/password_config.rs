@@ -0,0 +1,172 @@
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::SaltString;
+use argon2::password_hash::rand_core::OsRng;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::error::custom_error::{E, Status};
+
+// 密码哈希使用 Argon2id，修改参数后旧参数的哈希会在用户下次登录时重新计算
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordConfig {
+    // 内存开销 单位 KiB
+    pub memory_cost: u32,
+    // 迭代次数
+    pub time_cost: u32,
+    // 并行度
+    pub parallelism: u32,
+}
+
+// OWASP 推荐的 Argon2id 参数
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        return PasswordConfig {
+            memory_cost: 19456,
+            time_cost: 2,
+            parallelism: 1,
+        };
+    }
+}
+
+// 密码校验结果
+#[derive(Debug, PartialEq)]
+pub enum PasswordVerify {
+    // 密码错误
+    Invalid,
+    // 密码正确，哈希使用的是当前参数
+    Valid,
+    // 密码正确，但哈希是旧的 MD5 或者参数和当前配置不同，需要重新计算
+    NeedsRehash,
+}
+
+impl PasswordConfig {
+    fn argon2(&self) -> Result<Argon2<'static>, E> {
+        let params = Params::new(self.memory_cost, self.time_cost, self.parallelism, None)
+            .map_err(|e| {
+                error!("Argon2 参数错误: {}", e);
+                E::error(Status::SERVER_ERROR, String::from("密码加密失败"))
+            })?;
+        return Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params));
+    }
+
+    // 计算密码的 PHC 格式哈希，盐值随机生成
+    pub fn hash(&self, password: &str) -> Result<String, E> {
+        let salt = SaltString::generate(&mut OsRng);
+        return self
+            .argon2()?
+            .hash_password(password.as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(|e| {
+                error!("密码加密失败: {}", e);
+                E::error(Status::SERVER_ERROR, String::from("密码加密失败"))
+            });
+    }
+
+    // 校验密码，兼容旧的无盐 MD5 哈希
+    pub fn verify(&self, password: &str, hash: &str) -> PasswordVerify {
+        if is_legacy_hash(hash) {
+            let md5_password = format!("{:x}", md5::compute(password));
+            return if md5_password == hash.to_lowercase() {
+                PasswordVerify::NeedsRehash
+            } else {
+                PasswordVerify::Invalid
+            };
+        }
+
+        let parsed = match PasswordHash::new(hash) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                error!("密码哈希格式错误: {}", e);
+                return PasswordVerify::Invalid;
+            }
+        };
+
+        // 校验使用哈希中记录的参数，和当前配置无关
+        if Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_err()
+        {
+            return PasswordVerify::Invalid;
+        }
+
+        return if hash.starts_with(&self.hash_prefix()) {
+            PasswordVerify::Valid
+        } else {
+            PasswordVerify::NeedsRehash
+        };
+    }
+
+    // 使用当前参数计算的哈希都以这个前缀开头
+    pub fn hash_prefix(&self) -> String {
+        return format!(
+            "$argon2id$v=19$m={},t={},p={}$",
+            self.memory_cost, self.time_cost, self.parallelism
+        );
+    }
+}
+
+// 旧版本保存的是 32 位十六进制的 MD5
+pub fn is_legacy_hash(hash: &str) -> bool {
+    return hash.len() == 32 && hash.chars().all(|c| c.is_ascii_hexdigit());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试使用较小的参数，减少计算时间
+    fn config(time_cost: u32) -> PasswordConfig {
+        return PasswordConfig {
+            memory_cost: 1024,
+            time_cost,
+            parallelism: 1,
+        };
+    }
+
+    #[test]
+    fn hash_and_verify() {
+        let config = config(1);
+        let hash = config.hash("p@ssw0rd").unwrap();
+        assert!(hash.starts_with(&config.hash_prefix()));
+        assert_eq!(config.verify("p@ssw0rd", &hash), PasswordVerify::Valid);
+        assert_eq!(config.verify("wrong", &hash), PasswordVerify::Invalid);
+    }
+
+    #[test]
+    fn changed_params_need_rehash() {
+        let hash = config(1).hash("p@ssw0rd").unwrap();
+        assert_eq!(config(2).verify("p@ssw0rd", &hash), PasswordVerify::NeedsRehash);
+        assert_eq!(config(2).verify("wrong", &hash), PasswordVerify::Invalid);
+    }
+
+    #[test]
+    fn legacy_md5_needs_rehash() {
+        let config = config(1);
+        assert_eq!(
+            config.verify("123456", "e10adc3949ba59abbe56e057f20f883e"),
+            PasswordVerify::NeedsRehash
+        );
+        assert_eq!(
+            config.verify("123456", "E10ADC3949BA59ABBE56E057F20F883E"),
+            PasswordVerify::NeedsRehash
+        );
+        assert_eq!(
+            config.verify("1234567", "e10adc3949ba59abbe56e057f20f883e"),
+            PasswordVerify::Invalid
+        );
+    }
+
+    #[test]
+    fn legacy_hash_detection() {
+        assert!(is_legacy_hash("e10adc3949ba59abbe56e057f20f883e"));
+        assert!(!is_legacy_hash("e10adc3949ba59abbe56e057f20f883"));
+        assert!(!is_legacy_hash("g10adc3949ba59abbe56e057f20f883e"));
+        assert!(!is_legacy_hash(&config(1).hash("123456").unwrap()));
+    }
+
+    #[test]
+    fn invalid_hash_is_rejected() {
+        assert_eq!(config(1).verify("123456", "not a hash"), PasswordVerify::Invalid);
+    }
+}
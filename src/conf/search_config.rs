@@ -1,15 +1,83 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
 
+use crate::search::backend::{FallbackSearch, SearchBackend};
 use crate::search::meilisearch_client::MeiliSearchClient;
+use crate::search::postgres_search::PostgresSearch;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MeiliSearchConfig {
     host: String,
     api_key: String,
+    // 请求超时时间 单位秒
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+    // 等待异步任务完成的最长时间 单位秒
+    #[serde(default = "default_task_timeout")]
+    task_timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    return 5;
+}
+
+fn default_task_timeout() -> u64 {
+    return 120;
 }
 
 impl MeiliSearchConfig {
     pub fn get_search_client(&self) -> MeiliSearchClient {
-        MeiliSearchClient::new(&self.host, &self.api_key)
+        MeiliSearchClient::new(&self.host, &self.api_key, self.timeout, self.task_timeout)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    // 搜索后端 meilisearch 或 postgres
+    pub backend: String,
+    // 使用 meilisearch 时是否同时写入 postgres，meilisearch 请求失败后改用 postgres 搜索
+    pub fallback: bool,
+    // 对比索引和数据库并修复差异的间隔 单位秒，为 0 时不定时对比
+    pub reconcile_interval: u64,
+    // 专题、标签、分类和作者的搜索索引
+    pub topic_index: String,
+    pub tag_index: String,
+    pub category_index: String,
+    pub author_index: String,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        return SearchConfig {
+            backend: String::from("meilisearch"),
+            fallback: true,
+            reconcile_interval: 3600,
+            topic_index: String::from("zsy-topics"),
+            tag_index: String::from("zsy-tags"),
+            category_index: String::from("zsy-categories"),
+            author_index: String::from("zsy-authors"),
+        };
+    }
+}
+
+impl SearchConfig {
+    pub fn get_search_backend(
+        &self,
+        meilisearch: &MeiliSearchConfig,
+        db_pool: Pool<Postgres>,
+    ) -> Arc<dyn SearchBackend> {
+        let postgres = Arc::new(PostgresSearch::new(db_pool));
+        if self.backend == "postgres" {
+            return postgres;
+        }
+        let meilisearch = Arc::new(meilisearch.get_search_client());
+        return if self.fallback {
+            Arc::new(FallbackSearch::new(meilisearch, postgres))
+        } else {
+            meilisearch
+        };
     }
 }
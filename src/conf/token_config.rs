@@ -3,13 +3,20 @@ use jsonwebtoken::{decode, DecodingKey, encode, EncodingKey, Header, Validation}
 use serde::{Deserialize, Serialize};
 use sqlx::types::chrono::Local;
 
-use crate::conf::config::CONFIG;
 
 // 序列化和反序列化 Token 配置
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenConfig {
     pub secret: String,
+    // 刷新令牌和登录会话的有效期，单位天
     pub expire: i64,
+    // 访问令牌的有效期，单位分钟
+    #[serde(default = "default_access_expire")]
+    pub access_expire: i64,
+}
+
+fn default_access_expire() -> i64 {
+    return 15;
 }
 
 // JWT 的声明（Claims）
@@ -17,23 +24,25 @@ pub struct TokenConfig {
 pub struct Claims {
     exp: usize,      // 过期时间
     iat: usize,      // 签发时间
-    iss: i64,        // 签发者
+    pub iss: i64,    // 签发者
     pub sub: String, // 主题（通常是用户名）
+    pub sid: String, // 登录会话 ID
 }
 
 impl TokenConfig {
-    // 创建 JWT Token
-    pub async fn create_token(&self, user_id: i64, username: &String) -> String {
+    // 创建访问令牌，只在较短的时间内有效，过期后使用刷新令牌重新获取
+    pub async fn create_token(&self, user_id: i64, username: &String, session_id: &String) -> String {
         let current_time = Local::now().timestamp();
 
         // 计算过期时间
-        let expiration_time = current_time + Duration::days(CONFIG.token.expire).whole_seconds();
+        let expiration_time = current_time + self.access_expire_seconds();
 
         let claims = Claims {
             exp: expiration_time as usize,
             iat: current_time as usize,
             iss: user_id,
             sub: username.to_owned(),
+            sid: session_id.to_owned(),
         };
 
         // 使用密钥编码 JWT
@@ -47,6 +56,16 @@ impl TokenConfig {
         return token.unwrap();
     }
 
+    // 访问令牌的有效秒数
+    pub fn access_expire_seconds(&self) -> i64 {
+        return Duration::minutes(self.access_expire).whole_seconds();
+    }
+
+    // 刷新令牌的有效秒数
+    pub fn refresh_expire_seconds(&self) -> i64 {
+        return Duration::days(self.expire).whole_seconds();
+    }
+
     // 解析 JWT Token
     pub fn parse_token(&self, token: &String) -> Option<Claims> {
         let result = decode::<Claims>(
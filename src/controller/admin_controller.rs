@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::Read;
@@ -7,11 +8,13 @@ use actix_web::web::{Data, Json, Path, Query};
 use log::info;
 use r2d2_redis::redis::Commands;
 use serde::Deserialize;
+use serde_json::json;
 use sqlx::types::chrono::Local;
 
 use crate::AppState;
 use crate::cache::{
-    clear_category_info_keys, clear_tag_info_key, clear_topic_info_key, clear_user_info,
+    clear_category_info_keys, clear_page_info_keys, clear_tag_info_key, clear_topic_info_key,
+    clear_user_info,
 };
 use crate::common::redis_keys::{BLOG_WEB_CONFIG, LATEST_BLOG_KEY};
 use crate::common::result::R;
@@ -21,11 +24,20 @@ use crate::error::custom_error::{E, Status};
 use crate::middleware::jwt::{JwtAdminRole, JwtSuperAdminRole};
 use crate::models::category::{CategoryVo, OtherAdminVo};
 use crate::models::topic::TopicRequest;
+use crate::models::webhook::{
+    BLOG_DELETED, BLOG_RESTORED, CATEGORY_DELETED, CATEGORY_RESTORED, CATEGORY_UPDATED,
+    TOPIC_DELETED, TOPIC_RESTORED, TOPIC_UPDATED,
+};
 use crate::request::admin_request::{
-    AdminBlogFilter, OtherAdminFilter, UpdateGpt, UpdatePublicRequest, UpdateRole,
+    AdminBlogFilter, ContactFlagRequest, ContactMessageFilter, DashboardRequest, EmailQueueFilter,
+    MergeTagRequest, OtherAdminFilter, ReferrerReportRequest, RenameTagRequest,
+    SearchSettingsRequest, SplitTagRequest, UpdateCategoryRequest, UpdateGpt, UpdatePublicRequest,
+    UpdateRole, WebhookDeliveryFilter, WebhookRequest,
 };
 use crate::response::page_info::PageInfo;
+use crate::response::search_info::SearchInitReport;
 use crate::response::website_info::BlogConfigInfo;
+use crate::search::meilisearch_request::IndexSettings;
 
 // 获取标签列表
 #[get("/blog/current_blogs")]
@@ -164,21 +176,59 @@ pub async fn update_tag(
     }
 }
 
+#[put("/tag/merge")]
+pub async fn merge_tags(
+    _: JwtSuperAdminRole,
+    req: Json<MergeTagRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.tag_service.merge_tags(&req.into_inner()).await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+#[put("/tag/rename")]
+pub async fn rename_tag(
+    _: JwtSuperAdminRole,
+    req: Json<RenameTagRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.tag_service.rename_tag(&req.into_inner()).await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+#[put("/tag/split")]
+pub async fn split_tag(
+    _: JwtSuperAdminRole,
+    req: Json<SplitTagRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.tag_service.split_tag(&req.into_inner()).await?;
+    return Ok(R::success(result).response_to_json());
+}
+
 #[put("/category/update")]
 pub async fn update_category(
     _: JwtSuperAdminRole,
-    category: Json<CategoryVo>,
+    category: Json<UpdateCategoryRequest>,
     service: Data<AppState>,
 ) -> impl Responder {
-    let result = service
-        .admin_service
-        .update_category(category.into_inner())
-        .await;
-    if result > 0 {
-        clear_category_info_keys();
-        return R::success(result).response_to_json();
-    } else {
-        return HttpResponse::Ok().json(E::default());
+    let category = category.into_inner();
+    let (id, name, parent_id) = (category.id, category.name.to_owned(), category.parent_id);
+    let result = service.admin_service.update_category(category).await;
+    match result {
+        Ok(r) if r > 0 => {
+            clear_category_info_keys();
+            if CONFIG.blog_page_cache {
+                clear_page_info_keys()
+            }
+            service.webhook_service.dispatch(
+                CATEGORY_UPDATED,
+                json!({ "id": id, "name": name, "parentId": parent_id }),
+            );
+            R::success(r).response_to_json()
+        }
+        Ok(_) => HttpResponse::Ok().json(E::default()),
+        Err(e) => HttpResponse::Ok().json(e),
     }
 }
 
@@ -188,11 +238,19 @@ pub async fn delete_category_by_id(
     id: Path<i64>,
     service: Data<AppState>,
 ) -> impl Responder {
-    let result = service
+    let ids = vec![id.into_inner()];
+    let (result, category_ids, blog_ids) = service
         .admin_service
-        .delete_category_ids(&vec![id.into_inner()], true)
+        .delete_category_ids(&ids, true)
         .await;
     if result > 0 {
+        dispatch_cascade(
+            &service,
+            CATEGORY_DELETED,
+            &category_ids,
+            BLOG_DELETED,
+            &blog_ids,
+        );
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -222,11 +280,19 @@ pub async fn batch_delete_category_by_ids(
     ids: Json<Vec<i64>>,
     service: Data<AppState>,
 ) -> impl Responder {
-    let result = service
+    let ids = ids.into_inner();
+    let (result, category_ids, blog_ids) = service
         .admin_service
-        .delete_category_ids(&ids.into_inner(), true)
+        .delete_category_ids(&ids, true)
         .await;
     if result > 0 {
+        dispatch_cascade(
+            &service,
+            CATEGORY_DELETED,
+            &category_ids,
+            BLOG_DELETED,
+            &blog_ids,
+        );
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -256,11 +322,19 @@ pub async fn batch_un_delete_category_by_ids(
     ids: Json<Vec<i64>>,
     service: Data<AppState>,
 ) -> impl Responder {
-    let result = service
+    let ids = ids.into_inner();
+    let (result, category_ids, blog_ids) = service
         .admin_service
-        .delete_category_ids(&ids.into_inner(), false)
+        .delete_category_ids(&ids, false)
         .await;
     if result > 0 {
+        dispatch_cascade(
+            &service,
+            CATEGORY_RESTORED,
+            &category_ids,
+            BLOG_RESTORED,
+            &blog_ids,
+        );
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -290,11 +364,19 @@ pub async fn un_delete_category_by_id(
     id: Path<i64>,
     service: Data<AppState>,
 ) -> impl Responder {
-    let result = service
+    let ids = vec![id.into_inner()];
+    let (result, category_ids, blog_ids) = service
         .admin_service
-        .delete_category_ids(&vec![id.into_inner()], false)
+        .delete_category_ids(&ids, false)
         .await;
     if result > 0 {
+        dispatch_cascade(
+            &service,
+            CATEGORY_RESTORED,
+            &category_ids,
+            BLOG_RESTORED,
+            &blog_ids,
+        );
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -313,11 +395,15 @@ pub async fn delete_blog_by_id(
         jwt.user.id
     };
 
+    let ids = vec![id.into_inner()];
     let result = service
         .admin_service
-        .delete_blog_ids(vec![id.into_inner()], user_id, true)
+        .delete_blog_ids(ids.clone(), user_id, true)
         .await;
     if result > 0 {
+        service
+            .webhook_service
+            .dispatch(BLOG_DELETED, json!({ "ids": ids }));
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -336,11 +422,15 @@ pub async fn un_delete_blog_by_id(
         jwt.user.id
     };
 
+    let ids = vec![id.into_inner()];
     let result = service
         .admin_service
-        .delete_blog_ids(vec![id.into_inner()], user_id, false)
+        .delete_blog_ids(ids.clone(), user_id, false)
         .await;
     if result > 0 {
+        service
+            .webhook_service
+            .dispatch(BLOG_RESTORED, json!({ "ids": ids }));
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -359,11 +449,15 @@ pub async fn batch_delete_blog_by_ids(
         jwt.user.id
     };
 
+    let ids = ids.into_inner();
     let result = service
         .admin_service
-        .delete_blog_ids(ids.into_inner(), user_id, true)
+        .delete_blog_ids(ids.clone(), user_id, true)
         .await;
     if result > 0 {
+        service
+            .webhook_service
+            .dispatch(BLOG_DELETED, json!({ "ids": ids }));
         R::success(result).response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -382,11 +476,15 @@ pub async fn batch_un_delete_blog_by_ids(
         jwt.user.id
     };
 
+    let ids = ids.into_inner();
     let result = service
         .admin_service
-        .delete_blog_ids(ids.into_inner(), user_id, false)
+        .delete_blog_ids(ids.clone(), user_id, false)
         .await;
     if result > 0 {
+        service
+            .webhook_service
+            .dispatch(BLOG_RESTORED, json!({ "ids": ids }));
         R::success("result").response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -404,12 +502,12 @@ pub async fn update_topic(
     } else {
         jwt.user.id
     };
-    let result = service
-        .admin_service
-        .update_topic(req.into_inner(), user_id)
-        .await;
+    let req = req.into_inner();
+    let data = json!(&req);
+    let result = service.admin_service.update_topic(req, user_id).await;
     if result > 0 {
         clear_topic_info_key();
+        service.webhook_service.dispatch(TOPIC_UPDATED, data);
         R::success("result").response_to_json()
     } else {
         HttpResponse::Ok().json(E::default())
@@ -491,6 +589,16 @@ pub async fn set_web_site_info(
     R::success("更新网站配置成功").response_to_json()
 }
 
+// 密码哈希迁移进度，统计还在使用旧 MD5 哈希的账号
+#[get("/user/password/report")]
+pub async fn get_password_hash_report(
+    _: JwtSuperAdminRole,
+    service: Data<AppState>,
+) -> impl Responder {
+    let result = service.user_service.get_password_hash_report().await;
+    return R::success(result).response_to_json();
+}
+
 #[put("/update_role")]
 pub async fn update_role(
     _: JwtSuperAdminRole,
@@ -575,10 +683,14 @@ pub async fn delete_topic_by_id(
     } else {
         jwt.user.id
     };
-    let result = service
+    let ids = vec![id.into_inner()];
+    let (result, blog_ids) = service
         .admin_service
-        .delete_topics_ids(&vec![id.into_inner()], true, user_id)
+        .delete_topics_ids(&ids, true, user_id)
         .await;
+    if result > 0 {
+        dispatch_cascade(&service, TOPIC_DELETED, &ids, BLOG_DELETED, &blog_ids);
+    }
     return R::success(result).response_to_json();
 }
 
@@ -593,10 +705,14 @@ pub async fn delete_topic_by_ids(
     } else {
         jwt.user.id
     };
-    let result = service
+    let ids = ids.into_inner();
+    let (result, blog_ids) = service
         .admin_service
-        .delete_topics_ids(&ids.into_inner(), true, user_id)
+        .delete_topics_ids(&ids, true, user_id)
         .await;
+    if result > 0 {
+        dispatch_cascade(&service, TOPIC_DELETED, &ids, BLOG_DELETED, &blog_ids);
+    }
     return R::success(result).response_to_json();
 }
 
@@ -611,10 +727,14 @@ pub async fn un_delete_topic_by_id(
     } else {
         jwt.user.id
     };
-    let result = service
+    let ids = vec![id.into_inner()];
+    let (result, blog_ids) = service
         .admin_service
-        .delete_topics_ids(&vec![id.into_inner()], false, user_id)
+        .delete_topics_ids(&ids, false, user_id)
         .await;
+    if result > 0 {
+        dispatch_cascade(&service, TOPIC_RESTORED, &ids, BLOG_RESTORED, &blog_ids);
+    }
     return R::success(result).response_to_json();
 }
 
@@ -629,26 +749,417 @@ pub async fn un_delete_topic_by_ids(
     } else {
         jwt.user.id
     };
-    let result = service
+    let ids = ids.into_inner();
+    let (result, blog_ids) = service
         .admin_service
-        .delete_topics_ids(&ids.into_inner(), false, user_id)
+        .delete_topics_ids(&ids, false, user_id)
         .await;
+    if result > 0 {
+        dispatch_cascade(&service, TOPIC_RESTORED, &ids, BLOG_RESTORED, &blog_ids);
+    }
     return R::success(result).response_to_json();
 }
 
+// 删除或恢复分类、专题时会连带子分类和博客，受影响的记录都发送对应的事件
+fn dispatch_cascade(
+    service: &AppState,
+    event: &'static str,
+    ids: &Vec<i64>,
+    blog_event: &'static str,
+    blog_ids: &Vec<i64>,
+) {
+    service.webhook_service.dispatch(event, json!({ "ids": ids }));
+    if !blog_ids.is_empty() {
+        service
+            .webhook_service
+            .dispatch(blog_event, json!({ "ids": blog_ids }));
+    }
+}
+
 #[get("/init_search")]
-pub async fn init_search_blog(_: JwtSuperAdminRole, service: Data<AppState>) -> impl Responder {
-    let blogs = service.blog_service.get_all_simple_blog().await;
+pub async fn init_search_blog(
+    _: JwtSuperAdminRole,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let index = &CONFIG.blog_search_index;
+    let blogs = service.blog_service.get_search_blogs(None).await;
     let json_value = serde_json::to_value(&blogs).unwrap();
-    let _ = service
+    // 确保索引存在并且声明了过滤和排序使用的属性，每一步都等待任务完成，任务失败时返回错误
+    let task = service.search_client.create_index(index).await?;
+    service.search_service.wait_for_task(task).await?;
+    let task = service.search_client.delete_all_documents(index).await?;
+    service.search_service.wait_for_task(task).await?;
+    let task = service
         .search_client
-        .delete_all_documents(&CONFIG.blog_search_index)
-        .await;
-    let _ = service
+        .save_documents(index, json_value)
+        .await?;
+    service.search_service.wait_for_task(task).await?;
+    let entities = service.search_service.init_entities().await?;
+    info!(
+        "初始化搜索索引成功 index:{} 文档:{} 其他文档:{}",
+        index,
+        blogs.len(),
+        entities
+    );
+    return Ok(R::success(SearchInitReport {
+        documents: blogs.len(),
+        entities,
+        task_uid: task,
+    })
+    .response_to_json());
+}
+
+// 对比搜索索引和数据库，修复缺少、过期和多余的文档
+#[post("/search/reconcile")]
+pub async fn reconcile_search(
+    _: JwtSuperAdminRole,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.search_service.reconcile().await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+// 查询搜索索引异步任务的状态
+#[get("/search/task/{uid}")]
+pub async fn get_search_task(
+    _: JwtSuperAdminRole,
+    uid: Path<u64>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.search_client.get_task(uid.into_inner()).await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+// 获取搜索索引设置
+#[get("/search/settings")]
+pub async fn get_search_settings(
+    _: JwtSuperAdminRole,
+    req: Query<SearchSettingsRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.search_client.get_settings(&req.get_index()).await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+// 更新搜索索引设置，只修改请求中包含的设置项
+#[put("/search/settings")]
+pub async fn update_search_settings(
+    _: JwtSuperAdminRole,
+    req: Query<SearchSettingsRequest>,
+    settings: Json<IndexSettings>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let index = req.get_index();
+    // 设置在后台生效，返回任务 uid
+    let task = service
+        .search_client
+        .update_settings(&index, &settings.into_inner())
+        .await?;
+    info!("更新搜索索引设置 index:{} task:{:?}", index, task);
+    return Ok(R::success(task).response_to_json());
+}
+
+// 恢复默认的索引设置
+#[put("/search/settings/default")]
+pub async fn reset_search_settings(
+    _: JwtSuperAdminRole,
+    req: Query<SearchSettingsRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let index = req.get_index();
+    let task = service
+        .search_client
+        .update_settings(&index, &IndexSettings::default_for(&index))
+        .await?;
+    info!("恢复默认搜索索引设置 index:{} task:{:?}", index, task);
+    return Ok(R::success(task).response_to_json());
+}
+
+#[get("/search/synonyms")]
+pub async fn get_search_synonyms(
+    _: JwtSuperAdminRole,
+    req: Query<SearchSettingsRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.search_client.get_settings(&req.get_index()).await?;
+    return Ok(R::success(result.synonyms.unwrap_or_default()).response_to_json());
+}
+
+// 替换所有同义词，例如 {"js": ["javascript"]}
+#[put("/search/synonyms")]
+pub async fn update_search_synonyms(
+    _: JwtSuperAdminRole,
+    req: Query<SearchSettingsRequest>,
+    synonyms: Json<HashMap<String, Vec<String>>>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let settings = IndexSettings {
+        synonyms: Some(synonyms.into_inner()),
+        ..Default::default()
+    };
+    let task = service
         .search_client
-        .save_documents(&CONFIG.blog_search_index, json_value)
+        .update_settings(&req.get_index(), &settings)
+        .await?;
+    return Ok(R::success(task).response_to_json());
+}
+
+// 搜索关键字报表：热门关键字、无结果的关键字和每日搜索趋势
+#[get("/search/report")]
+pub async fn get_search_report(
+    _: JwtSuperAdminRole,
+    req: Query<DashboardRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service
+        .search_service
+        .get_query_report(&req.into_inner())
+        .await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+// 统计面板：热门博客、每日访问、地区/平台分布、月度发文和存储占用
+#[get("/dashboard")]
+pub async fn get_dashboard(
+    _: JwtSuperAdminRole,
+    req: Query<DashboardRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service
+        .dashboard_service
+        .get_dashboard(&req.into_inner())
+        .await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+// 访问来源报表：来源域名和 utm 推广参数，传 blogId 时只统计该博客
+#[get("/referrers")]
+pub async fn get_referrer_report(
+    _: JwtSuperAdminRole,
+    req: Query<ReferrerReportRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service
+        .dashboard_service
+        .get_referrer_report(&req.into_inner())
+        .await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+#[get("/webhook/list")]
+pub async fn get_webhook_list(_: JwtSuperAdminRole, service: Data<AppState>) -> HttpResponse {
+    let result = service.webhook_service.get_webhook_list().await;
+    return R::success(result).response_to_json();
+}
+
+#[post("/webhook/add")]
+pub async fn add_webhook(
+    _: JwtSuperAdminRole,
+    req: Json<WebhookRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service.webhook_service.add_webhook(req.into_inner()).await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+#[put("/webhook/update")]
+pub async fn update_webhook(
+    _: JwtSuperAdminRole,
+    req: Json<WebhookRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = service
+        .webhook_service
+        .update_webhook(req.into_inner())
+        .await?;
+    return Ok(R::success(result).response_to_json());
+}
+
+#[delete("/webhook/delete/{id}")]
+pub async fn delete_webhook(
+    _: JwtSuperAdminRole,
+    id: Path<i64>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .webhook_service
+        .delete_webhook(id.into_inner())
+        .await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+// 向 Webhook 发送一个 webhook.ping 测试事件
+#[post("/webhook/ping/{id}")]
+pub async fn ping_webhook(
+    _: JwtSuperAdminRole,
+    id: Path<i64>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service.webhook_service.ping_webhook(id.into_inner()).await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+// Webhook 投递记录，可以按 Webhook 和投递状态筛选
+#[get("/webhook/deliveries")]
+pub async fn get_webhook_deliveries(
+    _: JwtSuperAdminRole,
+    req: Query<WebhookDeliveryFilter>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .webhook_service
+        .get_delivery_list(&req.into_inner())
+        .await;
+    return R::success(result).response_to_json();
+}
+
+#[put("/webhook/retry/{id}")]
+pub async fn retry_webhook_delivery(
+    _: JwtSuperAdminRole,
+    id: Path<i64>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .webhook_service
+        .retry_delivery(id.into_inner())
+        .await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+// 联系留言列表，可以按关键字、已读和归档状态筛选
+#[get("/contact/list")]
+pub async fn get_contact_messages(
+    _: JwtSuperAdminRole,
+    req: Query<ContactMessageFilter>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .contact_service
+        .get_message_list(&req.into_inner())
+        .await;
+    return R::success(result).response_to_json();
+}
+
+#[put("/contact/read")]
+pub async fn update_contact_read(
+    _: JwtSuperAdminRole,
+    req: Json<ContactFlagRequest>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let req = req.into_inner();
+    let result = service.contact_service.update_read(&req.ids, req.flag).await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+#[put("/contact/archive")]
+pub async fn update_contact_archived(
+    _: JwtSuperAdminRole,
+    req: Json<ContactFlagRequest>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let req = req.into_inner();
+    let result = service
+        .contact_service
+        .update_archived(&req.ids, req.flag)
+        .await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+#[delete("/contact/delete/{id}")]
+pub async fn delete_contact_message(
+    _: JwtSuperAdminRole,
+    id: Path<i64>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .contact_service
+        .delete_messages(&vec![id.into_inner()])
+        .await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+#[put("/contact/deletes")]
+pub async fn batch_delete_contact_messages(
+    _: JwtSuperAdminRole,
+    ids: Json<Vec<i64>>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .contact_service
+        .delete_messages(&ids.into_inner())
+        .await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+// 邮件发送队列，可以按状态筛选，status 为 dead 时是多次发送失败的邮件
+#[get("/email/list")]
+pub async fn get_email_queue(
+    _: JwtSuperAdminRole,
+    req: Query<EmailQueueFilter>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service
+        .email_queue_service
+        .get_email_list(&req.into_inner())
         .await;
-    return R::success("初始化搜索成功").response_to_json();
+    return R::success(result).response_to_json();
+}
+
+// 重新发送死信邮件
+#[put("/email/retry/{id}")]
+pub async fn retry_email(
+    _: JwtSuperAdminRole,
+    id: Path<i64>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service.email_queue_service.retry_email(id.into_inner()).await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
+}
+
+#[delete("/email/delete/{id}")]
+pub async fn delete_email(
+    _: JwtSuperAdminRole,
+    id: Path<i64>,
+    service: Data<AppState>,
+) -> HttpResponse {
+    let result = service.email_queue_service.delete_email(id.into_inner()).await;
+    if result > 0 {
+        R::success(result).response_to_json()
+    } else {
+        HttpResponse::Ok().json(E::default())
+    }
 }
 
 #[get("/init_latest")]
@@ -2,17 +2,19 @@ use actix_web::{get, HttpRequest, HttpResponse, post, Responder};
 use actix_web::web::{Data, Json, Path, Query};
 use log::info;
 use serde::Deserialize;
+use serde_json::json;
 
 use crate::AppState;
+use crate::common::{get_ip_address, get_user_agent};
 use crate::common::result::R;
 use crate::conf::config::CONFIG;
 use crate::error::custom_error::{E, Status};
 use crate::middleware::jwt::JwtAdminRole;
-use crate::models::blogs::SearchBlogVo;
+use crate::models::webhook::{BLOG_CREATED, BLOG_UPDATED};
 use crate::request::blog_request::{
     ArchiveRangeRequest, BlogFindRequest, BlogRequest, GetUserBlogRequest, SearchQueryRequest,
+    SuggestRequest, ViewRangeRequest, ViewSource, ViewSourceQuery,
 };
-use crate::response::page_info::PageInfo;
 use crate::search::meillsearch_response::Hits;
 
 // 获取按类别列出博客列表
@@ -81,16 +83,21 @@ pub async fn save_blog(
         Ok(id) => {
             // 记录成功日志
             info!("用户添加博客成功, 用户名: {}, 角色: {}",jwt.user.username, jwt.user.role);
-            let search_request = SearchBlogVo {
-                id,
-                title: req.title.to_owned(),
-                description: req.description.to_owned(),
-            };
-            let json_value = serde_json::to_value(&search_request).unwrap();
+            state.search_service.sync_blogs(vec![id]);
+            // 通知博客内容中链接到的外部页面
             state
-                .search_client
-                .save_documents(&CONFIG.blog_search_index, json_value)
-                .await;
+                .webmention_service
+                .send_on_publish(id, req.content.to_owned());
+            state.newsletter_service.notify_on_publish(id);
+            state.webhook_service.dispatch(
+                BLOG_CREATED,
+                json!({
+                    "id": id,
+                    "title": req.title,
+                    "description": req.description,
+                    "userId": jwt.user.id,
+                }),
+            );
             Ok(R::success("添加成功").response_to_json())
         }
     }
@@ -138,7 +145,15 @@ pub async fn update_blog(
 
     let mut blog_request = req.into_inner();
 
-    blog_request.id = Option::from(b_id.into_inner());
+    let id = b_id.into_inner();
+
+    blog_request.id = Option::from(id);
+
+    let data = json!({
+        "id": id,
+        "title": blog_request.title,
+        "description": blog_request.description,
+    });
 
     let result = state.blog_service.update_blog(blog_request, u_id).await;
 
@@ -157,6 +172,8 @@ pub async fn update_blog(
                 "用户修改博客成功, 用户ID: {}, 用户名: {}",
                 jwt.user.id, jwt.user.username
             );
+            state.search_service.sync_blogs(vec![id]);
+            state.webhook_service.dispatch(BLOG_UPDATED, data);
             Ok(R::success("修改成功").response_to_json())
         }
     }
@@ -205,10 +222,27 @@ pub async fn get_blog_info_by_id(
     if result.is_ok() {
         let mut blog = result.unwrap();
 
+        let ip = get_ip_address(&req);
+
+        let user_agent = req
+            .headers()
+            .get("User-Agent")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("");
+
+        // 来源参数解析失败时不影响博客获取
+        let query = Query::<ViewSourceQuery>::from_query(req.query_string())
+            .map(|q| q.into_inner())
+            .unwrap_or_default();
+
+        let referer = req.headers().get("Referer").and_then(|h| h.to_str().ok());
+
+        let source = ViewSource::new(query, referer);
+
         // 调用博客服务增加博客访问量
         blog.eye_count = state
             .blog_service
-            .increase_in_view(blog.eye_count, blog.id)
+            .increase_in_view(blog.eye_count, blog.id, &ip, user_agent, &source)
             .await;
 
         info!("获取博客 博客id:{} title:{}",id,blog.title.to_owned());
@@ -221,6 +255,21 @@ pub async fn get_blog_info_by_id(
     }
 }
 
+// 获取博客在日期范围内每天的浏览量
+#[get("/views/{id}")]
+pub async fn get_blog_daily_views(
+    id: Path<i64>,
+    req: Query<ViewRangeRequest>,
+    state: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = state
+        .blog_service
+        .get_daily_views(id.into_inner(), &req.into_inner())
+        .await?;
+
+    Ok(R::success(result).response_to_json())
+}
+
 // 根据日期范围获取归档博客列表
 #[get("/range")]
 pub async fn get_range_blog_list(
@@ -296,35 +345,44 @@ pub async fn set_save_edit_blog_content(
 // 搜索博客列表
 #[get("/search")]
 pub async fn search_blog_list(
+    http_request: HttpRequest,
     req: Query<SearchQueryRequest>,
     state: Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse, E> {
     let search_request = &req.into_inner();
 
-    // 调用搜索客户端搜索博客列表
-    let result = state
-        .search_client
-        .search_documents(&CONFIG.blog_search_index, search_request)
-        .await;
+    // 调用搜索服务搜索博客列表
+    let page_info = state.search_service.search(search_request).await?;
 
-    let mut page_info: PageInfo<Hits> = PageInfo {
-        page:search_request.page,
-        size: 10,
-        total: 0,
-        data: vec![],
-    };
-
-    if let Some(r) = result {
-        page_info.size = r.limit;
-        page_info.data = r.hits;
-        page_info.total = r.total_hits;
-        info!("搜索博客 关键字: {}",search_request.keyword);
-        // 返回成功响应
-        R::success(page_info).response_to_json()
-    } else {
-        // 返回错误响应
-        R::success(E::default()).response_to_json()
-    }
+    state.search_service.record_query(
+        &search_request.keyword,
+        page_info.total,
+        page_info.page,
+        &get_user_agent(&http_request),
+    );
+    info!("搜索博客 关键字: {}", search_request.keyword);
+    // 返回成功响应
+    Ok(R::success(page_info).response_to_json())
+}
+
+// 全站搜索，按博客、专题、标签、分类和作者分组返回
+#[get("/search/all")]
+pub async fn search_all(
+    req: Query<SuggestRequest>,
+    state: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = state.search_service.search_all(&req.keyword).await?;
+    Ok(R::success(result).response_to_json())
+}
+
+// 搜索建议，返回匹配的博客标题、标签和专题
+#[get("/search/suggest")]
+pub async fn get_search_suggest(
+    req: Query<SuggestRequest>,
+    state: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let result = state.search_service.suggest(&req.keyword).await?;
+    Ok(R::success(result).response_to_json())
 }
 
 // 获取相似的博客列表
@@ -342,7 +400,7 @@ pub async fn get_similar_blog(
         .search_documents(&CONFIG.blog_search_index, &request)
         .await;
 
-    if let Some(r) = result {
+    if let Ok(r) = result {
         // 返回成功响应
         R::success(r.hits).response_to_json()
     } else {
@@ -357,27 +415,20 @@ pub async fn get_similar_blog(
 #[get("/init_search")]
 pub async fn init_search_blog(state: Data<AppState>) -> Result<HttpResponse, E> {
     // 调用博客服务获取所有简化的博客数据
-    let simple_blogs = state.blog_service.get_all_simple_blog().await;
+    let simple_blogs = state.blog_service.get_search_blogs(None).await;
 
     let json_value = serde_json::to_value(&simple_blogs).unwrap();
 
-    // 调用搜索客户端保存博客数据到搜索索引
-    let result = state
+    // 调用搜索客户端保存博客数据到搜索索引，并等待索引任务执行完成
+    let task = state
         .search_client
         .save_documents(&CONFIG.blog_search_index, json_value)
-        .await;
+        .await?;
+    state.search_service.wait_for_task(task).await?;
 
-    match result {
-        None => {
-            // 记录成功日志
-            info!("初始化博客搜索索引成功");
-            Ok(R::success("索引初始化成功").response_to_json())
-        }
-        Some(e) => {
-            // 返回错误响应
-            Err(e)
-        }
-    }
+    // 记录成功日志
+    info!("初始化博客搜索索引成功");
+    Ok(R::success("索引初始化成功").response_to_json())
 }
 
 // 创建新的搜索索引
@@ -389,13 +440,8 @@ pub async fn create_search_index(
     let name = &index_name.into_inner();
 
     // 调用搜索客户端创建新的搜索索引
-    let result = state.search_client.create_index(name).await;
+    let task = state.search_client.create_index(name).await?;
+    state.search_service.wait_for_task(task).await?;
 
-    if result.is_none() {
-        // 记录成功日志
-        Ok(R::success(format!("创建搜索索引 {} 成功", name)).response_to_json())
-    } else {
-        // 返回错误响应
-        Err(result.unwrap())
-    }
+    Ok(R::success(format!("创建搜索索引 {} 成功", name)).response_to_json())
 }
@@ -3,13 +3,15 @@ use actix_web::web::{Data, Query};
 use log::{error, info};
 use r2d2_redis::redis::Commands;
 use serde::Deserialize;
+use serde_json::json;
 
 use crate::AppState;
 use crate::common::redis_keys::CATEGORY_LIST_KEY;
 use crate::common::result::R;
 use crate::conf::redis_config::get_pool_connection;
-use crate::error::custom_error::{E, Status};
+use crate::error::custom_error::E;
 use crate::middleware::jwt::JwtAdminRole;
+use crate::models::webhook::CATEGORY_CREATED;
 
 // 添加 log
 
@@ -23,10 +25,10 @@ pub async fn get_category_list_for_db(service: Data<AppState>) -> impl Responder
     return R::success(result).response_to_json();
 }
 
-// 获取分类列表（从缓存）
+// 获取分类树（从缓存）
 #[get("/list2")]
 pub async fn get_category_list_for_cache(service: Data<AppState>) -> impl Responder {
-    // 调用分类服务获取分类列表
+    // 调用分类服务获取分类树
     let result = service.category_service.get_category_for_cache().await;
 
     // 返回成功响应
@@ -37,6 +39,8 @@ pub async fn get_category_list_for_cache(service: Data<AppState>) -> impl Respon
 #[derive(Debug, Deserialize)]
 pub struct AddCategory {
     pub name: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<i64>,
 }
 
 #[post("/add_category")]
@@ -48,13 +52,15 @@ pub async fn add_category(
     // 记录信息日志，表示收到添加分类请求
     info!("收到添加分类请求: {:?}", name);
 
+    let req = name.into_inner();
+
     // 调用分类服务添加新的分类
     let result = service
         .category_service
-        .add_category(&name.into_inner().name)
+        .add_category(&req.name, req.parent_id)
         .await;
 
-    if let Some(r) = result {
+    if let Ok(r) = result {
         get_pool_connection()
             .del::<&str, String>(CATEGORY_LIST_KEY)
             .unwrap_or_default();
@@ -62,6 +68,11 @@ pub async fn add_category(
         // 记录信息日志，表示添加分类成功
         info!("添加分类成功");
 
+        service.webhook_service.dispatch(
+            CATEGORY_CREATED,
+            json!({ "id": r.id, "name": r.name, "parentId": req.parent_id }),
+        );
+
         // 添加分类成功，返回成功响应
         return Ok(R::success(r).response_to_json());
     } else {
@@ -69,6 +80,6 @@ pub async fn add_category(
         error!("添加分类失败");
 
         // 如果添加分类失败，则返回错误响应
-        return Err(E::error(Status::ADD_ERROR, String::from("添加分类失败")));
+        return Err(result.unwrap_err());
     }
 }
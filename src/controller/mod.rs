@@ -10,9 +10,11 @@ pub mod admin_controller;
 pub mod blog_controller;
 pub mod category_controller;
 pub mod file_controller;
+pub mod newsletter_controller;
 pub mod tag_controller;
 pub mod topic_controller;
 pub(crate) mod user_controller;
+pub mod webmention_controller;
 
 struct LogMap(String, Value);
 
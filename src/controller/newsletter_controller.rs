@@ -0,0 +1,80 @@
+use actix_web::{get, HttpRequest, HttpResponse, post};
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Form, Json, Query};
+
+use crate::AppState;
+use crate::common::{escape_html, get_ip_address};
+use crate::common::result::R;
+use crate::error::custom_error::E;
+use crate::request::newsletter_request::{NewsletterTokenRequest, SubscribeRequest};
+
+// 订阅新文章通知，发送确认邮件
+#[post("/subscribe")]
+pub async fn subscribe(
+    http_request: HttpRequest,
+    req: Json<SubscribeRequest>,
+    state: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let ip = get_ip_address(&http_request);
+    state.newsletter_service.subscribe(req.into_inner(), &ip).await?;
+    return Ok(R::success("确认邮件已发送，请查收").response_to_json());
+}
+
+// 确认和退订链接是在邮件里直接打开的，GET 只返回带表单的页面，
+// 用户点击按钮提交后才会修改订阅，避免邮件客户端预取链接时误操作
+#[get("/confirm")]
+pub async fn confirm_page(req: Query<NewsletterTokenRequest>) -> HttpResponse {
+    return form_page("confirm", &req.token, "确认订阅新文章通知？", "确认订阅");
+}
+
+#[post("/confirm")]
+pub async fn confirm_subscribe(
+    req: Form<NewsletterTokenRequest>,
+    state: Data<AppState>,
+) -> HttpResponse {
+    let result = state.newsletter_service.confirm(&req.token).await;
+    return message_page(result.map(|_| String::from("订阅成功，有新文章时会通过邮件通知你")));
+}
+
+#[get("/unsubscribe")]
+pub async fn unsubscribe_page(req: Query<NewsletterTokenRequest>) -> HttpResponse {
+    return form_page("unsubscribe", &req.token, "确认退订新文章通知？", "退订");
+}
+
+#[post("/unsubscribe")]
+pub async fn unsubscribe(
+    req: Form<NewsletterTokenRequest>,
+    state: Data<AppState>,
+) -> HttpResponse {
+    let result = state.newsletter_service.unsubscribe(&req.token).await;
+    return message_page(result.map(|_| String::from("已退订，不会再收到新文章通知")));
+}
+
+fn form_page(action: &str, token: &str, message: &str, button: &str) -> HttpResponse {
+    return html_page(format!(
+        "<p>{}</p><form method=\"post\" action=\"{}\">\
+        <input type=\"hidden\" name=\"token\" value=\"{}\">\
+        <button type=\"submit\">{}</button></form>",
+        escape_html(message),
+        action,
+        escape_html(token),
+        escape_html(button)
+    ));
+}
+
+fn message_page(result: Result<String, E>) -> HttpResponse {
+    let message = match result {
+        Ok(message) => message,
+        Err(e) => e.message,
+    };
+    return html_page(format!("<p>{}</p>", escape_html(&message)));
+}
+
+fn html_page(body: String) -> HttpResponse {
+    return HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"></head><body>{}</body></html>",
+            body
+        ));
+}
@@ -5,10 +5,10 @@ use r2d2_redis::redis::Commands;
 use serde::Deserialize;
 
 use crate::AppState;
+use crate::common::constants::default_page;
 use crate::common::redis_keys::RANDOM_TAG_KEY;
 use crate::common::result::R;
 use crate::conf::redis_config::get_pool_connection;
-use crate::controller::topic_controller::TopicOrTagBlogByPage;
 use crate::error::custom_error::{E, Status};
 use crate::middleware::jwt::JwtAdminRole;
 
@@ -67,16 +67,25 @@ pub async fn get_topic_by_id(t_id: Path<i64>, service: Data<AppState>) -> impl R
     };
 }
 
+// 标签博客分页请求，t_id 或 name 可以是合并前的旧标签
+#[derive(Deserialize, Debug)]
+pub struct TagBlogByPage {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    pub t_id: Option<i64>,
+    pub name: Option<String>,
+}
+
 // 获取特定标签下的博客列表
 #[get("/blogs")]
 pub async fn get_tag_blogs(
-    req: Query<TopicOrTagBlogByPage>,
+    req: Query<TagBlogByPage>,
     service: Data<AppState>,
 ) -> impl Responder {
     let req = req.into_inner();
     let result = service
         .tag_service
-        .get_tag_blog_list(req.page, req.t_id)
+        .get_tag_blog_list(req.page, req.t_id, req.name)
         .await;
     return R::success(result).response_to_json();
 }
@@ -1,6 +1,7 @@
 use actix_web::{get, HttpResponse, post, Responder};
 use actix_web::web::{Data, Json, Path, Query};
 use serde::Deserialize;
+use serde_json::json;
 
 use crate::AppState;
 use crate::common::constants::default_page;
@@ -8,6 +9,7 @@ use crate::common::result::R;
 use crate::error::custom_error::E;
 use crate::middleware::jwt::{JwtAdminRole, JwtSuperAdminRole};
 use crate::models::topic::TopicRequest;
+use crate::models::webhook::TOPIC_CREATED;
 
 #[derive(Deserialize, Debug)]
 pub struct TopicByPage {
@@ -95,13 +97,18 @@ pub async fn add_topic(
     service: Data<AppState>,
     req: Json<TopicRequest>,
 ) -> Result<HttpResponse, E> {
-    let result = service
-        .topic_service
-        .add_topic(jwt.user.id, req.into_inner())
-        .await;
+    let req = req.into_inner();
+    let data = json!({
+        "name": req.name,
+        "desc": req.desc,
+        "cover": req.cover,
+        "userId": jwt.user.id,
+    });
+    let result = service.topic_service.add_topic(jwt.user.id, req).await;
     if let Some(e) = result {
         return Err(e);
     } else {
+        service.webhook_service.dispatch(TOPIC_CREATED, data);
         return Ok(R::success("添加专题成功").response_to_json());
     }
 }
@@ -1,15 +1,19 @@
-use actix_web::{get, http, HttpRequest, HttpResponse, post, Responder};
+use actix_web::{delete, get, http, HttpRequest, HttpResponse, post, put, Responder};
 use actix_web::http::header::ContentEncoding;
-use actix_web::web::{Data, Json, Query};
+use actix_web::web::{Data, Json, Path, Query};
 use log::info;
 use serde::{Deserialize, Serialize};
 
 use crate::AppState;
-use crate::common::{get_client_ip_city, get_ip_address};
+use crate::common::{get_client_ip_city, get_client_platform_info, get_ip_address};
 use crate::common::result::R;
 use crate::error::custom_error::{E, Status};
 use crate::middleware::jwt::{JwtAdminRole, JwtUserRole};
-use crate::request::user_request::{ContactRequest, UserRegisteredRequest, UserRequest};
+use crate::models::session::SessionClient;
+use crate::request::user_request::{
+    ChangeEmailRequest, ChangePasswordRequest, ContactRequest, PasswordResetRequest,
+    ProfileRequest, RefreshTokenRequest, UserRegisteredRequest, UserRequest,
+};
 
 #[derive(Deserialize, Debug)]
 pub struct GetUserQuery {
@@ -33,7 +37,11 @@ pub async fn send_email_for_code(
     service: Data<AppState>,
 ) -> Result<HttpResponse, E> {
     let email = &email_req.into_inner().email;
-    let result = service.user_service.send_mail(&email);
+    let locale = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|h| h.to_str().ok());
+    let result = service.user_service.send_mail(&email, locale).await;
     return match result {
         Some(e) => Err(e),
         None => {
@@ -43,24 +51,54 @@ pub async fn send_email_for_code(
     };
 }
 
+// 重置密码验证码，邮箱是否注册都返回相同的结果
+#[get("/send_reset_mail")]
+pub async fn send_password_reset_mail(
+    req: HttpRequest,
+    email_req: Query<SendEmailRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let email = &email_req.into_inner().email;
+    let locale = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|h| h.to_str().ok());
+    let result = service
+        .user_service
+        .send_password_reset_mail(&email, locale)
+        .await;
+    return match result {
+        Some(e) => Err(e),
+        None => Ok(R::success("如果该邮箱已注册，验证码将发送到该邮箱").response_to_json()),
+    };
+}
+
+#[post("/reset_password")]
+pub async fn reset_password(
+    reset_request: Json<PasswordResetRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    service
+        .user_service
+        .reset_password(&reset_request.into_inner())
+        .await?;
+    Ok(R::success("重置密码成功，请重新登录").response_to_json())
+}
+
 #[post("/contact_me")]
 pub async fn contact_me(
+    req: HttpRequest,
     contact_request: Json<ContactRequest>,
     service: Data<AppState>,
 ) -> Result<HttpResponse, E> {
     let request = contact_request.into_inner();
-    let result = service.user_service.contact_me(&request); // 传递引用
-
-    match result {
-        Some(e) => Err(e),
-        None => {
-            info!(
-                "联系我 信息: email:{} subject:{} message:{}",
-                request.email, request.subject, request.content
-            );
-            Ok(R::success("发送邮件成功").response_to_json())
-        }
-    }
+    let ip = get_ip_address(&req);
+    info!(
+        "联系我 信息: email:{} subject:{} message:{}",
+        request.email, request.subject, request.content
+    );
+    service.contact_service.contact_me(request, &ip).await?;
+    Ok(R::success("发送邮件成功").response_to_json())
 }
 
 #[get("/config")]
@@ -86,11 +124,15 @@ pub async fn registered_user(
 
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     user_request: Json<UserRequest>,
     service: Data<AppState>,
 ) -> Result<HttpResponse, E> {
     let user = &user_request.into_inner();
-    let result = service.user_service.login_user(user).await;
+    let result = service
+        .user_service
+        .login_user(user, get_session_client(&req))
+        .await;
     return if result.is_ok() {
         Ok(R::success(result.unwrap()).response_to_json())
     } else {
@@ -98,12 +140,147 @@ pub async fn login(
     };
 }
 
+// 使用刷新令牌换取新的访问令牌，返回的刷新令牌替换旧的刷新令牌
+#[post("/refresh_token")]
+pub async fn refresh_token(
+    refresh_request: Json<RefreshTokenRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let token = service
+        .session_service
+        .refresh(&refresh_request.refresh_token)
+        .await?;
+    Ok(R::success(token).response_to_json())
+}
+
+// 注销只撤销当前会话，其他设备保持登录
 #[get("/logout")]
 pub async fn logout(jwt: JwtUserRole, service: Data<AppState>) -> impl Responder {
-    let result = service.user_service.logout(&jwt.user.username).await;
+    let result = service
+        .session_service
+        .revoke_session(jwt.user.id, &jwt.session_id)
+        .await
+        .is_ok();
     R::success(result).response_to_json()
 }
 
+#[get("/auth/sessions")]
+pub async fn get_sessions(jwt: JwtUserRole, service: Data<AppState>) -> impl Responder {
+    let sessions = service
+        .session_service
+        .get_sessions(jwt.user.id, &jwt.session_id)
+        .await;
+    R::success(sessions).response_to_json()
+}
+
+#[delete("/auth/sessions/{id}")]
+pub async fn revoke_session(
+    jwt: JwtUserRole,
+    id: Path<String>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    service
+        .session_service
+        .revoke_session(jwt.user.id, &id.into_inner())
+        .await?;
+    Ok(R::success("撤销会话成功").response_to_json())
+}
+
+// 撤销全部会话，包括当前会话
+#[delete("/auth/sessions")]
+pub async fn revoke_all_sessions(jwt: JwtUserRole, service: Data<AppState>) -> impl Responder {
+    let count = service
+        .session_service
+        .revoke_all_sessions(jwt.user.id)
+        .await;
+    info!("用户撤销全部会话 username:{} count:{}", jwt.user.username, count);
+    R::success(count).response_to_json()
+}
+
+// 修改昵称和头像
+#[put("/auth/profile")]
+pub async fn update_profile(
+    jwt: JwtUserRole,
+    profile_request: Json<ProfileRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    service
+        .user_service
+        .update_profile(&jwt.user, &mut profile_request.into_inner())
+        .await?;
+    Ok(R::success("修改个人资料成功").response_to_json())
+}
+
+#[put("/auth/password")]
+pub async fn change_password(
+    jwt: JwtUserRole,
+    password_request: Json<ChangePasswordRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    service
+        .user_service
+        .change_password(&jwt.user, &jwt.session_id, &password_request.into_inner())
+        .await?;
+    Ok(R::success("修改密码成功").response_to_json())
+}
+
+// 发送修改邮箱的验证码到新邮箱
+#[get("/auth/email/send_mail")]
+pub async fn send_email_change_mail(
+    jwt: JwtUserRole,
+    req: HttpRequest,
+    email_req: Query<SendEmailRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let email = &email_req.into_inner().email;
+    let locale = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|h| h.to_str().ok());
+    let result = service
+        .user_service
+        .send_email_change_mail(&jwt.user, &email, locale)
+        .await;
+    return match result {
+        Some(e) => Err(e),
+        None => Ok(R::success("发送邮件成功").response_to_json()),
+    };
+}
+
+#[put("/auth/email")]
+pub async fn change_email(
+    jwt: JwtUserRole,
+    req: HttpRequest,
+    email_request: Json<ChangeEmailRequest>,
+    service: Data<AppState>,
+) -> Result<HttpResponse, E> {
+    let locale = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|h| h.to_str().ok());
+    service
+        .user_service
+        .change_email(&jwt.user, &jwt.session_id, &email_request.into_inner(), locale)
+        .await?;
+    Ok(R::success("修改邮箱成功").response_to_json())
+}
+
+// 登录会话记录的设备、IP 和城市
+fn get_session_client(req: &HttpRequest) -> SessionClient {
+    let ip = get_ip_address(req);
+    let city = get_client_ip_city(&ip);
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+    return SessionClient {
+        device: get_client_platform_info(user_agent),
+        ip,
+        city,
+    };
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ChatQuery {
     message: String,
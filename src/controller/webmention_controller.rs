@@ -0,0 +1,45 @@
+use actix_web::{get, HttpRequest, HttpResponse, post};
+use actix_web::web::{Data, Form, Path};
+use log::info;
+
+use crate::AppState;
+use crate::common::get_ip_address;
+use crate::common::result::R;
+use crate::error::custom_error::{E, Status};
+use crate::models::webmention::WebmentionRequest;
+
+// Webmention 接收端点，按规范出错时返回 400，接收成功后返回 202 并异步验证
+#[post("")]
+pub async fn receive_webmention(
+    http_request: HttpRequest,
+    req: Form<WebmentionRequest>,
+    state: Data<AppState>,
+) -> HttpResponse {
+    let req = req.into_inner();
+
+    let ip = get_ip_address(&http_request);
+
+    if !state.webmention_service.check_rate_limit(&ip) {
+        return HttpResponse::TooManyRequests().json(E::error(
+            Status::QUERY_OR_PARAMS_ERROR,
+            String::from("请求太频繁，请稍后再试"),
+        ));
+    }
+
+    info!("收到 Webmention source:{} target:{}", req.source, req.target);
+
+    return match state.webmention_service.receive(req).await {
+        Ok(id) => HttpResponse::Accepted().json(R::success(id)),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    };
+}
+
+// 获取博客已验证的 Webmention
+#[get("/blog/{id}")]
+pub async fn get_blog_webmentions(id: Path<i64>, state: Data<AppState>) -> HttpResponse {
+    let result = state
+        .webmention_service
+        .get_webmentions(id.into_inner())
+        .await;
+    return R::success(result).response_to_json();
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use log::warn;
+use regex::{Captures, Regex};
+
+use crate::common::escape_html;
+use crate::conf::config::CONFIG;
+use crate::error::custom_error::{E, Status};
+
+// 内置的邮件模板名称
+pub const VERIFICATION_CODE: &str = "verification_code";
+pub const PASSWORD_RESET_CODE: &str = "password_reset_code";
+pub const EMAIL_CHANGED: &str = "email_changed";
+pub const CONTACT_MESSAGE: &str = "contact_message";
+pub const NEWSLETTER_CONFIRM: &str = "newsletter_confirm";
+pub const NEWSLETTER_POST: &str = "newsletter_post";
+
+lazy_static! {
+    // {{{name}}} 原样输出，{{name}} 在 HTML 中转义后输出
+    static ref VARIABLE_REG: Regex =
+        Regex::new(r"\{\{\{\s*(\w+)\s*\}\}\}|\{\{\s*(\w+)\s*\}\}").unwrap();
+}
+
+struct BuiltinTemplate {
+    name: &'static str,
+    subject: &'static str,
+    html: &'static str,
+    text: &'static str,
+}
+
+const BUILTIN_TEMPLATES: [BuiltinTemplate; 6] = [
+    BuiltinTemplate {
+        name: VERIFICATION_CODE,
+        subject: "Yuice 验证码",
+        html: "<p>你的验证码是：</p><h2>{{code}}</h2><p>验证码{{expire}}分钟内有效，请勿泄露给他人。</p>",
+        text: "你的验证码是：{{code}}\n验证码{{expire}}分钟内有效，请勿泄露给他人。",
+    },
+    BuiltinTemplate {
+        name: PASSWORD_RESET_CODE,
+        subject: "Yuice 重置密码",
+        html: "<p>你正在重置密码，验证码是：</p><h2>{{code}}</h2><p>验证码{{expire}}分钟内有效，如果这不是你本人的操作，请忽略这封邮件。</p>",
+        text: "你正在重置密码，验证码是：{{code}}\n验证码{{expire}}分钟内有效，如果这不是你本人的操作，请忽略这封邮件。",
+    },
+    BuiltinTemplate {
+        name: EMAIL_CHANGED,
+        subject: "Yuice 邮箱已修改",
+        html: "<p>你的账号 {{username}} 的邮箱已修改为：{{email}}</p><p>如果这不是你本人的操作，请立即重置密码并联系管理员。</p>",
+        text: "你的账号 {{username}} 的邮箱已修改为：{{email}}\n如果这不是你本人的操作，请立即重置密码并联系管理员。",
+    },
+    BuiltinTemplate {
+        name: CONTACT_MESSAGE,
+        subject: "{{subject}}",
+        html: "<h3>{{subject}}</h3><p>对方名字: {{name}}</p><p>对方邮箱: {{email}}</p>留言内容:<p>{{content}}</p>",
+        text: "{{subject}}\n对方名字: {{name}}\n对方邮箱: {{email}}\n留言内容:\n{{content}}",
+    },
+    BuiltinTemplate {
+        name: NEWSLETTER_CONFIRM,
+        subject: "确认订阅博客更新",
+        html: "<p>你正在订阅博客的新文章通知，请点击下面的链接确认订阅：</p><p><a href=\"{{link}}\">{{link}}</a></p><p>如果这不是你本人的操作，请忽略这封邮件。</p>",
+        text: "你正在订阅博客的新文章通知，请打开下面的链接确认订阅：\n{{link}}\n如果这不是你本人的操作，请忽略这封邮件。",
+    },
+    BuiltinTemplate {
+        name: NEWSLETTER_POST,
+        subject: "新文章：{{title}}",
+        html: "<h3>{{title}}</h3><p>{{description}}</p><p><a href=\"{{link}}\">阅读全文</a></p><hr/><p style=\"font-size:12px;color:#999\">不想再收到通知？<a href=\"{{unsubscribe}}\">退订</a></p>",
+        text: "{{title}}\n\n{{description}}\n\n阅读全文：{{link}}\n\n不想再收到通知？退订：{{unsubscribe}}",
+    },
+];
+
+// 渲染后的邮件
+#[derive(Debug)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+// 渲染邮件模板，每个部分按 目录/语言/名称、目录/名称、内置模板 的顺序查找，
+// 主题和纯文本部分不转义，HTML 部分的变量默认转义
+pub fn render(
+    name: &str,
+    vars: &[(&str, &str)],
+    locale: Option<&str>,
+) -> Result<RenderedEmail, E> {
+    let builtin = BUILTIN_TEMPLATES.iter().find(|t| t.name == name);
+
+    let locale = locale
+        .and_then(normalize_locale)
+        .unwrap_or(CONFIG.email_template.default_locale.to_owned());
+
+    let load = |ext: &str, default: Option<&str>| -> Option<String> {
+        let dir = Path::new(&CONFIG.email_template.dir);
+        for path in [
+            dir.join(&locale).join(format!("{}.{}", name, ext)),
+            dir.join(format!("{}.{}", name, ext)),
+        ] {
+            if let Ok(content) = fs::read_to_string(&path) {
+                return Some(content);
+            }
+        }
+        return default.map(|s| s.to_string());
+    };
+
+    let subject = load("subject", builtin.map(|t| t.subject));
+    let html = load("html", builtin.map(|t| t.html));
+    let text = load("txt", builtin.map(|t| t.text));
+
+    if subject.is_none() || (html.is_none() && text.is_none()) {
+        return Err(E::error(
+            Status::EMAIL_ERROR,
+            format!("邮件模板不存在: {}", name),
+        ));
+    }
+
+    // 只有一种正文时，HTML 由纯文本转义生成，纯文本为空
+    let html = html.unwrap_or_else(|| {
+        format!("<pre>{}</pre>", escape_html(text.as_deref().unwrap_or("")))
+    });
+
+    return Ok(RenderedEmail {
+        subject: fill(subject.unwrap().trim(), vars, false),
+        html: fill(&html, vars, true),
+        text: text.map(|t| fill(&t, vars, false)).unwrap_or_default(),
+    });
+}
+
+// 从 Accept-Language 这类值中取第一个语言，只保留字母、数字和 -，避免拼接路径时越出模板目录
+pub fn normalize_locale(locale: &str) -> Option<String> {
+    let locale = locale.split(|c| c == ',' || c == ';').next()?.trim();
+    if locale.is_empty()
+        || locale.len() > 16
+        || !locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return None;
+    }
+    return Some(locale.to_string());
+}
+
+fn fill(template: &str, vars: &[(&str, &str)], html: bool) -> String {
+    return VARIABLE_REG
+        .replace_all(template, |caps: &Captures| {
+            let (key, raw) = match caps.get(1) {
+                Some(key) => (key.as_str(), true),
+                None => (&caps[2], false),
+            };
+            let value = match vars.iter().find(|(k, _)| *k == key) {
+                Some((_, v)) => *v,
+                None => {
+                    warn!("邮件模板变量不存在: {}", key);
+                    ""
+                }
+            };
+            if html && !raw {
+                escape_html(value)
+            } else {
+                value.to_string()
+            }
+        })
+        .to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_escapes_html_variables() {
+        let vars = [("name", "<script>alert('x')</script> & \"y\"")];
+        assert_eq!(
+            fill("<p>{{ name }}</p>", &vars, true),
+            "<p>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; &quot;y&quot;</p>"
+        );
+    }
+
+    #[test]
+    fn fill_raw_and_text_variables() {
+        let vars = [("link", "https://a.com/?a=1&b=2")];
+        assert_eq!(
+            fill("<a href=\"{{{link}}}\">", &vars, true),
+            "<a href=\"https://a.com/?a=1&b=2\">"
+        );
+        assert_eq!(fill("{{link}}", &vars, false), "https://a.com/?a=1&b=2");
+        assert_eq!(fill("[{{missing}}]", &vars, true), "[]");
+    }
+
+    #[test]
+    fn normalize_locale_rejects_paths() {
+        assert_eq!(normalize_locale("zh-CN,zh;q=0.9"), Some(String::from("zh-CN")));
+        assert_eq!(normalize_locale("../../etc"), None);
+        assert_eq!(normalize_locale(""), None);
+    }
+}
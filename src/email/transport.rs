@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::PathBuf;
+
+use lettre::Message;
+use lettre::message::{MultiPart, SinglePart};
+use sqlx::types::chrono::Local;
+use uuid::Uuid;
+
+use crate::conf::config::CONFIG;
+use crate::email::template::RenderedEmail;
+
+// 邮件发送方式，发送是阻塞的，需要在线程池中调用
+pub trait EmailTransport: Send + Sync {
+    fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), String>;
+}
+
+// 通过配置的 SMTP 服务器发送
+pub struct SmtpEmailTransport;
+
+impl EmailTransport for SmtpEmailTransport {
+    fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), String> {
+        let message = build_message(CONFIG.smtp.get_from(), to, email)?;
+        return CONFIG.smtp.send_message(&message);
+    }
+}
+
+// 把邮件写成 .eml 文件，用于本地开发和测试
+pub struct FileEmailTransport {
+    dir: PathBuf,
+}
+
+impl FileEmailTransport {
+    pub fn new(dir: &str) -> FileEmailTransport {
+        FileEmailTransport {
+            dir: PathBuf::from(dir),
+        }
+    }
+}
+
+impl EmailTransport for FileEmailTransport {
+    fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), String> {
+        let message = build_message(CONFIG.smtp.get_from(), to, email)?;
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let file_name = format!(
+            "{}-{}.eml",
+            Local::now().format("%Y%m%d%H%M%S"),
+            Uuid::new_v4().simple()
+        );
+        return fs::write(self.dir.join(file_name), message.formatted()).map_err(|e| e.to_string());
+    }
+}
+
+// 构建同时包含 HTML 和纯文本两部分的邮件，没有纯文本时只包含 HTML
+pub fn build_message(from: &str, to: &str, email: &RenderedEmail) -> Result<Message, String> {
+    let builder = Message::builder()
+        .from(from.parse().map_err(|e| format!("错误的发件人邮箱 {}: {}", from, e))?)
+        .to(to.parse().map_err(|e| format!("错误的收件人邮箱 {}: {}", to, e))?)
+        .subject(email.subject.to_owned());
+
+    let message = if email.text.is_empty() {
+        builder.singlepart(SinglePart::html(email.html.to_owned()))
+    } else {
+        builder.multipart(MultiPart::alternative_plain_html(
+            email.text.to_owned(),
+            email.html.to_owned(),
+        ))
+    };
+
+    return message.map_err(|e| e.to_string());
+}
@@ -58,9 +58,6 @@ impl Status {
     // 数据为空错误，状态码为 10006
     pub const DATA_EMPTY_ERROR: Code = 10006;
 
-    // 获取热门博客错误，状态码为 10007
-    pub const GET_HOT_BLOG_ERROR: Code = 10007;
-
     // 查询或参数错误，状态码为 10008
     pub const QUERY_OR_PARAMS_ERROR: Code = 10008;
 
@@ -82,8 +79,11 @@ impl Status {
     // 添加失败
     pub const ADD_ERROR: Code = 10014;
 
-    // 删除失败
-    pub const DELETE_ERROR: Code = 10014;
+    // 搜索索引不支持请求中的过滤条件
+    pub const SEARCH_FILTER_ERROR: Code = 10015;
+
+    // 搜索服务连接失败、超时或返回 5xx
+    pub const SEARCH_UNAVAILABLE_ERROR: Code = 10016;
 }
 
 impl E {
@@ -17,16 +17,26 @@ use actix_web::http::header;
 use actix_web::middleware::ErrorHandlers;
 use actix_web::rt::time::{interval, sleep, sleep_until};
 use actix_web::web::{Data, scope};
+use log::{error, info};
 use serde::Serialize;
 use sqlx::{Connection, FromRow, Pool, Postgres, Row};
 use sqlx::types::chrono::{Local, TimeZone, Utc};
 
 use crate::conf::config::CONFIG;
 use crate::conf::logger_config::LoggerParams;
-use crate::search::meilisearch_client::MeiliSearchClient;
+use crate::search::backend::SearchBackend;
 use crate::service::admin_service::AdminService;
 use crate::service::blog_service::BlogService;
 use crate::service::category_service::CategoryService;
+use crate::service::contact_service::ContactService;
+use crate::service::dashboard_service::DashboardService;
+use crate::service::email_queue_service::EmailQueueService;
+use crate::service::newsletter_service::NewsletterService;
+use crate::service::search_service::SearchService;
+use crate::service::session_service::SessionService;
+use crate::service::webhook_service::WebhookService;
+use crate::service::webmention_service::WebmentionService;
+use crate::webmention::fetcher::ReqwestFetcher;
 use crate::service::file_service::FileService;
 use crate::service::gpt_service::GptService;
 use crate::service::tag_service::TagService;
@@ -36,6 +46,7 @@ use crate::service::user_service::UserService;
 mod cache;
 mod common;
 mod conf;
+mod email;
 mod controller;
 mod error;
 mod middleware;
@@ -46,15 +57,24 @@ mod repository;
 mod routers;
 mod search;
 mod service;
+mod webmention;
 pub struct AppState {
     pub user_service: Arc<UserService>,
+    pub session_service: Arc<SessionService>,
     pub blog_service: Arc<BlogService>,
     pub category_service: Arc<CategoryService>,
-    pub search_client: Arc<MeiliSearchClient>,
+    pub search_client: Arc<dyn SearchBackend>,
+    pub search_service: Arc<SearchService>,
     pub tag_service: Arc<TagService>,
     pub topic_service: Arc<TopicService>,
     pub file_service: Arc<FileService>,
     pub admin_service: Arc<AdminService>,
+    pub dashboard_service: Arc<DashboardService>,
+    pub webmention_service: Arc<WebmentionService>,
+    pub webhook_service: Arc<WebhookService>,
+    pub newsletter_service: Arc<NewsletterService>,
+    pub contact_service: Arc<ContactService>,
+    pub email_queue_service: Arc<EmailQueueService>,
     pub chat_service: Arc<Mutex<GptService>>,
 }
 
@@ -111,28 +131,81 @@ async fn main() -> std::io::Result<()> {
 
     let mut connections = Connections::new().await;
 
-    let user_service = Arc::new(UserService::new(connections.db_pool.clone()));
+    let email_queue_service = Arc::new(EmailQueueService::new(connections.db_pool.clone()));
 
     let blog_service = Arc::new(BlogService::new(connections.db_pool.clone()));
 
-    let search_config = CONFIG.meilisearch.get_search_client();
+    let search_client = CONFIG
+        .search
+        .get_search_backend(&CONFIG.meilisearch, connections.db_pool.clone());
 
-    let search_client = Arc::new(search_config);
+    let search_service = Arc::new(SearchService::new(
+        connections.db_pool.clone(),
+        blog_service.clone(),
+        search_client.clone(),
+    ));
 
-    let category_service = Arc::new(CategoryService::new(connections.db_pool.clone()));
+    let session_service = Arc::new(SessionService::new(connections.db_pool.clone()));
 
-    let tag_service = Arc::new(TagService::new(connections.db_pool.clone()));
+    let user_service = Arc::new(UserService::new(
+        connections.db_pool.clone(),
+        email_queue_service.clone(),
+        search_service.clone(),
+        session_service.clone(),
+    ));
 
-    let topic_service = Arc::new(TopicService::new(connections.db_pool.clone()));
+    let category_service = Arc::new(CategoryService::new(
+        connections.db_pool.clone(),
+        search_service.clone(),
+    ));
+
+    let tag_service = Arc::new(TagService::new(
+        connections.db_pool.clone(),
+        search_service.clone(),
+    ));
+
+    let topic_service = Arc::new(TopicService::new(
+        connections.db_pool.clone(),
+        search_service.clone(),
+    ));
 
     let file_service = Arc::new(FileService::new(connections.db_pool.clone()));
 
-    let admin_service = Arc::new(AdminService::new(connections.db_pool.clone()));
+    let admin_service = Arc::new(AdminService::new(
+        connections.db_pool.clone(),
+        search_service.clone(),
+    ));
+
+    let dashboard_service = Arc::new(DashboardService::new(connections.db_pool.clone()));
+
+    let webmention_fetcher = Arc::new(ReqwestFetcher::new(
+        CONFIG.webmention.timeout,
+        CONFIG.webmention.max_body_size,
+    ));
+
+    let webmention_service = Arc::new(WebmentionService::new(
+        connections.db_pool.clone(),
+        webmention_fetcher,
+    ));
+
+    let webhook_service = Arc::new(WebhookService::new(connections.db_pool.clone()));
+
+    let newsletter_service = Arc::new(NewsletterService::new(
+        connections.db_pool.clone(),
+        email_queue_service.clone(),
+    ));
+
+    let contact_service = Arc::new(ContactService::new(
+        connections.db_pool.clone(),
+        email_queue_service.clone(),
+    ));
 
     let chat_service = Arc::new(Mutex::new(GptService::new()));
 
     actix_web::rt::spawn({
         let blog_service_clone = blog_service.clone();
+        let session_service_clone = session_service.clone();
+        let search_service_clone = search_service.clone();
         async move {
 
             sleep(Duration::from_secs(get_last_time_seconds())).await;
@@ -143,11 +216,68 @@ async fn main() -> std::io::Result<()> {
                 interval.tick().await;
                 println!("定时任务更新 更新时间{}",Local::now().to_rfc3339());
                 blog_service_clone.init_blog_eye_couunt().await;
+                session_service_clone.delete_stale_sessions().await;
+                search_service_clone.delete_old_queries().await;
                 init_log();
             }
         }
     });
 
+    // 定时投递失败后等待重试的 Webhook
+    actix_web::rt::spawn({
+        let webhook_service_clone = webhook_service.clone();
+        async move {
+            let mut interval =
+                actix_web::rt::time::interval(Duration::from_secs(CONFIG.webhook.check_interval));
+            loop {
+                interval.tick().await;
+                webhook_service_clone.process_due().await;
+            }
+        }
+    });
+
+    // 定时对比搜索索引和数据库，修复同步失败造成的差异，间隔为 0 时不对比
+    if CONFIG.search.reconcile_interval > 0 {
+        actix_web::rt::spawn({
+            let search_service_clone = search_service.clone();
+            async move {
+                let mut interval = actix_web::rt::time::interval(Duration::from_secs(
+                    CONFIG.search.reconcile_interval,
+                ));
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = search_service_clone.reconcile().await {
+                        error!("搜索索引对比失败: {}", e.message);
+                    }
+                }
+            }
+        });
+    } else {
+        info!("搜索索引定时对比已关闭");
+    }
+
+    // 继续发送上次没有发送完的订阅通知
+    actix_web::rt::spawn({
+        let newsletter_service_clone = newsletter_service.clone();
+        async move {
+            newsletter_service_clone.resume_notifications().await;
+        }
+    });
+
+    // 定时发送队列中到期的邮件，服务重启后未发送的邮件也会继续发送
+    actix_web::rt::spawn({
+        let email_queue_service_clone = email_queue_service.clone();
+        async move {
+            let mut interval = actix_web::rt::time::interval(Duration::from_secs(
+                CONFIG.email_queue.check_interval,
+            ));
+            loop {
+                interval.tick().await;
+                email_queue_service_clone.process_due().await;
+            }
+        }
+    });
+
     HttpServer::new(move || {
         let mut cors = Cors::default();
         let urls = CONFIG.origin.to_url_vec();
@@ -172,17 +302,27 @@ async fn main() -> std::io::Result<()> {
             .configure(routers::tag_router)
             .configure(routers::topic_router)
             .configure(routers::file_router)
-            .configure(routers::admin_router);
+            .configure(routers::admin_router)
+            .configure(routers::webmention_router)
+            .configure(routers::newsletter_router);
 
         let app_data = Data::new(AppState {
             blog_service: blog_service.clone(),
             user_service: user_service.clone(),
+            session_service: session_service.clone(),
             search_client: search_client.clone(),
+            search_service: search_service.clone(),
             category_service: category_service.clone(),
             tag_service: tag_service.clone(),
             topic_service: topic_service.clone(),
             file_service: file_service.clone(),
             admin_service: admin_service.clone(),
+            dashboard_service: dashboard_service.clone(),
+            webmention_service: webmention_service.clone(),
+            webhook_service: webhook_service.clone(),
+            newsletter_service: newsletter_service.clone(),
+            contact_service: contact_service.clone(),
+            email_queue_service: email_queue_service.clone(),
             chat_service: chat_service.clone(),
         });
 
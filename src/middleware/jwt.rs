@@ -12,14 +12,18 @@ use serde::{Deserialize, Serialize};
 
 use crate::AppState;
 use crate::conf::config::CONFIG;
+use crate::conf::token_config::Claims;
 use crate::error::custom_error::{E, Status};
 use crate::models::user::UserVo;
+use crate::service::session_service::SessionService;
 use crate::service::user_service::UserService;
 
 //普通用户jwt验证
 #[derive(Deserialize, Debug, Serialize)]
 pub struct JwtUserRole {
     pub user: UserVo,
+    // 当前请求所属的登录会话
+    pub session_id: String,
 }
 
 //管理员用户jwt验证
@@ -40,10 +44,7 @@ enum RoleType {
     SuperAdmin,
 }
 
-fn get_user_and_verify_token(
-    token_header: Option<&HeaderValue>,
-    user_service: &UserService,
-) -> Result<String, E> {
+fn get_user_and_verify_token(token_header: Option<&HeaderValue>) -> Result<Claims, E> {
     if token_header.is_none() {
         return Err(E::error(
             Status::AUTHENTICATE_ERROR,
@@ -73,18 +74,29 @@ fn get_user_and_verify_token(
         ));
     }
 
-    let username = token_option.unwrap().sub;
-
-    let redis_token = user_service.get_user_token(&username);
+    return Ok(token_option.unwrap());
+}
 
-    if redis_token.is_none() || redis_token.unwrap() != token {
+// 访问令牌对应的会话必须仍然有效，撤销会话后令牌立即失效
+async fn get_session_user(
+    claims: &Claims,
+    user_service: &UserService,
+    session_service: &SessionService,
+) -> Result<UserVo, E> {
+    if !session_service.verify_session(claims).await {
         return Err(E::error(
             Status::AUTHENTICATE_ERROR,
             String::from("服务器已拒绝你的访问，请重新登录"),
         ));
     }
 
-    return Ok(username);
+    return match user_service.get_user(&claims.sub).await {
+        Some(u) => Ok(u),
+        None => Err(E::error(
+            Status::AUTHENTICATE_ERROR,
+            String::from("该用户不存在"),
+        )),
+    };
 }
 
 impl FromRequest for JwtUserRole {
@@ -98,23 +110,22 @@ impl FromRequest for JwtUserRole {
 
         let user_service = app_state.user_service.clone();
 
-        let result = get_user_and_verify_token(token_header, &user_service);
+        let session_service = app_state.session_service.clone();
+
+        let result = get_user_and_verify_token(token_header);
 
         if result.is_err() {
             return Box::pin(async { Err(result.unwrap_err()) });
         }
 
-        let username = result.unwrap();
+        let claims = result.unwrap();
 
         return Box::pin(async move {
-            let user = user_service.get_user(&username).await;
-            if let Some(u) = user {
-                return Ok(JwtUserRole { user: u });
-            }
-            return Err(E::error(
-                Status::AUTHENTICATE_ERROR,
-                String::from("该用户不存在"),
-            ));
+            let u = get_session_user(&claims, &user_service, &session_service).await?;
+            return Ok(JwtUserRole {
+                user: u,
+                session_id: claims.sid,
+            });
         });
     }
 }
@@ -130,29 +141,24 @@ impl FromRequest for JwtAdminRole {
 
         let user_service = app_state.user_service.clone();
 
-        let result = get_user_and_verify_token(token_header, &user_service);
+        let session_service = app_state.session_service.clone();
+
+        let result = get_user_and_verify_token(token_header);
 
         if result.is_err() {
             return Box::pin(async { Err(result.unwrap_err()) });
         }
 
-        let username = result.unwrap();
+        let claims = result.unwrap();
 
         return Box::pin(async move {
-            let user = user_service.get_user(&username).await;
-            if let Some(u) = user {
-                if u.role == "ADMIN" || u.role == "SUPER_ADMIN" {
-                    return Ok(JwtAdminRole { user: u });
-                } else {
-                    return Err(E::error(
-                        Status::AUTHENTICATE_ERROR,
-                        String::from("你的权限不够，需要ADMIN角色"),
-                    ));
-                }
+            let u = get_session_user(&claims, &user_service, &session_service).await?;
+            if u.role == "ADMIN" || u.role == "SUPER_ADMIN" {
+                return Ok(JwtAdminRole { user: u });
             }
             return Err(E::error(
                 Status::AUTHENTICATE_ERROR,
-                String::from("该用户不存在"),
+                String::from("你的权限不够，需要ADMIN角色"),
             ));
         });
     }
@@ -169,29 +175,24 @@ impl FromRequest for JwtSuperAdminRole {
 
         let user_service = app_state.user_service.clone();
 
-        let result = get_user_and_verify_token(token_header, &user_service);
+        let session_service = app_state.session_service.clone();
+
+        let result = get_user_and_verify_token(token_header);
 
         if result.is_err() {
             return Box::pin(async { Err(result.unwrap_err()) });
         }
 
-        let username = result.unwrap();
+        let claims = result.unwrap();
 
         return Box::pin(async move {
-            let user = user_service.get_user(&username).await;
-            if let Some(u) = user {
-                if u.role == "SUPER_ADMIN" {
-                    return Ok(JwtSuperAdminRole { user: u });
-                } else {
-                    return Err(E::error(
-                        Status::AUTHENTICATE_ERROR,
-                        String::from("你的权限不够，需要SUPER_ADMIN角色"),
-                    ));
-                }
+            let u = get_session_user(&claims, &user_service, &session_service).await?;
+            if u.role == "SUPER_ADMIN" {
+                return Ok(JwtSuperAdminRole { user: u });
             }
             return Err(E::error(
                 Status::AUTHENTICATE_ERROR,
-                String::from("该用户不存在"),
+                String::from("你的权限不够，需要SUPER_ADMIN角色"),
             ));
         });
     }
@@ -103,6 +103,12 @@ pub struct SimpleBlogVo {
     pub title: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct BlogViewDailyVo {
+    pub date: String,
+    pub count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct RecommendBlogVo {
     pub id: i64,
@@ -111,11 +117,28 @@ pub struct RecommendBlogVo {
     pub cover_image: String,
 }
 
+// 搜索索引中的博客文档，content 为去除标记后的正文摘录，create_at 为毫秒时间戳
 #[derive(Debug, Serialize, FromRow, Deserialize)]
 pub struct SearchBlogVo {
     pub id: i64,
     pub description: String,
     pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    #[serde(rename = "categoryId")]
+    pub category_id: Option<i64>,
+    pub category: Option<String>,
+    #[serde(rename = "topicId")]
+    pub topic_id: Option<i64>,
+    pub topic: Option<String>,
+    #[serde(rename = "authorId")]
+    pub author_id: i64,
+    pub author: String,
+    #[serde(rename = "createAt")]
+    pub create_at: i64,
+    #[serde(rename = "updateAt")]
+    pub update_at: i64,
+    pub views: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,3 +19,15 @@ pub struct OtherAdminVo {
     #[serde(with = "date_format", rename = "updateAt")]
     pub update_at: DateTime<Local>,
 }
+
+// 分类树节点
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct CategoryTreeVo {
+    pub id: i64,
+    pub name: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<i64>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub children: Vec<CategoryTreeVo>,
+}
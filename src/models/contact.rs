@@ -0,0 +1,23 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::common::date_format;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ContactMessageVo {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub subject: String,
+    pub content: String,
+    pub ip: String,
+    #[serde(rename = "read")]
+    pub is_read: bool,
+    pub archived: bool,
+    // 通知邮件在发送队列中的状态，邮件被删除后为空
+    #[serde(rename = "emailStatus")]
+    pub email_status: Option<String>,
+    #[serde(with = "date_format", rename = "createAt")]
+    pub create_at: DateTime<Local>,
+}
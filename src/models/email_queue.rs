@@ -0,0 +1,37 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::common::date_format;
+
+// 邮件状态：等待发送（包括等待重试）、已发送、多次失败后进入死信
+pub const EMAIL_PENDING: &str = "pending";
+pub const EMAIL_SENT: &str = "sent";
+pub const EMAIL_DEAD: &str = "dead";
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct EmailQueueVo {
+    pub id: i64,
+    #[serde(rename = "to")]
+    pub to_email: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(with = "date_format", rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Local>,
+    #[serde(with = "date_format", rename = "createAt")]
+    pub create_at: DateTime<Local>,
+}
+
+// 取出准备发送的邮件
+#[derive(Debug, FromRow)]
+pub struct QueuedEmail {
+    pub id: i64,
+    pub to_email: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    pub attempts: i32,
+}
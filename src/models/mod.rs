@@ -1,6 +1,12 @@
 pub mod blogs;
 pub mod category;
+pub mod contact;
+pub mod email_queue;
 pub mod file;
+pub mod newsletter;
+pub mod session;
 pub mod tag;
 pub mod topic;
+pub mod webhook;
+pub mod webmention;
 pub(crate) mod user;
@@ -0,0 +1,23 @@
+use sqlx::FromRow;
+
+// 订阅状态：等待确认、已确认、已退订
+pub const SUBSCRIBER_PENDING: &str = "pending";
+pub const SUBSCRIBER_ACTIVE: &str = "active";
+pub const SUBSCRIBER_UNSUBSCRIBED: &str = "unsubscribed";
+
+#[derive(Debug, FromRow)]
+pub struct SubscriberVo {
+    pub id: i64,
+    pub email: String,
+    pub unsubscribe_token: String,
+}
+
+// 发送通知需要的博客信息
+#[derive(Debug, FromRow)]
+pub struct NewsletterBlogVo {
+    pub id: i64,
+    pub title: String,
+    pub description: String,
+    pub category_id: Option<i64>,
+    pub topic_id: Option<i64>,
+}
@@ -0,0 +1,40 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::common::date_format;
+
+// 登录会话列表，current 表示发起请求的会话
+#[derive(Debug, Serialize, FromRow)]
+pub struct UserSessionVo {
+    pub id: String,
+    pub device: String,
+    pub ip: String,
+    pub city: String,
+    #[serde(with = "date_format", rename = "createAt")]
+    pub create_at: DateTime<Local>,
+    #[serde(with = "date_format", rename = "lastActiveAt")]
+    pub last_active_at: DateTime<Local>,
+    #[serde(with = "date_format", rename = "expireAt")]
+    pub expire_at: DateTime<Local>,
+    #[sqlx(skip)]
+    pub current: bool,
+}
+
+// 刷新令牌时使用的会话信息
+#[derive(Debug, FromRow)]
+pub struct SessionToken {
+    pub user_id: i64,
+    pub username: String,
+    pub previous_refresh_token_hash: Option<String>,
+    pub expire_at: DateTime<Local>,
+    pub revoked_at: Option<DateTime<Local>>,
+}
+
+// 新建会话时记录的客户端信息
+#[derive(Debug)]
+pub struct SessionClient {
+    pub device: String,
+    pub ip: String,
+    pub city: String,
+}
@@ -0,0 +1,98 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::common::date_format;
+
+// 可以订阅的事件
+pub const WEBHOOK_EVENT_ALL: &str = "*";
+pub const BLOG_CREATED: &str = "blog.created";
+pub const BLOG_UPDATED: &str = "blog.updated";
+pub const BLOG_DELETED: &str = "blog.deleted";
+pub const BLOG_RESTORED: &str = "blog.restored";
+pub const TOPIC_CREATED: &str = "topic.created";
+pub const TOPIC_UPDATED: &str = "topic.updated";
+pub const TOPIC_DELETED: &str = "topic.deleted";
+pub const TOPIC_RESTORED: &str = "topic.restored";
+pub const CATEGORY_CREATED: &str = "category.created";
+pub const CATEGORY_UPDATED: &str = "category.updated";
+pub const CATEGORY_DELETED: &str = "category.deleted";
+pub const CATEGORY_RESTORED: &str = "category.restored";
+
+pub const WEBHOOK_EVENTS: [&str; 13] = [
+    WEBHOOK_EVENT_ALL,
+    BLOG_CREATED,
+    BLOG_UPDATED,
+    BLOG_DELETED,
+    BLOG_RESTORED,
+    TOPIC_CREATED,
+    TOPIC_UPDATED,
+    TOPIC_DELETED,
+    TOPIC_RESTORED,
+    CATEGORY_CREATED,
+    CATEGORY_UPDATED,
+    CATEGORY_DELETED,
+    CATEGORY_RESTORED,
+];
+
+// 测试 Webhook 时发送的事件，不能订阅
+pub const WEBHOOK_PING: &str = "webhook.ping";
+
+// 投递状态
+pub const DELIVERY_PENDING: &str = "pending";
+pub const DELIVERY_SUCCESS: &str = "success";
+pub const DELIVERY_FAILED: &str = "failed";
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebhookVo {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub enabled: bool,
+    pub description: Option<String>,
+    #[serde(with = "date_format", rename = "createAt")]
+    pub create_at: DateTime<Local>,
+    #[serde(with = "date_format", rename = "updateAt")]
+    pub update_at: DateTime<Local>,
+}
+
+// 添加 Webhook 后返回，密钥只在这里完整返回一次
+#[derive(Debug, Serialize)]
+pub struct WebhookSecretVo {
+    pub id: i64,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebhookDeliveryVo {
+    pub id: i64,
+    #[serde(rename = "webhookId")]
+    pub webhook_id: i64,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    #[serde(rename = "responseStatus")]
+    pub response_status: Option<i32>,
+    #[serde(rename = "responseBody")]
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    #[serde(with = "date_format", rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Local>,
+    #[serde(with = "date_format", rename = "createAt")]
+    pub create_at: DateTime<Local>,
+    #[serde(with = "date_format", rename = "updateAt")]
+    pub update_at: DateTime<Local>,
+}
+
+// 待投递的记录及其 Webhook 地址和密钥
+#[derive(Debug, FromRow)]
+pub struct PendingDelivery {
+    pub id: i64,
+    pub event: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::common::date_format;
+
+// 待验证、已验证、验证失败
+pub const WEBMENTION_PENDING: &str = "pending";
+pub const WEBMENTION_VERIFIED: &str = "verified";
+pub const WEBMENTION_REJECTED: &str = "rejected";
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebmentionVo {
+    pub id: i64,
+    pub source: String,
+    pub title: Option<String>,
+    #[serde(with = "date_format", rename = "createAt")]
+    pub create_at: DateTime<Local>,
+}
+
+// 接收 Webmention 的表单参数
+#[derive(Debug, Deserialize)]
+pub struct WebmentionRequest {
+    pub source: String,
+    pub target: String,
+}
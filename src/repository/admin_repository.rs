@@ -5,6 +5,7 @@ use crate::common::constants::{
     ADMIN_BLOG_PAGE_COUNT, CATEGORY_ADMIN_PAGE_COUNT, FILE_ADMIN_PAGE_COUNT, TOPIC_ADMIN_PAGE_COUNT,
 };
 use crate::common::date_format::time_stamp_to_date;
+use crate::error::custom_error::{E, Status};
 use crate::models::blogs::BlogAdminVo;
 use crate::models::category::{CategoryVo, OtherAdminVo};
 use crate::models::file::FileAdminVo;
@@ -37,6 +38,169 @@ impl AdminRepository {
         }
     }
 
+    /// 获取分类及其所有子孙分类的ID（包含已删除的分类）
+    pub async fn get_category_subtree_ids(&self, ids: &Vec<i64>) -> Result<Vec<i64>, E> {
+        let sql = "WITH RECURSIVE sub AS (
+            SELECT id FROM categories WHERE id = ANY($1)
+            UNION
+            SELECT c.id FROM categories c JOIN sub ON c.parent_id = sub.id
+        ) SELECT id FROM sub";
+
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await;
+
+        return match result {
+            Ok(r) => Ok(r),
+            Err(e) => {
+                error!("获取子分类失败 ids:{:?} message:{:?}", ids, e);
+                Err(E::error(Status::DATABASE_ERROR, String::from("获取子分类失败")))
+            }
+        };
+    }
+
+    /// 获取分类的所有祖先分类ID（不包含自身）
+    pub async fn get_category_ancestor_ids(&self, ids: &Vec<i64>) -> Vec<i64> {
+        let sql = "WITH RECURSIVE parent AS (
+            SELECT parent_id AS id FROM categories WHERE id = ANY($1) AND parent_id IS NOT NULL
+            UNION
+            SELECT c.parent_id FROM categories c JOIN parent ON c.id = parent.id WHERE c.parent_id IS NOT NULL
+        ) SELECT id FROM parent";
+
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await;
+
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("获取父分类失败 ids:{:?} message:{:?}", ids, e);
+                vec![]
+            }
+        };
+    }
+
+    /// 在同一事务中修改分类名称和父分类，parent 为 None 时不修改父分类，
+    /// Some(None) 时设为顶级分类。父分类必须存在且未删除，并且不能是分类自身或其子孙分类。
+    /// 检查前先锁住分类、新的父分类及其所有祖先分类，两个同时进行的移动不会形成环。
+    /// 返回修改的行数。
+    pub async fn update_category(
+        &self,
+        id: i64,
+        name: &String,
+        parent: Option<Option<i64>>,
+    ) -> Result<i64, E> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                error!("事务开始失败: {}", e);
+                return Err(E::error(Status::DATABASE_ERROR, String::from("修改分类失败")));
+            }
+        };
+
+        if let Some(Some(parent_id)) = parent {
+            let lock_sql = "SELECT id FROM categories WHERE id = $1 OR id IN (
+                WITH RECURSIVE parent AS (
+                    SELECT id, parent_id FROM categories WHERE id = $2
+                    UNION
+                    SELECT c.id, c.parent_id FROM categories c JOIN parent ON c.id = parent.parent_id
+                ) SELECT id FROM parent) ORDER BY id FOR UPDATE";
+            if let Err(e) = sqlx::query(lock_sql)
+                .bind(&id)
+                .bind(&parent_id)
+                .execute(&mut *transaction)
+                .await
+            {
+                error!("修改分类失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("修改分类失败")));
+            }
+
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1 AND deleted_at IS NULL)",
+            )
+            .bind(&parent_id)
+            .fetch_one(&mut *transaction)
+            .await;
+            match exists {
+                Ok(true) => {}
+                Ok(false) => {
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(
+                        Status::CHECK_DATA_ERROR,
+                        String::from("父分类不存在或已删除"),
+                    ));
+                }
+                Err(e) => {
+                    error!("修改分类失败: {}", e);
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(Status::DATABASE_ERROR, String::from("修改分类失败")));
+                }
+            }
+
+            // 父分类不能是自身或自身的子孙分类，否则会形成环
+            let cycle = sqlx::query_scalar::<_, bool>(
+                "WITH RECURSIVE sub AS (
+                    SELECT id FROM categories WHERE id = $1
+                    UNION
+                    SELECT c.id FROM categories c JOIN sub ON c.parent_id = sub.id
+                ) SELECT EXISTS (SELECT 1 FROM sub WHERE id = $2)",
+            )
+            .bind(&id)
+            .bind(&parent_id)
+            .fetch_one(&mut *transaction)
+            .await;
+            match cycle {
+                Ok(false) => {}
+                Ok(true) => {
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(
+                        Status::CHECK_DATA_ERROR,
+                        String::from("不能将分类移动到自身或其子分类下"),
+                    ));
+                }
+                Err(e) => {
+                    error!("修改分类失败: {}", e);
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(Status::DATABASE_ERROR, String::from("修改分类失败")));
+                }
+            }
+        }
+
+        let sql = "UPDATE categories SET name = $1,
+            parent_id = CASE WHEN $3 THEN $4 ELSE parent_id END, update_at = now()
+            WHERE id = $2";
+        let result = sqlx::query(sql)
+            .bind(name)
+            .bind(&id)
+            .bind(parent.is_some())
+            .bind(parent.flatten())
+            .execute(&mut *transaction)
+            .await;
+
+        let rows = match result {
+            Ok(r) if r.rows_affected() > 0 => r.rows_affected() as i64,
+            Ok(_) => {
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::CHECK_DATA_ERROR, String::from("该分类不存在")));
+            }
+            Err(e) => {
+                error!("修改分类失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("修改分类失败")));
+            }
+        };
+
+        if let Err(e) = transaction.commit().await {
+            error!("修改分类失败: {}", e);
+            return Err(E::error(Status::DATABASE_ERROR, String::from("修改分类失败")));
+        }
+
+        return Ok(rows);
+    }
+
     pub async fn get_admin_other_filter(
         &self,
         table: String,
@@ -529,7 +693,7 @@ FROM
     //     }
     // }
 
-    pub async fn delete_blog_by_categories(&self, ids: &Vec<i64>, deleted: bool, uid: i64) -> i64 {
+    pub async fn delete_blog_by_categories(&self, ids: &Vec<i64>, deleted: bool, uid: i64) -> Vec<i64> {
         let sql = format!(
             "update blogs set deleted_at = {} ",
             if deleted { "now()" } else { "null" }
@@ -553,17 +717,20 @@ FROM
             builder.push(" and user_id = ").push_bind(uid);
         }
 
-        let query = builder.build().execute(&self.pool).await;
+        // 返回受影响的博客id，用于同步搜索索引
+        builder.push(" returning id");
 
-        if let Ok(r) = query {
-            return r.rows_affected() as i64;
+        let query = builder.build_query_scalar::<i64>().fetch_all(&self.pool).await;
+
+        if let Ok(ids) = query {
+            return ids;
         } else {
             error!(
                 "删除专题博客失败 ids:{:?} message:{:?}",
                 ids,
                 query.unwrap_err()
             );
-            return 0;
+            return vec![];
         }
     }
 
@@ -577,7 +744,7 @@ FROM
     //     }
     // }
 
-    pub async fn delete_blog_by_topics(&self, ids: &Vec<i64>, deleted: bool, uid: i64) -> i64 {
+    pub async fn delete_blog_by_topics(&self, ids: &Vec<i64>, deleted: bool, uid: i64) -> Vec<i64> {
         let sql = format!(
             "update blogs set deleted_at = {} ",
             if deleted { "now()" } else { "null" }
@@ -601,20 +768,58 @@ FROM
             builder.push(" and user_id = ").push_bind(uid);
         }
 
-        let query = builder.build().execute(&self.pool).await;
+        // 返回受影响的博客id，用于同步搜索索引
+        builder.push(" returning id");
 
-        if let Ok(r) = query {
-            return r.rows_affected() as i64;
+        let query = builder.build_query_scalar::<i64>().fetch_all(&self.pool).await;
+
+        if let Ok(ids) = query {
+            return ids;
         } else {
             error!(
                 "删除专题博客失败 ids:{:?} message:{:?}",
                 ids,
                 query.unwrap_err()
             );
-            return 0;
+            return vec![];
         }
     }
 
+    // 获取属于这些分类或专题的博客id，column 为 category_id 或 topic_id
+    pub async fn get_blog_ids_by_column(&self, column: &str, ids: &Vec<i64>) -> Vec<i64> {
+        let sql = format!(
+            "select id from blogs where deleted_at is null and {} = ANY($1)",
+            column
+        );
+        let result = sqlx::query_scalar::<_, i64>(&sql)
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    pub async fn get_blog_ids_by_tags(&self, ids: &Vec<i64>) -> Vec<i64> {
+        let sql = "select distinct b.id from blogs b join blogs_tags bt on b.id = bt.blog_id
+            where b.deleted_at is null and bt.tag_id = ANY($1)";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
     pub async fn update_role(&self, req: &UpdateRole) -> i64 {
         let sql = "update users set role_id = $1 where username = $2";
 
@@ -1,16 +1,18 @@
 use log::error;
 use sqlx::{Executor, Pool, Postgres, QueryBuilder, Row};
+use sqlx::types::chrono::{DateTime, Local};
 
 use crate::common::constants::{
     ARCHIVE_BLOG_PAGE_SIZE, BLOG_PAGE_SIZE, LATEST_BLOG_PAGE_SIZE, USER_TOP_BLOG_PAGE_SIZE,
 };
 use crate::error::custom_error::{E, Status};
 use crate::models::blogs::{
-    ArchiveBlogVo, BlogContentVo, BlogVo, RecommendBlogVo, SearchBlogVo, SimpleBlogVo,
+    ArchiveBlogVo, BlogContentVo, BlogViewDailyVo, BlogVo, RecommendBlogVo, SearchBlogVo,
+    SimpleBlogVo,
 };
 use crate::models::tag::TagVo;
 use crate::request::blog_request::{
-    ArchiveRange, BlogFindRequest, BlogRequest, GetUserBlogRequest,
+    ArchiveRange, BlogFindRequest, BlogRequest, GetUserBlogRequest, ViewSource,
 };
 use crate::response::page_info::PageInfo;
 
@@ -46,7 +48,15 @@ impl BlogRepository {
 
         let mut count_build: QueryBuilder<Postgres> = QueryBuilder::new(&count_sql.to_string());
 
-        if r.cid > 0 {
+        if r.cid > 0 && r.children {
+            // 包含该分类及其所有子孙分类
+            let sub_sql = " AND b.category_id IN (WITH RECURSIVE sub AS (
+                SELECT id FROM categories WHERE id = ";
+            let sub_end_sql = " UNION SELECT c2.id FROM categories c2 JOIN sub ON c2.parent_id = sub.id
+                WHERE c2.deleted_at is null) SELECT id FROM sub)";
+            count_build.push(sub_sql).push_bind(r.cid).push(sub_end_sql);
+            select_build.push(sub_sql).push_bind(r.cid).push(sub_end_sql);
+        } else if r.cid > 0 {
             count_build.push(" AND b.category_id = ").push_bind(r.cid);
             select_build.push(" AND b.category_id = ").push_bind(r.cid);
         } else {
@@ -129,6 +139,80 @@ impl BlogRepository {
             _ => 0,
         };
     }
+    pub async fn increase_daily_view(&self, id: i64) -> i64 {
+        let sql = "insert into blog_view_daily(blog_id, view_date, view_count) values ($1, current_date, 1)
+        on conflict (blog_id, view_date) do update set view_count = blog_view_daily.view_count + 1";
+        let result = sqlx::query(sql).bind(&id).execute(&self.pool).await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn insert_visit_log(
+        &self,
+        id: i64,
+        visitor: &str,
+        ip: &str,
+        city: &str,
+        platform: &str,
+        source: &ViewSource,
+    ) -> i64 {
+        let sql = "insert into blog_visit_logs(blog_id, visitor, ip, city, platform, referrer, referrer_domain,
+        utm_source, utm_medium, utm_campaign, utm_term, utm_content, create_at)
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, now())";
+        let result = sqlx::query(sql)
+            .bind(&id)
+            .bind(visitor)
+            .bind(ip)
+            .bind(city)
+            .bind(platform)
+            .bind(&source.referrer)
+            .bind(&source.domain)
+            .bind(&source.utm_source)
+            .bind(&source.utm_medium)
+            .bind(&source.utm_campaign)
+            .bind(&source.utm_term)
+            .bind(&source.utm_content)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn get_daily_views(
+        &self,
+        id: i64,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+    ) -> Vec<BlogViewDailyVo> {
+        let sql = "select to_char(d.day, 'YYYY-MM-DD') as date, coalesce(v.view_count, 0) as count
+        from generate_series($2::date, $3::date, interval '1 day') as d(day)
+        left join blog_view_daily v on v.view_date = d.day::date and v.blog_id = $1
+        order by d.day";
+        let result = sqlx::query_as::<_, BlogViewDailyVo>(sql)
+            .bind(&id)
+            .bind(start)
+            .bind(end)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                Vec::new()
+            }
+        };
+    }
+
     pub async fn get_blog_by_id(&self, id: &i64) -> Option<BlogContentVo> {
         let sql = "SELECT
         b.id, b.title, b.description, b.cover_image, b.source_url, b.content, b.eye_count, b.create_at,b,
@@ -343,9 +427,16 @@ impl BlogRepository {
         };
     }
 
-    pub async fn get_all_simple_blog(&self) -> Vec<SearchBlogVo> {
-        let sql = "select id,title,description from blogs where deleted_at is null";
-        let result = sqlx::query_as::<_, SearchBlogVo>(&sql)
+    // 获取搜索索引使用的博客文档，ids 为空时获取所有未删除的博客
+    pub async fn get_search_blogs(&self, ids: Option<&Vec<i64>>) -> Vec<SearchBlogVo> {
+        let mut builder = search_blogs_query();
+
+        if let Some(ids) = ids {
+            builder.push(" AND b.id = ANY(").push_bind(ids).push(")");
+        }
+
+        let result = builder
+            .build_query_as::<SearchBlogVo>()
             .fetch_all(&self.pool)
             .await;
         return match result {
@@ -357,6 +448,32 @@ impl BlogRepository {
         };
     }
 
+    // 按 id 顺序分批获取 id 大于 after_id 的博客文档，查询失败时返回错误，避免对比索引时误删文档
+    pub async fn get_search_blogs_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<SearchBlogVo>, E> {
+        let mut builder = search_blogs_query();
+        builder
+            .push(" AND b.id > ")
+            .push_bind(after_id)
+            .push(" ORDER BY b.id LIMIT ")
+            .push_bind(limit);
+
+        let result = builder
+            .build_query_as::<SearchBlogVo>()
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(blogs) => Ok(blogs),
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                Err(E::error(Status::DATABASE_ERROR, String::from("获取搜索文档失败")))
+            }
+        };
+    }
+
     pub async fn insert_blog(&self, req: &BlogRequest, uid: i64) -> Result<i64, E> {
         let sql = "insert into blogs(description, title, cover_image,
                 source_url, content, create_at, update_at, category_id, user_id, topic_id)
@@ -582,3 +699,21 @@ values ($1,$2,$3,$4,$5,now(),now(),$6,$7,$8) returning id;";
         };
     }
 }
+
+fn search_blogs_query<'a>() -> QueryBuilder<'a, Postgres> {
+    return QueryBuilder::<Postgres>::new(
+        "SELECT b.id, b.title, b.description, b.content,
+        ARRAY(SELECT t.name FROM tags t JOIN blogs_tags bt ON t.id = bt.tag_id
+            WHERE t.deleted_at IS NULL AND bt.blog_id = b.id ORDER BY t.name) AS tags,
+        c.id AS category_id, c.name AS category, t.id AS topic_id, t.name AS topic,
+        u.id AS author_id, u.nick_name AS author,
+        (extract(epoch FROM b.create_at) * 1000)::bigint AS create_at,
+        (extract(epoch FROM b.update_at) * 1000)::bigint AS update_at,
+        b.eye_count AS views
+        FROM blogs b
+        LEFT JOIN categories c ON b.category_id = c.id
+        INNER JOIN users u ON b.user_id = u.id
+        LEFT JOIN topics t ON b.topic_id = t.id
+        WHERE b.deleted_at IS NULL",
+    );
+}
@@ -1,6 +1,6 @@
 use sqlx::{Pool, Postgres, Row};
 
-use crate::models::category::CategoryVo;
+use crate::models::category::{CategoryTreeVo, CategoryVo};
 
 pub struct CategoryRepository {
     pool: Pool<Postgres>,
@@ -11,10 +11,14 @@ impl CategoryRepository {
         CategoryRepository { pool: db_pool }
     }
 
-    /// 添加新的分类到数据库。
-    pub async fn add_category(&self, name: &String) -> Option<CategoryVo> {
-        let sql = "INSERT INTO categories (name, create_at, update_at) VALUES ($1, NOW(), NOW()) returning id";
-        let result = sqlx::query(sql).bind(name).fetch_one(&self.pool).await;
+    /// 添加新的分类到数据库，parent_id 为空时为顶级分类。
+    pub async fn add_category(&self, name: &String, parent_id: Option<i64>) -> Option<CategoryVo> {
+        let sql = "INSERT INTO categories (name, parent_id, create_at, update_at) VALUES ($1, $2, NOW(), NOW()) returning id";
+        let result = sqlx::query(sql)
+            .bind(name)
+            .bind(&parent_id)
+            .fetch_one(&self.pool)
+            .await;
 
         match result {
             Ok(r) => {
@@ -50,4 +54,37 @@ impl CategoryRepository {
             }
         }
     }
+
+    /// 获取所有分类节点（包含父分类ID），用于构建分类树。
+    pub async fn get_category_nodes(&self) -> Vec<CategoryTreeVo> {
+        let sql = "SELECT id, name, parent_id FROM categories WHERE deleted_at IS NULL ORDER BY id";
+        let result = sqlx::query_as::<_, CategoryTreeVo>(sql)
+            .fetch_all(&self.pool)
+            .await;
+
+        match result {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("从数据库中获取分类节点时出错：{}", e);
+                vec![]
+            }
+        }
+    }
+
+    /// 判断分类是否存在（未删除）。
+    pub async fn category_exists(&self, id: i64) -> bool {
+        let sql = "SELECT count(id) FROM categories WHERE deleted_at IS NULL AND id = $1";
+        let result = sqlx::query(sql).bind(&id).fetch_one(&self.pool).await;
+
+        match result {
+            Ok(r) => {
+                let count: i64 = r.get("count");
+                count > 0
+            }
+            Err(e) => {
+                log::error!("查询分类是否存在时出错：{}", e);
+                false
+            }
+        }
+    }
 }
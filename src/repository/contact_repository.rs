@@ -0,0 +1,173 @@
+use log::error;
+use sqlx::{Pool, Postgres, QueryBuilder};
+
+use crate::common::constants::CONTACT_ADMIN_PAGE_COUNT;
+use crate::models::contact::ContactMessageVo;
+use crate::request::admin_request::ContactMessageFilter;
+use crate::request::user_request::ContactRequest;
+use crate::response::page_info::PageInfo;
+
+pub struct ContactRepository {
+    pool: Pool<Postgres>,
+}
+
+impl ContactRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> ContactRepository {
+        ContactRepository { pool: db_pool }
+    }
+
+    pub async fn insert_message(&self, req: &ContactRequest, ip: &str) -> Option<i64> {
+        let sql = "INSERT INTO contact_messages(name, email, subject, content, ip, create_at)
+            VALUES ($1, $2, $3, $4, $5, now()) RETURNING id";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(&req.name)
+            .bind(&req.email)
+            .bind(&req.subject)
+            .bind(&req.content)
+            .bind(ip)
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok(id) => Some(id),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                None
+            }
+        };
+    }
+
+    pub async fn set_email_id(&self, id: i64, email_id: i64) {
+        let sql = "UPDATE contact_messages SET email_id = $2 WHERE id = $1";
+        if let Err(e) = sqlx::query(sql)
+            .bind(&id)
+            .bind(&email_id)
+            .execute(&self.pool)
+            .await
+        {
+            error!("数据库执行失败: {}", e);
+        }
+    }
+
+    pub async fn get_message_list(&self, req: &ContactMessageFilter) -> PageInfo<ContactMessageVo> {
+        let mut count_builder = QueryBuilder::<Postgres>::new(
+            "SELECT count(*) FROM contact_messages WHERE deleted_at IS NULL AND archived = ",
+        );
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT id, name, email, subject, content, ip, is_read, archived,
+            (SELECT q.status FROM email_queue q WHERE q.id = email_id) AS email_status, create_at
+            FROM contact_messages WHERE deleted_at IS NULL AND archived = ",
+        );
+
+        builder.push_bind(req.archived);
+        count_builder.push_bind(req.archived);
+
+        if let Some(read) = req.read {
+            builder.push(" AND is_read = ").push_bind(read);
+            count_builder.push(" AND is_read = ").push_bind(read);
+        }
+
+        if let Some(keyword) = req.keyword.as_ref().filter(|k| !k.trim().is_empty()) {
+            let keyword = format!("%{}%", keyword.trim());
+            for b in [&mut builder, &mut count_builder] {
+                b.push(" AND (name ILIKE ")
+                    .push_bind(keyword.to_owned())
+                    .push(" OR email ILIKE ")
+                    .push_bind(keyword.to_owned())
+                    .push(" OR subject ILIKE ")
+                    .push_bind(keyword.to_owned())
+                    .push(" OR content ILIKE ")
+                    .push_bind(keyword.to_owned())
+                    .push(")");
+            }
+        }
+
+        let mut result = PageInfo {
+            page: req.page,
+            size: CONTACT_ADMIN_PAGE_COUNT,
+            total: 0,
+            data: vec![],
+        };
+
+        match count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(count) => result.total = count,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                return result;
+            }
+        }
+
+        if result.total == 0 {
+            return result;
+        }
+
+        let offset = (req.page.max(1) - 1) * CONTACT_ADMIN_PAGE_COUNT;
+
+        builder
+            .push(" ORDER BY create_at DESC OFFSET ")
+            .push_bind(offset)
+            .push(" LIMIT ")
+            .push_bind(CONTACT_ADMIN_PAGE_COUNT);
+
+        match builder
+            .build_query_as::<ContactMessageVo>()
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(rows) => result.data = rows,
+            Err(e) => error!("数据库查询失败: {}", e),
+        }
+
+        return result;
+    }
+
+    pub async fn update_read(&self, ids: &Vec<i64>, read: bool) -> i64 {
+        let sql = "UPDATE contact_messages SET is_read = $2,
+                read_at = CASE WHEN $2 THEN coalesce(read_at, now()) ELSE NULL END
+            WHERE id = ANY($1) AND deleted_at IS NULL";
+        let result = sqlx::query(sql)
+            .bind(ids)
+            .bind(&read)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn update_archived(&self, ids: &Vec<i64>, archived: bool) -> i64 {
+        let sql = "UPDATE contact_messages SET archived = $2 WHERE id = ANY($1) AND deleted_at IS NULL";
+        let result = sqlx::query(sql)
+            .bind(ids)
+            .bind(&archived)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn delete_messages(&self, ids: &Vec<i64>) -> i64 {
+        let sql = "UPDATE contact_messages SET deleted_at = now() WHERE id = ANY($1) AND deleted_at IS NULL";
+        let result = sqlx::query(sql).bind(ids).execute(&self.pool).await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+}
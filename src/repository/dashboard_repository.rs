@@ -0,0 +1,153 @@
+use log::error;
+use sqlx::{Pool, Postgres};
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::response::dashboard_info::{DailyVisitVo, NameCountVo, TopBlogVo};
+
+pub struct DashboardRepository {
+    pool: Pool<Postgres>,
+}
+
+impl DashboardRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> DashboardRepository {
+        DashboardRepository { pool: db_pool }
+    }
+
+    /// 获取日期范围内浏览量最高的博客。
+    pub async fn get_top_blogs(
+        &self,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+        limit: i64,
+    ) -> Vec<TopBlogVo> {
+        let sql = "SELECT b.id, b.title, sum(v.view_count)::BIGINT AS count
+            FROM blog_view_daily v JOIN blogs b ON b.id = v.blog_id
+            WHERE b.deleted_at IS NULL AND v.view_date BETWEEN $1::date AND $2::date
+            GROUP BY b.id, b.title ORDER BY count DESC LIMIT $3";
+        let result = sqlx::query_as::<_, TopBlogVo>(sql)
+            .bind(start)
+            .bind(end)
+            .bind(&limit)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 获取日期范围内每天的总浏览量和独立访客数。
+    pub async fn get_daily_visits(
+        &self,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+    ) -> Vec<DailyVisitVo> {
+        let sql = "SELECT to_char(d.day, 'YYYY-MM-DD') AS date,
+                coalesce(v.views, 0)::BIGINT AS views, coalesce(l.visitors, 0)::BIGINT AS visitors
+            FROM generate_series($1::date, $2::date, interval '1 day') AS d(day)
+            LEFT JOIN (SELECT view_date, sum(view_count) AS views FROM blog_view_daily
+                WHERE view_date BETWEEN $1::date AND $2::date GROUP BY view_date) v
+                ON v.view_date = d.day::date
+            LEFT JOIN (SELECT create_at::date AS day, count(DISTINCT visitor) AS visitors FROM blog_visit_logs
+                WHERE create_at >= $1::date AND create_at < $2::date + interval '1 day'
+                GROUP BY create_at::date) l
+                ON l.day = d.day::date
+            ORDER BY d.day";
+        let result = sqlx::query_as::<_, DailyVisitVo>(sql)
+            .bind(start)
+            .bind(end)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 按访问记录的某一列（city、platform、referrer_domain、utm_*）统计独立访客数，
+    /// 该列为空的记录不参与统计，blog_id 为空时统计全站。
+    pub async fn get_visit_group_count(
+        &self,
+        column: &str,
+        blog_id: Option<i64>,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+        limit: i64,
+    ) -> Vec<NameCountVo> {
+        let sql = format!(
+            "SELECT {} AS name, count(DISTINCT visitor) AS count FROM blog_visit_logs
+            WHERE create_at >= $1::date AND create_at < $2::date + interval '1 day' AND {} IS NOT NULL
+                AND ($4::BIGINT IS NULL OR blog_id = $4)
+            GROUP BY {} ORDER BY count DESC LIMIT $3",
+            column, column, column
+        );
+        let result = sqlx::query_as::<_, NameCountVo>(&sql)
+            .bind(start)
+            .bind(end)
+            .bind(&limit)
+            .bind(&blog_id)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 统计独立访客总数和没有外部来源也没有 utm_source 的直接访问数。
+    pub async fn get_visitor_and_direct_count(
+        &self,
+        blog_id: Option<i64>,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+    ) -> (i64, i64) {
+        let sql = "SELECT count(DISTINCT visitor),
+                count(DISTINCT visitor) FILTER (WHERE referrer_domain IS NULL AND utm_source IS NULL)
+            FROM blog_visit_logs
+            WHERE create_at >= $1::date AND create_at < $2::date + interval '1 day'
+                AND ($3::BIGINT IS NULL OR blog_id = $3)";
+        let result = sqlx::query_as::<_, (i64, i64)>(sql)
+            .bind(start)
+            .bind(end)
+            .bind(&blog_id)
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                (0, 0)
+            }
+        };
+    }
+
+    /// 获取截止日期之前若干个月每月发布的博客数量。
+    pub async fn get_monthly_posts(&self, end: &DateTime<Local>, months: i32) -> Vec<NameCountVo> {
+        let sql = "SELECT to_char(m.month, 'YYYY-MM') AS name, count(b.id) AS count
+            FROM generate_series(date_trunc('month', $1::date) - make_interval(months => $2 - 1),
+                date_trunc('month', $1::date), interval '1 month') AS m(month)
+            LEFT JOIN blogs b ON b.deleted_at IS NULL AND date_trunc('month', b.create_at) = m.month
+            GROUP BY m.month ORDER BY m.month";
+        let result = sqlx::query_as::<_, NameCountVo>(sql)
+            .bind(end)
+            .bind(&months)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+}
@@ -0,0 +1,209 @@
+use log::error;
+use sqlx::{Pool, Postgres, QueryBuilder};
+
+use crate::common::constants::EMAIL_QUEUE_PAGE_COUNT;
+use crate::email::template::RenderedEmail;
+use crate::models::email_queue::{EMAIL_DEAD, EMAIL_PENDING, EMAIL_SENT, EmailQueueVo, QueuedEmail};
+use crate::request::admin_request::EmailQueueFilter;
+use crate::response::page_info::PageInfo;
+
+pub struct EmailQueueRepository {
+    pool: Pool<Postgres>,
+}
+
+impl EmailQueueRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> EmailQueueRepository {
+        EmailQueueRepository { pool: db_pool }
+    }
+
+    /// 加入发送队列，max_age 不为空时超过该秒数还没有发送成功就不再发送。
+    pub async fn insert_email(
+        &self,
+        to: &str,
+        email: &RenderedEmail,
+        max_age: Option<i64>,
+    ) -> Option<i64> {
+        let sql = "INSERT INTO email_queue(to_email, subject, html, text, status, next_attempt_at, expire_at, create_at, update_at)
+            VALUES ($1, $2, $3, $4, $5, now(), now() + make_interval(secs => $6), now(), now()) RETURNING id";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(to)
+            .bind(&email.subject)
+            .bind(&email.html)
+            .bind(&email.text)
+            .bind(EMAIL_PENDING)
+            .bind(max_age.map(|s| s as f64))
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok(id) => Some(id),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 取出一封到期的待发送邮件，同时把下次发送时间推后 lease 秒，避免被重复发送。
+    pub async fn claim_due_email(&self, lease: i64) -> Option<QueuedEmail> {
+        let sql = "UPDATE email_queue SET next_attempt_at = now() + make_interval(secs => $2)
+            WHERE id = (
+                SELECT id FROM email_queue WHERE status = $1 AND next_attempt_at <= now()
+                    AND (expire_at IS NULL OR expire_at > now())
+                ORDER BY next_attempt_at LIMIT 1 FOR UPDATE SKIP LOCKED)
+            RETURNING id, to_email, subject, html, text, attempts";
+        let result = sqlx::query_as::<_, QueuedEmail>(sql)
+            .bind(EMAIL_PENDING)
+            .bind(lease as f64)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 超过有效期还没有发送成功的邮件直接进入死信，不再发送。
+    pub async fn expire_emails(&self) -> i64 {
+        let sql = "UPDATE email_queue SET status = $2, last_error = $3, update_at = now()
+            WHERE status = $1 AND expire_at <= now()";
+        let result = sqlx::query(sql)
+            .bind(EMAIL_PENDING)
+            .bind(EMAIL_DEAD)
+            .bind("邮件已过期")
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn mark_sent(&self, id: i64) {
+        let sql = "UPDATE email_queue SET status = $2, attempts = attempts + 1, last_error = NULL,
+            sent_at = now(), update_at = now() WHERE id = $1";
+        if let Err(e) = sqlx::query(sql)
+            .bind(&id)
+            .bind(EMAIL_SENT)
+            .execute(&self.pool)
+            .await
+        {
+            error!("数据库执行失败: {}", e);
+        }
+    }
+
+    /// 记录发送失败，retry_after 为空时进入死信。
+    pub async fn mark_failed(&self, id: i64, error_message: &str, retry_after: Option<i64>) {
+        let status = if retry_after.is_some() {
+            EMAIL_PENDING
+        } else {
+            EMAIL_DEAD
+        };
+        let sql = "UPDATE email_queue SET status = $2, attempts = attempts + 1, last_error = $3,
+            next_attempt_at = now() + make_interval(secs => $4), update_at = now() WHERE id = $1";
+        if let Err(e) = sqlx::query(sql)
+            .bind(&id)
+            .bind(status)
+            .bind(error_message)
+            .bind(retry_after.unwrap_or(0) as f64)
+            .execute(&self.pool)
+            .await
+        {
+            error!("数据库执行失败: {}", e);
+        }
+    }
+
+    /// 把死信重新加入发送队列，重新计算尝试次数，已过期的邮件不能重新发送。
+    pub async fn retry_email(&self, id: i64) -> i64 {
+        let sql = "UPDATE email_queue SET status = $2, attempts = 0, next_attempt_at = now(), update_at = now()
+            WHERE id = $1 AND status = $3 AND (expire_at IS NULL OR expire_at > now())";
+        let result = sqlx::query(sql)
+            .bind(&id)
+            .bind(EMAIL_PENDING)
+            .bind(EMAIL_DEAD)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn delete_email(&self, id: i64) -> i64 {
+        let result = sqlx::query("DELETE FROM email_queue WHERE id = $1")
+            .bind(&id)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn get_email_list(&self, req: &EmailQueueFilter) -> PageInfo<EmailQueueVo> {
+        let mut count_builder = QueryBuilder::<Postgres>::new("SELECT count(*) FROM email_queue");
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT id, to_email, subject, status, attempts, last_error, next_attempt_at, create_at
+            FROM email_queue",
+        );
+
+        if let Some(status) = &req.status {
+            builder.push(" WHERE status = ").push_bind(status.to_owned());
+            count_builder.push(" WHERE status = ").push_bind(status.to_owned());
+        }
+
+        let mut result = PageInfo {
+            page: req.page,
+            size: EMAIL_QUEUE_PAGE_COUNT,
+            total: 0,
+            data: vec![],
+        };
+
+        match count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(count) => result.total = count,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                return result;
+            }
+        }
+
+        if result.total == 0 {
+            return result;
+        }
+
+        let offset = (req.page.max(1) - 1) * EMAIL_QUEUE_PAGE_COUNT;
+
+        builder
+            .push(" ORDER BY id DESC OFFSET ")
+            .push_bind(offset)
+            .push(" LIMIT ")
+            .push_bind(EMAIL_QUEUE_PAGE_COUNT);
+
+        match builder
+            .build_query_as::<EmailQueueVo>()
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(rows) => result.data = rows,
+            Err(e) => error!("数据库查询失败: {}", e),
+        }
+
+        return result;
+    }
+}
@@ -2,7 +2,15 @@ pub mod blog_repository;
 
 pub mod admin_repository;
 pub mod category_repository;
+pub mod contact_repository;
+pub mod dashboard_repository;
+pub mod email_queue_repository;
 pub mod file_repository;
+pub mod newsletter_repository;
+pub mod search_repository;
+pub mod session_repository;
 pub mod tag_repository;
 pub mod topic_repository;
 pub mod user_repository;
+pub mod webhook_repository;
+pub mod webmention_repository;
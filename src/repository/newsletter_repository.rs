@@ -0,0 +1,263 @@
+use log::error;
+use sqlx::{Pool, Postgres};
+
+use crate::email::template::RenderedEmail;
+use crate::models::email_queue::EMAIL_PENDING;
+use crate::models::newsletter::{
+    NewsletterBlogVo, SUBSCRIBER_ACTIVE, SUBSCRIBER_PENDING, SUBSCRIBER_UNSUBSCRIBED,
+    SubscriberVo,
+};
+
+pub struct NewsletterRepository {
+    pool: Pool<Postgres>,
+}
+
+impl NewsletterRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> NewsletterRepository {
+        NewsletterRepository { pool: db_pool }
+    }
+
+    /// 保存订阅请求，已存在的邮箱更新确认令牌和待确认的订阅范围，已确认的订阅保持生效。
+    pub async fn save_subscriber(
+        &self,
+        email: &str,
+        categories: &Vec<i64>,
+        topics: &Vec<i64>,
+        confirm_token: &str,
+        unsubscribe_token: &str,
+    ) -> bool {
+        let sql = "INSERT INTO newsletter_subscribers(email, status, confirm_token, unsubscribe_token,
+                pending_category_ids, pending_topic_ids, create_at)
+            VALUES ($1, $2, $3, $4, $5, $6, now())
+            ON CONFLICT (email) DO UPDATE SET confirm_token = $3, pending_category_ids = $5,
+                pending_topic_ids = $6,
+                status = CASE WHEN newsletter_subscribers.status = $7 THEN $7 ELSE $2 END";
+        let result = sqlx::query(sql)
+            .bind(email)
+            .bind(SUBSCRIBER_PENDING)
+            .bind(confirm_token)
+            .bind(unsubscribe_token)
+            .bind(categories)
+            .bind(topics)
+            .bind(SUBSCRIBER_ACTIVE)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 确认订阅并使待确认的订阅范围生效，确认后替换掉确认令牌使其只能使用一次。
+    pub async fn confirm_subscriber(&self, token: &str, next_token: &str) -> bool {
+        let sql = "UPDATE newsletter_subscribers SET status = $3, category_ids = pending_category_ids,
+                topic_ids = pending_topic_ids, confirm_token = $2, confirmed_at = now(), unsubscribed_at = NULL
+            WHERE confirm_token = $1";
+        let result = sqlx::query(sql)
+            .bind(token)
+            .bind(next_token)
+            .bind(SUBSCRIBER_ACTIVE)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    pub async fn unsubscribe(&self, token: &str) -> bool {
+        let sql = "UPDATE newsletter_subscribers SET status = $2,
+                unsubscribed_at = coalesce(unsubscribed_at, now())
+            WHERE unsubscribe_token = $1";
+        let result = sqlx::query(sql)
+            .bind(token)
+            .bind(SUBSCRIBER_UNSUBSCRIBED)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    pub async fn get_newsletter_blog(&self, id: i64) -> Option<NewsletterBlogVo> {
+        let sql = "SELECT id, title, description, category_id::BIGINT AS category_id,
+                topic_id::BIGINT AS topic_id
+            FROM blogs WHERE id = $1 AND deleted_at IS NULL";
+        let result = sqlx::query_as::<_, NewsletterBlogVo>(sql)
+            .bind(&id)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 获取分类及其所有父分类的id，订阅了父分类的用户也会收到子分类博客的通知。
+    pub async fn get_category_with_ancestors(&self, id: i64) -> Vec<i64> {
+        let sql = "WITH RECURSIVE parent AS (
+            SELECT id, parent_id FROM categories WHERE id = $1
+            UNION
+            SELECT c.id, c.parent_id FROM categories c JOIN parent ON c.id = parent.parent_id
+        ) SELECT id FROM parent";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(&id)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![id]
+            }
+        };
+    }
+
+    /// 开始或继续发送博客的订阅通知，返回已经加入邮件队列的最后一个订阅者 id，
+    /// 之前已经发送完成时返回 None。
+    pub async fn start_issue(&self, blog_id: i64) -> Option<i64> {
+        let sql = "INSERT INTO newsletter_issues(blog_id, create_at) VALUES ($1, now())
+            ON CONFLICT (blog_id) DO UPDATE SET blog_id = excluded.blog_id
+                WHERE newsletter_issues.finished_at IS NULL
+            RETURNING last_subscriber_id";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(&blog_id)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 在同一事务中把一批通知邮件加入发送队列并记录发送进度，
+    /// 进度已经被其他任务更新（after_id 不匹配）时不写入，返回 false。
+    pub async fn save_issue_batch(
+        &self,
+        blog_id: i64,
+        after_id: i64,
+        last_id: i64,
+        emails: &Vec<(String, RenderedEmail)>,
+    ) -> bool {
+        let mut transaction = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                error!("事务开始失败: {}", e);
+                return false;
+            }
+        };
+
+        let sql = "UPDATE newsletter_issues SET last_subscriber_id = $3, sent_count = sent_count + $4
+            WHERE blog_id = $1 AND last_subscriber_id = $2 AND finished_at IS NULL";
+        let result = sqlx::query(sql)
+            .bind(&blog_id)
+            .bind(&after_id)
+            .bind(&last_id)
+            .bind(emails.len() as i64)
+            .execute(&mut *transaction)
+            .await;
+        match result {
+            Ok(r) if r.rows_affected() > 0 => {}
+            Ok(_) => {
+                transaction.rollback().await.unwrap();
+                return false;
+            }
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return false;
+            }
+        }
+
+        let sql = "INSERT INTO email_queue(to_email, subject, html, text, status, next_attempt_at, create_at, update_at)
+            VALUES ($1, $2, $3, $4, $5, now(), now(), now())";
+        for (to, email) in emails {
+            let result = sqlx::query(sql)
+                .bind(to)
+                .bind(&email.subject)
+                .bind(&email.html)
+                .bind(&email.text)
+                .bind(EMAIL_PENDING)
+                .execute(&mut *transaction)
+                .await;
+            if let Err(e) = result {
+                error!("数据库执行失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return false;
+            }
+        }
+
+        if let Err(e) = transaction.commit().await {
+            error!("数据库执行失败: {}", e);
+            return false;
+        }
+
+        return true;
+    }
+
+    pub async fn finish_issue(&self, blog_id: i64) {
+        let sql = "UPDATE newsletter_issues SET finished_at = now() WHERE blog_id = $1";
+        if let Err(e) = sqlx::query(sql).bind(&blog_id).execute(&self.pool).await {
+            error!("数据库执行失败: {}", e);
+        }
+    }
+
+    /// 获取还没有发送完成的博客id，服务重启后继续发送。
+    pub async fn get_unfinished_issue_ids(&self) -> Vec<i64> {
+        let sql = "SELECT blog_id FROM newsletter_issues WHERE finished_at IS NULL ORDER BY create_at";
+        let result = sqlx::query_scalar::<_, i64>(sql).fetch_all(&self.pool).await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 按id分批获取需要通知的订阅者：订阅全部、订阅了博客所在分类（含父分类）或专题的用户。
+    pub async fn get_subscriber_batch(
+        &self,
+        category_ids: &Vec<i64>,
+        topic_id: Option<i64>,
+        after_id: i64,
+        limit: i64,
+    ) -> Vec<SubscriberVo> {
+        let sql = "SELECT id, email, unsubscribe_token FROM newsletter_subscribers
+            WHERE status = $1 AND id > $2 AND (
+                (cardinality(category_ids) = 0 AND cardinality(topic_ids) = 0)
+                OR category_ids && $3 OR $4::BIGINT = ANY(topic_ids))
+            ORDER BY id LIMIT $5";
+        let result = sqlx::query_as::<_, SubscriberVo>(sql)
+            .bind(SUBSCRIBER_ACTIVE)
+            .bind(&after_id)
+            .bind(category_ids)
+            .bind(&topic_id)
+            .bind(&limit)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+}
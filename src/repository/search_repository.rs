@@ -0,0 +1,363 @@
+use log::error;
+use sqlx::{Pool, Postgres, QueryBuilder};
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::error::custom_error::{E, Status};
+use crate::models::tag::TagVo;
+use crate::models::topic::SimpleTopicVo;
+use crate::request::blog_request::{SearchQueryRequest, SearchSort};
+use crate::response::search_info::{SearchDailyStat, SearchEntityVo, SearchQueryStat};
+use crate::search::search_entity::SearchEntity;
+
+pub struct SearchRepository {
+    pool: Pool<Postgres>,
+}
+
+impl SearchRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> SearchRepository {
+        SearchRepository { pool: db_pool }
+    }
+
+    // 名称包含关键字的标签，以关键字开头的排在前面
+    pub async fn find_tags(&self, keyword: &str, limit: i64) -> Vec<TagVo> {
+        let sql = "SELECT id, name FROM tags WHERE deleted_at IS NULL AND name ILIKE $1 ESCAPE '\\'
+            ORDER BY name ILIKE $2 ESCAPE '\\' DESC, length(name), id LIMIT $3";
+        let keyword = escape_like(keyword);
+        let result = sqlx::query_as::<_, TagVo>(sql)
+            .bind(format!("%{}%", keyword))
+            .bind(format!("{}%", keyword))
+            .bind(&limit)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    pub async fn find_topics(&self, keyword: &str, limit: i64) -> Vec<SimpleTopicVo> {
+        let sql = "SELECT id, name FROM topics WHERE deleted_at IS NULL AND name ILIKE $1 ESCAPE '\\'
+            ORDER BY name ILIKE $2 ESCAPE '\\' DESC, length(name), id LIMIT $3";
+        let keyword = escape_like(keyword);
+        let result = sqlx::query_as::<_, SimpleTopicVo>(sql)
+            .bind(format!("%{}%", keyword))
+            .bind(format!("{}%", keyword))
+            .bind(&limit)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    // 获取搜索索引使用的专题、标签、分类或作者文档，ids 为空时获取所有未删除的记录
+    pub async fn get_entity_documents(
+        &self,
+        entity: SearchEntity,
+        ids: Option<&Vec<i64>>,
+    ) -> Vec<SearchEntityVo> {
+        let mut builder = QueryBuilder::<Postgres>::new(entity_documents_sql(entity));
+        if let Some(ids) = ids {
+            builder.push(" AND id = ANY(").push_bind(ids).push(")");
+        }
+        let result = builder
+            .build_query_as::<SearchEntityVo>()
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    // 按 id 顺序分批获取 id 大于 after_id 的文档，查询失败时返回错误，避免对比索引时误删文档
+    pub async fn get_entity_documents_after(
+        &self,
+        entity: SearchEntity,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<SearchEntityVo>, E> {
+        let mut builder = QueryBuilder::<Postgres>::new(entity_documents_sql(entity));
+        builder
+            .push(" AND id > ")
+            .push_bind(after_id)
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(limit);
+        let result = builder
+            .build_query_as::<SearchEntityVo>()
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => Ok(r),
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                Err(E::error(Status::DATABASE_ERROR, String::from("获取搜索文档失败")))
+            }
+        };
+    }
+
+    // 搜索索引不可用时直接在博客表中按范围和关键字查询，返回总数和当前页的博客 id
+    pub async fn find_blog_ids(&self, req: &SearchQueryRequest) -> (i64, Vec<i64>) {
+        let keyword = format!("%{}%", escape_like(req.keyword.trim()));
+
+        let mut count_builder = QueryBuilder::<Postgres>::new("SELECT count(*) FROM blogs b");
+        push_blog_conditions(&mut count_builder, req, &keyword);
+        let total = match count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(total) => total,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                return (0, vec![]);
+            }
+        };
+
+        if total == 0 {
+            return (0, vec![]);
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT b.id FROM blogs b");
+        push_blog_conditions(&mut builder, req, &keyword);
+        builder.push(" ORDER BY ");
+        match req.sort {
+            SearchSort::Relevance => {
+                // 标题匹配的排在前面
+                builder
+                    .push("b.title ILIKE ")
+                    .push_bind(keyword.to_owned())
+                    .push(" ESCAPE '\\' DESC, b.create_at DESC");
+            }
+            SearchSort::Newest => {
+                builder.push("b.create_at DESC");
+            }
+            SearchSort::Views => {
+                builder.push("b.eye_count DESC");
+            }
+        }
+        builder
+            .push(", b.id DESC OFFSET ")
+            .push_bind(req.get_offset())
+            .push(" LIMIT ")
+            .push_bind(req.get_size());
+
+        return match builder.build_query_scalar::<i64>().fetch_all(&self.pool).await {
+            Ok(ids) => (total, ids),
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                (0, vec![])
+            }
+        };
+    }
+
+    /// 获取专题下未删除的博客id。
+    pub async fn get_blog_ids_by_topics(&self, ids: &Vec<i64>) -> Vec<i64> {
+        let sql = "SELECT id FROM blogs WHERE deleted_at IS NULL AND topic_id = ANY($1)";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    pub async fn insert_query(&self, keyword: &str, result_count: i64, page: i64) {
+        let sql = "INSERT INTO search_queries(keyword, result_count, page) VALUES ($1, $2, $3)";
+        if let Err(e) = sqlx::query(sql)
+            .bind(keyword)
+            .bind(&result_count)
+            .bind(&page)
+            .execute(&self.pool)
+            .await
+        {
+            error!("数据库执行失败: {}", e);
+        }
+    }
+
+    /// 删除超过保留天数的搜索记录。
+    pub async fn delete_old_queries(&self, days: i64) -> u64 {
+        let sql = "DELETE FROM search_queries WHERE create_at < now() - make_interval(days => $1)";
+        let result = sqlx::query(sql)
+            .bind(days as i32)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    /// 获取日期范围内第一页的搜索总数和无结果的搜索数。
+    pub async fn get_query_count(
+        &self,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+    ) -> (i64, i64) {
+        let sql = "SELECT count(*), count(*) FILTER (WHERE result_count = 0) FROM search_queries
+            WHERE page = 1 AND create_at >= $1::date AND create_at < $2::date + interval '1 day'";
+        let result = sqlx::query_as::<_, (i64, i64)>(sql)
+            .bind(start)
+            .bind(end)
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                (0, 0)
+            }
+        };
+    }
+
+    /// 获取搜索次数最多的关键字，zero_only 为 true 时只统计没有结果的搜索。
+    pub async fn get_top_queries(
+        &self,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+        zero_only: bool,
+        limit: i64,
+    ) -> Vec<SearchQueryStat> {
+        let sql = "SELECT keyword, count(*) AS count,
+                (array_agg(result_count ORDER BY create_at DESC))[1] AS results
+            FROM search_queries
+            WHERE page = 1 AND create_at >= $1::date AND create_at < $2::date + interval '1 day'
+                AND (NOT $3 OR result_count = 0)
+            GROUP BY keyword ORDER BY count DESC, keyword LIMIT $4";
+        let result = sqlx::query_as::<_, SearchQueryStat>(sql)
+            .bind(start)
+            .bind(end)
+            .bind(zero_only)
+            .bind(&limit)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 获取日期范围内每天的搜索数和无结果的搜索数。
+    pub async fn get_daily_queries(
+        &self,
+        start: &DateTime<Local>,
+        end: &DateTime<Local>,
+    ) -> Vec<SearchDailyStat> {
+        let sql = "SELECT to_char(d.day, 'YYYY-MM-DD') AS date,
+                coalesce(q.count, 0)::BIGINT AS count, coalesce(q.zero_results, 0)::BIGINT AS zero_results
+            FROM generate_series($1::date, $2::date, interval '1 day') AS d(day)
+            LEFT JOIN (SELECT create_at::date AS day, count(*) AS count,
+                count(*) FILTER (WHERE result_count = 0) AS zero_results FROM search_queries
+                WHERE page = 1 AND create_at >= $1::date AND create_at < $2::date + interval '1 day'
+                GROUP BY create_at::date) q
+                ON q.day = d.day::date
+            ORDER BY d.day";
+        let result = sqlx::query_as::<_, SearchDailyStat>(sql)
+            .bind(start)
+            .bind(end)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+}
+
+// 博客表的过滤条件，和搜索索引的过滤表达式保持一致
+fn push_blog_conditions(
+    builder: &mut QueryBuilder<Postgres>,
+    req: &SearchQueryRequest,
+    keyword: &str,
+) {
+    builder.push(" WHERE b.deleted_at IS NULL");
+    if !req.keyword.trim().is_empty() {
+        builder
+            .push(" AND (b.title ILIKE ")
+            .push_bind(keyword.to_owned())
+            .push(" ESCAPE '\\' OR b.description ILIKE ")
+            .push_bind(keyword.to_owned())
+            .push(" ESCAPE '\\' OR b.content ILIKE ")
+            .push_bind(keyword.to_owned())
+            .push(" ESCAPE '\\')");
+    }
+    if let Some(topic_id) = req.topic_id {
+        builder.push(" AND b.topic_id = ").push_bind(topic_id);
+    }
+    if let Some(category_id) = req.category_id {
+        builder.push(" AND b.category_id = ").push_bind(category_id);
+    }
+    if let Some(author_id) = req.author_id {
+        builder.push(" AND b.user_id = ").push_bind(author_id);
+    }
+    if let Some(tag) = req.tag.as_ref().filter(|t| !t.trim().is_empty()) {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM blogs_tags bt JOIN tags t ON t.id = bt.tag_id
+                WHERE bt.blog_id = b.id AND t.deleted_at IS NULL AND t.name = ")
+            .push_bind(tag.trim().to_owned())
+            .push(")");
+    }
+    if let Some(start) = req.start {
+        builder
+            .push(" AND b.create_at >= to_timestamp(")
+            .push_bind(start as f64 / 1000.0)
+            .push(")");
+    }
+    if let Some(end) = req.end {
+        builder
+            .push(" AND b.create_at <= to_timestamp(")
+            .push_bind(end as f64 / 1000.0)
+            .push(")");
+    }
+}
+
+// 转义 LIKE 中的通配符
+fn escape_like(s: &str) -> String {
+    return s
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+}
+
+fn entity_documents_sql(entity: SearchEntity) -> &'static str {
+    return match entity {
+        SearchEntity::Topic => {
+            "SELECT id, name, coalesce(description, '') AS description,
+            coalesce(cover_image, '') AS image FROM topics WHERE deleted_at IS NULL"
+        }
+        SearchEntity::Tag => {
+            "SELECT id, name, '' AS description, '' AS image FROM tags WHERE deleted_at IS NULL"
+        }
+        SearchEntity::Category => {
+            "SELECT id, name, '' AS description, '' AS image FROM categories WHERE deleted_at IS NULL"
+        }
+        SearchEntity::Author => {
+            "SELECT id, nick_name AS name, '' AS description, coalesce(icon, '') AS image
+            FROM users WHERE deleted_at IS NULL"
+        }
+    };
+}
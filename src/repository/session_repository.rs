@@ -0,0 +1,180 @@
+use log::error;
+use sqlx::{Pool, Postgres};
+
+use crate::models::session::{SessionClient, SessionToken, UserSessionVo};
+
+pub struct SessionRepository {
+    pool: Pool<Postgres>,
+}
+
+impl SessionRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> SessionRepository {
+        return SessionRepository { pool: db_pool };
+    }
+
+    /// 新建登录会话。
+    pub async fn insert_session(
+        &self,
+        id: &String,
+        user_id: i64,
+        refresh_token_hash: &String,
+        client: &SessionClient,
+        expire: i64,
+    ) -> bool {
+        let sql = "INSERT INTO user_sessions(id, user_id, refresh_token_hash, device, ip, city,
+            create_at, last_active_at, expire_at)
+            VALUES ($1, $2, $3, $4, $5, $6, now(), now(), now() + make_interval(secs => $7))";
+        let result = sqlx::query(sql)
+            .bind(id)
+            .bind(&user_id)
+            .bind(refresh_token_hash)
+            .bind(&client.device)
+            .bind(&client.ip)
+            .bind(&client.city)
+            .bind(expire as f64)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(_) => true,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 获取会话和所属用户名，用户已删除时返回 None。
+    pub async fn get_session_token(&self, id: &String) -> Option<SessionToken> {
+        let sql = "SELECT s.user_id, u.username, s.previous_refresh_token_hash,
+            s.expire_at, s.revoked_at
+            FROM user_sessions s JOIN users u ON u.id = s.user_id
+            WHERE s.id = $1 AND u.deleted_at IS NULL";
+        let result = sqlx::query_as::<_, SessionToken>(sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 轮换刷新令牌，只有旧令牌匹配且会话有效时才会更新，旧令牌的哈希保存为上一个令牌，
+    /// 同时把会话有效期延长 expire 秒。
+    pub async fn rotate_refresh_token(
+        &self,
+        id: &String,
+        old_hash: &String,
+        new_hash: &String,
+        expire: i64,
+    ) -> bool {
+        let sql = "UPDATE user_sessions SET previous_refresh_token_hash = refresh_token_hash,
+            refresh_token_hash = $3, expire_at = now() + make_interval(secs => $4), last_active_at = now()
+            WHERE id = $1 AND refresh_token_hash = $2 AND revoked_at IS NULL AND expire_at > now()";
+        let result = sqlx::query(sql)
+            .bind(id)
+            .bind(old_hash)
+            .bind(new_hash)
+            .bind(expire as f64)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() == 1,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 确认会话有效并记录最后活跃时间，返回会话所属的用户 ID。
+    pub async fn touch_session(&self, id: &String) -> Option<i64> {
+        let sql = "UPDATE user_sessions SET last_active_at = now()
+            WHERE id = $1 AND revoked_at IS NULL AND expire_at > now() RETURNING user_id";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                None
+            }
+        };
+    }
+
+    pub async fn get_user_sessions(&self, user_id: i64) -> Vec<UserSessionVo> {
+        let sql = "SELECT id, device, ip, city, create_at, last_active_at, expire_at
+            FROM user_sessions WHERE user_id = $1 AND revoked_at IS NULL AND expire_at > now()
+            ORDER BY last_active_at DESC";
+        let result = sqlx::query_as::<_, UserSessionVo>(sql)
+            .bind(&user_id)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 撤销用户的一个会话，返回是否撤销成功。
+    pub async fn revoke_session(&self, user_id: i64, id: &String) -> bool {
+        let sql = "UPDATE user_sessions SET revoked_at = now()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL";
+        let result = sqlx::query(sql)
+            .bind(id)
+            .bind(&user_id)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() == 1,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 撤销用户的全部会话，keep 不为空时保留该会话，返回被撤销的会话 ID。
+    pub async fn revoke_user_sessions(&self, user_id: i64, keep: Option<&String>) -> Vec<String> {
+        let sql = "UPDATE user_sessions SET revoked_at = now()
+            WHERE user_id = $1 AND revoked_at IS NULL AND ($2::VARCHAR IS NULL OR id <> $2)
+            RETURNING id";
+        let result = sqlx::query_scalar::<_, String>(sql)
+            .bind(&user_id)
+            .bind(keep)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 删除过期或已撤销超过 days 天的会话。
+    pub async fn delete_stale_sessions(&self, days: i64) -> u64 {
+        let sql = "DELETE FROM user_sessions
+            WHERE coalesce(revoked_at, expire_at) < now() - make_interval(days => $1)";
+        let result = sqlx::query(sql)
+            .bind(days as i32)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+}
@@ -2,6 +2,7 @@ use log::error;
 use sqlx::{Pool, Postgres, Row};
 
 use crate::common::constants::BLOG_PAGE_SIZE;
+use crate::error::custom_error::{E, Status};
 use crate::models::blogs::BlogVo;
 use crate::models::tag::TagVo;
 use crate::response::page_info::PageInfo;
@@ -131,4 +132,310 @@ impl TagRepository {
             }
         };
     }
+
+    /// 将标签ID解析为有效的标签ID，已合并的旧标签通过别名解析到新标签。
+    pub async fn resolve_tag_id(&self, t_id: i64) -> Option<i64> {
+        let sql = "SELECT id FROM tags WHERE deleted_at IS NULL AND id = $1
+            UNION ALL
+            SELECT a.tag_id FROM tag_aliases a JOIN tags t ON t.id = a.tag_id
+            WHERE t.deleted_at IS NULL AND a.alias_tag_id = $1
+            LIMIT 1";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(&t_id)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("{}", e); // 记录错误
+                None
+            }
+        };
+    }
+
+    /// 通过标签名称或别名解析标签ID（不区分大小写）。
+    pub async fn resolve_tag_name(&self, name: &String) -> Option<i64> {
+        let sql = "SELECT id FROM tags WHERE deleted_at IS NULL AND lower(name) = lower($1)
+            UNION ALL
+            SELECT a.tag_id FROM tag_aliases a JOIN tags t ON t.id = a.tag_id
+            WHERE t.deleted_at IS NULL AND lower(a.name) = lower($1)
+            LIMIT 1";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("{}", e); // 记录错误
+                None
+            }
+        };
+    }
+
+    /// 合并标签：将源标签的博客关联改写到目标标签（不产生重复关联），
+    /// 软删除源标签并保留别名。返回受影响的博客ID。
+    pub async fn merge_tags(&self, source_ids: &Vec<i64>, target_id: i64) -> Result<Vec<i64>, E> {
+        let mut transaction = self.pool.begin().await.unwrap();
+
+        let blog_ids_sql = "SELECT DISTINCT blog_id FROM blogs_tags WHERE tag_id = ANY($1)";
+
+        let insert_sql = "INSERT INTO blogs_tags (blog_id, tag_id)
+            SELECT DISTINCT bt.blog_id, $2 FROM blogs_tags bt
+            WHERE bt.tag_id = ANY($1)
+            AND NOT EXISTS (SELECT 1 FROM blogs_tags x WHERE x.blog_id = bt.blog_id AND x.tag_id = $2)";
+
+        let delete_sql = "DELETE FROM blogs_tags WHERE tag_id = ANY($1)";
+
+        // 之前指向源标签的别名改为指向目标标签
+        let update_alias_sql = "UPDATE tag_aliases SET tag_id = $2 WHERE tag_id = ANY($1)";
+
+        let insert_alias_sql = "INSERT INTO tag_aliases (tag_id, alias_tag_id, name, create_at)
+            SELECT $2, id, name, now() FROM tags WHERE id = ANY($1)";
+
+        let delete_tag_sql =
+            "UPDATE tags SET deleted_at = now() WHERE id = ANY($1) AND deleted_at IS NULL";
+
+        let blog_ids = sqlx::query_scalar::<_, i64>(blog_ids_sql)
+            .bind(source_ids)
+            .fetch_all(&mut *transaction)
+            .await;
+
+        let blog_ids = match blog_ids {
+            Ok(r) => r,
+            Err(e) => {
+                error!("合并标签失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("合并标签失败")));
+            }
+        };
+
+        for sql in [
+            insert_sql,
+            delete_sql,
+            update_alias_sql,
+            insert_alias_sql,
+            delete_tag_sql,
+        ] {
+            let result = sqlx::query(sql)
+                .bind(source_ids)
+                .bind(&target_id)
+                .execute(&mut *transaction)
+                .await;
+            if let Err(e) = result {
+                error!("合并标签失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("合并标签失败")));
+            }
+        }
+
+        if let Err(e) = transaction.commit().await {
+            error!("合并标签失败: {}", e);
+            return Err(E::error(Status::DATABASE_ERROR, String::from("合并标签失败")));
+        }
+
+        return Ok(blog_ids);
+    }
+
+    /// 重命名标签，alias 为 true 时将旧名称保留为别名。返回该标签下的博客ID。
+    pub async fn rename_tag(&self, id: i64, name: &String, alias: bool) -> Result<Vec<i64>, E> {
+        let mut transaction = self.pool.begin().await.unwrap();
+
+        if alias {
+            let alias_sql = "INSERT INTO tag_aliases (tag_id, alias_tag_id, name, create_at)
+                SELECT id, null, name, now() FROM tags
+                WHERE id = $1 AND deleted_at IS NULL AND lower(name) <> lower($2)";
+            let result = sqlx::query(alias_sql)
+                .bind(&id)
+                .bind(name)
+                .execute(&mut *transaction)
+                .await;
+            if let Err(e) = result {
+                error!("重命名标签失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("重命名标签失败")));
+            }
+        }
+
+        let update_sql =
+            "UPDATE tags SET name = $1, update_at = now() WHERE id = $2 AND deleted_at IS NULL";
+        let result = sqlx::query(update_sql)
+            .bind(name)
+            .bind(&id)
+            .execute(&mut *transaction)
+            .await;
+
+        match result {
+            Ok(r) if r.rows_affected() > 0 => {}
+            Ok(_) => {
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::CHECK_DATA_ERROR, String::from("该标签不存在")));
+            }
+            Err(e) => {
+                error!("重命名标签失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("重命名标签失败")));
+            }
+        }
+
+        let blog_ids = sqlx::query_scalar::<_, i64>("SELECT blog_id FROM blogs_tags WHERE tag_id = $1")
+            .bind(&id)
+            .fetch_all(&mut *transaction)
+            .await
+            .unwrap_or_default();
+
+        if let Err(e) = transaction.commit().await {
+            error!("重命名标签失败: {}", e);
+            return Err(E::error(Status::DATABASE_ERROR, String::from("重命名标签失败")));
+        }
+
+        return Ok(blog_ids);
+    }
+
+    /// 拆分标签：把源标签下指定的博客移动到新的标签（同名标签存在时复用）。
+    /// delete_source 时源标签下的博客必须全部分配出去，源标签的别名指向第一个目标标签。
+    /// 返回实际移动的博客ID。
+    pub async fn split_tag(
+        &self,
+        source_id: i64,
+        targets: &Vec<(String, Vec<i64>)>,
+        delete_source: bool,
+    ) -> Result<Vec<i64>, E> {
+        let mut transaction = self.pool.begin().await.unwrap();
+
+        let find_sql = "SELECT id FROM tags WHERE deleted_at IS NULL AND lower(name) = lower($1) LIMIT 1";
+
+        let create_sql =
+            "INSERT INTO tags (name, create_at, update_at) VALUES ($1, now(), now()) returning id";
+
+        let insert_sql = "INSERT INTO blogs_tags (blog_id, tag_id)
+            SELECT DISTINCT bt.blog_id, $3 FROM blogs_tags bt
+            WHERE bt.tag_id = $1 AND bt.blog_id = ANY($2)
+            AND NOT EXISTS (SELECT 1 FROM blogs_tags x WHERE x.blog_id = bt.blog_id AND x.tag_id = $3)";
+
+        let delete_sql = "DELETE FROM blogs_tags WHERE tag_id = $1 AND blog_id = ANY($2) AND $3 <> $1
+            RETURNING blog_id";
+
+        let mut blog_ids: Vec<i64> = Vec::new();
+
+        let mut target_ids: Vec<i64> = Vec::new();
+
+        for (name, ids) in targets {
+            let exists = sqlx::query_scalar::<_, i64>(find_sql)
+                .bind(name)
+                .fetch_optional(&mut *transaction)
+                .await;
+
+            let tag_id = match exists {
+                Ok(Some(id)) => Ok(id),
+                Ok(None) => {
+                    sqlx::query_scalar::<_, i64>(create_sql)
+                        .bind(name)
+                        .fetch_one(&mut *transaction)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+
+            let tag_id = match tag_id {
+                Ok(id) => id,
+                Err(e) => {
+                    error!("拆分标签失败: {}", e);
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(Status::DATABASE_ERROR, String::from("拆分标签失败")));
+                }
+            };
+
+            target_ids.push(tag_id);
+
+            let result = sqlx::query(insert_sql)
+                .bind(&source_id)
+                .bind(ids)
+                .bind(&tag_id)
+                .execute(&mut *transaction)
+                .await;
+            if let Err(e) = result {
+                error!("拆分标签失败: {}", e);
+                transaction.rollback().await.unwrap();
+                return Err(E::error(Status::DATABASE_ERROR, String::from("拆分标签失败")));
+            }
+
+            // 只统计真正从源标签移走的博客
+            let moved = sqlx::query_scalar::<_, i64>(delete_sql)
+                .bind(&source_id)
+                .bind(ids)
+                .bind(&tag_id)
+                .fetch_all(&mut *transaction)
+                .await;
+            match moved {
+                Ok(ids) => blog_ids.extend(ids),
+                Err(e) => {
+                    error!("拆分标签失败: {}", e);
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(Status::DATABASE_ERROR, String::from("拆分标签失败")));
+                }
+            }
+        }
+
+        if delete_source {
+            let remaining = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM blogs_tags WHERE tag_id = $1",
+            )
+            .bind(&source_id)
+            .fetch_one(&mut *transaction)
+            .await
+            .unwrap_or(i64::MAX);
+
+            if remaining > 0 {
+                transaction.rollback().await.unwrap();
+                return Err(E::error(
+                    Status::CHECK_DATA_ERROR,
+                    String::from("源标签下还有博客没有分配到目标标签，不能删除源标签"),
+                ));
+            }
+
+            // 源标签以及之前指向它的别名都解析到第一个目标标签，旧链接仍然可以访问
+            let alias_target = target_ids.iter().find(|id| **id != source_id);
+
+            let mut sqls: Vec<(&str, Option<i64>)> = vec![];
+            if let Some(target_id) = alias_target {
+                sqls.push((
+                    "UPDATE tag_aliases SET tag_id = $2 WHERE tag_id = $1",
+                    Some(*target_id),
+                ));
+                sqls.push((
+                    "INSERT INTO tag_aliases (tag_id, alias_tag_id, name, create_at)
+                    SELECT $2, id, name, now() FROM tags WHERE id = $1",
+                    Some(*target_id),
+                ));
+            }
+            sqls.push((
+                "UPDATE tags SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL",
+                None,
+            ));
+
+            for (sql, target_id) in sqls {
+                let mut query = sqlx::query(sql).bind(&source_id);
+                if let Some(target_id) = target_id {
+                    query = query.bind(target_id);
+                }
+                let result = query.execute(&mut *transaction).await;
+                if let Err(e) = result {
+                    error!("拆分标签失败: {}", e);
+                    transaction.rollback().await.unwrap();
+                    return Err(E::error(Status::DATABASE_ERROR, String::from("拆分标签失败")));
+                }
+            }
+        }
+
+        blog_ids.sort();
+        blog_ids.dedup();
+
+        if let Err(e) = transaction.commit().await {
+            error!("拆分标签失败: {}", e);
+            return Err(E::error(Status::DATABASE_ERROR, String::from("拆分标签失败")));
+        }
+
+        return Ok(blog_ids);
+    }
 }
@@ -190,21 +190,23 @@ impl TopicRepository {
         };
     }
 
-    pub async fn add_topic(&self, uid: i64, topic: TopicRequest) -> Option<E> {
-        let sql = "insert into topics(name,description,cover_image,create_at,update_at,user_id) values ($1,$2,$3,now(),now(),$4)";
+    pub async fn add_topic(&self, uid: i64, topic: TopicRequest) -> Result<i64, E> {
+        let sql = "insert into topics(name,description,cover_image,create_at,update_at,user_id) values ($1,$2,$3,now(),now(),$4) returning id";
 
-        let result = sqlx::query(sql)
+        let result = sqlx::query_scalar::<_, i64>(sql)
             .bind(&topic.name)
             .bind(&topic.desc)
             .bind(&topic.cover)
             .bind(&uid)
-            .execute(&self.pool)
+            .fetch_one(&self.pool)
             .await;
 
-        if let Err(e) = result {
-            return Some(E::error(Status::ADD_ERROR, String::from("添加专题失败")));
-        } else {
-            return None;
-        }
+        return match result {
+            Ok(id) => Ok(id),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                Err(E::error(Status::ADD_ERROR, String::from("添加专题失败")))
+            }
+        };
     }
 }
@@ -5,6 +5,7 @@ use crate::common::constants::DEFAULT_ROLE_ID;
 use crate::error::custom_error::{E, Status};
 use crate::models::user::UserVo;
 use crate::request::user_request::UserRegisteredRequest;
+use crate::response::user_info::PasswordHashReport;
 
 pub struct UserRepository {
     pool: Pool<Postgres>,
@@ -52,6 +53,48 @@ impl UserRepository {
         };
     }
 
+    /// 更新密码哈希，用于旧哈希升级、重置和修改密码。
+    pub async fn update_password(&self, id: i64, password: &String) -> bool {
+        let sql = "UPDATE users SET password = $1, update_at = now() WHERE id = $2";
+        return match sqlx::query(sql)
+            .bind(password)
+            .bind(&id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 统计各种密码哈希的账号数量，prefix 为当前 Argon2id 参数的哈希前缀。
+    pub async fn get_password_hash_report(&self, prefix: &String) -> PasswordHashReport {
+        let sql = "SELECT count(*) AS total,
+            count(*) FILTER (WHERE starts_with(password, $1)) AS current,
+            count(*) FILTER (WHERE starts_with(password, '$argon2') AND NOT starts_with(password, $1)) AS outdated,
+            count(*) FILTER (WHERE NOT starts_with(password, '$argon2')) AS legacy
+            FROM users WHERE deleted_at IS NULL";
+        let result = sqlx::query_as::<_, (i64, i64, i64, i64)>(sql)
+            .bind(prefix)
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok((total, current, outdated, legacy)) => PasswordHashReport {
+                total,
+                current,
+                outdated,
+                legacy,
+            },
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                PasswordHashReport::default()
+            }
+        };
+    }
+
     /// 通过用户ID获取用户信息。
     pub async fn get_user_by_id(&self, id: i64) -> Result<UserVo, Error> {
         let sql = "SELECT u.id, u.username, u.nick_name, u.password, u.icon , r.name as role_name
@@ -80,6 +123,117 @@ impl UserRepository {
         return result;
     }
 
+    /// 通过邮箱获取未删除的用户信息。
+    pub async fn get_user_by_email(&self, email: &String) -> Option<UserVo> {
+        let sql = "SELECT u.id, u.username, u.nick_name, u.password, u.icon , r.name as role_name
+            FROM users u
+            LEFT JOIN roles r ON u.role_id = r.id
+            WHERE u.email = $1 AND u.deleted_at IS NULL LIMIT 1";
+
+        let result = sqlx::query_as::<_, UserVo>(sql)
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await;
+
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 修改昵称和头像，为 None 的字段保持不变。
+    pub async fn update_profile(
+        &self,
+        id: i64,
+        nick_name: &Option<String>,
+        icon: &Option<String>,
+    ) -> bool {
+        let sql = "UPDATE users SET nick_name = coalesce($2, nick_name), icon = coalesce($3, icon),
+            update_at = now() WHERE id = $1";
+        return match sqlx::query(sql)
+            .bind(&id)
+            .bind(nick_name)
+            .bind(icon)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 修改邮箱，新邮箱已被其他账号使用时不修改。
+    pub async fn update_email(&self, id: i64, email: &String) -> bool {
+        let sql = "UPDATE users SET email = $2, update_at = now()
+            WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM users WHERE email = $2 AND id <> $1)";
+        return match sqlx::query(sql)
+            .bind(&id)
+            .bind(email)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                false
+            }
+        };
+    }
+
+    /// 获取用户当前的邮箱。
+    pub async fn get_user_email(&self, id: i64) -> Option<String> {
+        let sql = "SELECT email FROM users WHERE id = $1";
+        return match sqlx::query_scalar::<_, String>(sql)
+            .bind(&id)
+            .fetch_optional(&self.pool)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 获取用户发布的未删除博客 ID。
+    pub async fn get_user_blog_ids(&self, id: i64) -> Vec<i64> {
+        let sql = "SELECT id FROM blogs WHERE user_id = $1 AND deleted_at IS NULL";
+        return match sqlx::query_scalar::<_, i64>(sql)
+            .bind(&id)
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    /// 检查邮箱是否已被使用。
+    pub async fn email_is_exists(&self, email: &String) -> bool {
+        let sql = "SELECT EXISTS (SELECT 1 FROM users WHERE email = $1)";
+        return match sqlx::query_scalar::<_, bool>(sql)
+            .bind(email)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                true
+            }
+        };
+    }
+
     /// 检查用户名或邮箱是否已存在。
     pub async fn user_is_exists(&self, username: &String, email: &String) -> Option<E> {
         let sql = "SELECT COUNT(id) FROM users WHERE username = $1 OR email = $2";
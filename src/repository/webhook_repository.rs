@@ -0,0 +1,262 @@
+use log::error;
+use sqlx::{Pool, Postgres, QueryBuilder};
+
+use crate::common::constants::WEBHOOK_DELIVERY_PAGE_COUNT;
+use crate::models::webhook::{
+    DELIVERY_FAILED, DELIVERY_PENDING, DELIVERY_SUCCESS, PendingDelivery, WEBHOOK_EVENT_ALL,
+    WebhookDeliveryVo, WebhookVo,
+};
+use crate::request::admin_request::{WebhookDeliveryFilter, WebhookRequest};
+use crate::response::page_info::PageInfo;
+
+pub struct WebhookRepository {
+    pool: Pool<Postgres>,
+}
+
+impl WebhookRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> WebhookRepository {
+        WebhookRepository { pool: db_pool }
+    }
+
+    pub async fn get_webhook_list(&self) -> Vec<WebhookVo> {
+        let sql = "SELECT id, url, secret, events, enabled, description, create_at, update_at
+            FROM webhooks ORDER BY id";
+        let result = sqlx::query_as::<_, WebhookVo>(sql)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+
+    pub async fn insert_webhook(&self, req: &WebhookRequest, secret: &str) -> Option<i64> {
+        let sql = "INSERT INTO webhooks(url, secret, events, enabled, description, create_at, update_at)
+            VALUES ($1, $2, $3, $4, $5, now(), now()) RETURNING id";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(&req.url)
+            .bind(secret)
+            .bind(&req.events)
+            .bind(&req.enabled)
+            .bind(&req.description)
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok(id) => Some(id),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 修改 Webhook，secret 为空时保留原来的密钥。
+    pub async fn update_webhook(&self, id: i64, req: &WebhookRequest) -> i64 {
+        let sql = "UPDATE webhooks SET url = $2, secret = coalesce($3, secret), events = $4,
+            enabled = $5, description = $6, update_at = now() WHERE id = $1";
+        let result = sqlx::query(sql)
+            .bind(&id)
+            .bind(&req.url)
+            .bind(&req.secret)
+            .bind(&req.events)
+            .bind(&req.enabled)
+            .bind(&req.description)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn delete_webhook(&self, id: i64) -> i64 {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = $1")
+            .bind(&id)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    /// 为订阅了该事件的所有启用的 Webhook 创建投递记录，webhook_id 不为空时只投递给该 Webhook。
+    pub async fn create_deliveries(
+        &self,
+        event: &str,
+        payload: &str,
+        webhook_id: Option<i64>,
+    ) -> i64 {
+        let sql = "INSERT INTO webhook_deliveries(webhook_id, event, payload, status, next_attempt_at, create_at, update_at)
+            SELECT id, $1, $2, $3, now(), now(), now() FROM webhooks
+            WHERE enabled AND ($1 = ANY(events) OR $4 = ANY(events) OR $5::BIGINT IS NOT NULL)
+                AND ($5::BIGINT IS NULL OR id = $5)";
+        let result = sqlx::query(sql)
+            .bind(event)
+            .bind(payload)
+            .bind(DELIVERY_PENDING)
+            .bind(WEBHOOK_EVENT_ALL)
+            .bind(&webhook_id)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    /// 取出一条到期的待投递记录，同时把下次投递时间推后 lease 秒，避免被重复投递。
+    pub async fn claim_due_delivery(&self, lease: i64) -> Option<PendingDelivery> {
+        let sql = "UPDATE webhook_deliveries d SET next_attempt_at = now() + make_interval(secs => $2)
+            FROM webhooks w
+            WHERE d.webhook_id = w.id AND d.id = (
+                SELECT id FROM webhook_deliveries WHERE status = $1 AND next_attempt_at <= now()
+                ORDER BY next_attempt_at LIMIT 1 FOR UPDATE SKIP LOCKED)
+            RETURNING d.id, d.event, d.payload, d.attempts, w.url, w.secret";
+        let result = sqlx::query_as::<_, PendingDelivery>(sql)
+            .bind(DELIVERY_PENDING)
+            .bind(lease as f64)
+            .fetch_optional(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 记录一次投递的结果，retry_after 为空时根据 success 标记为成功或最终失败。
+    pub async fn update_delivery(
+        &self,
+        id: i64,
+        success: bool,
+        response_status: Option<i32>,
+        response_body: Option<String>,
+        error_message: Option<String>,
+        retry_after: Option<i64>,
+    ) -> i64 {
+        let status = if success {
+            DELIVERY_SUCCESS
+        } else if retry_after.is_some() {
+            DELIVERY_PENDING
+        } else {
+            DELIVERY_FAILED
+        };
+        let sql = "UPDATE webhook_deliveries SET status = $2, attempts = attempts + 1,
+            response_status = $3, response_body = $4, error = $5,
+            next_attempt_at = now() + make_interval(secs => $6), update_at = now() WHERE id = $1";
+        let result = sqlx::query(sql)
+            .bind(&id)
+            .bind(status)
+            .bind(&response_status)
+            .bind(&response_body)
+            .bind(&error_message)
+            .bind(retry_after.unwrap_or(0) as f64)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    /// 把投递记录重新加入投递队列。
+    pub async fn retry_delivery(&self, id: i64) -> i64 {
+        let sql = "UPDATE webhook_deliveries SET status = $2, next_attempt_at = now(), update_at = now()
+            WHERE id = $1 AND status <> $2";
+        let result = sqlx::query(sql)
+            .bind(&id)
+            .bind(DELIVERY_PENDING)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn get_delivery_list(
+        &self,
+        req: &WebhookDeliveryFilter,
+    ) -> PageInfo<WebhookDeliveryVo> {
+        let mut count_builder =
+            QueryBuilder::<Postgres>::new("SELECT count(*) FROM webhook_deliveries WHERE 1 = 1");
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT id, webhook_id, event, payload, status, attempts, response_status, response_body,
+            error, next_attempt_at, create_at, update_at FROM webhook_deliveries WHERE 1 = 1",
+        );
+
+        if let Some(webhook_id) = req.webhook_id {
+            builder.push(" AND webhook_id = ").push_bind(webhook_id);
+            count_builder.push(" AND webhook_id = ").push_bind(webhook_id);
+        }
+
+        if let Some(status) = &req.status {
+            builder.push(" AND status = ").push_bind(status.to_owned());
+            count_builder.push(" AND status = ").push_bind(status.to_owned());
+        }
+
+        let mut result = PageInfo {
+            page: req.page,
+            size: WEBHOOK_DELIVERY_PAGE_COUNT,
+            total: 0,
+            data: vec![],
+        };
+
+        match count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(count) => result.total = count,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                return result;
+            }
+        }
+
+        if result.total == 0 {
+            return result;
+        }
+
+        let offset = (req.page.max(1) - 1) * WEBHOOK_DELIVERY_PAGE_COUNT;
+
+        builder
+            .push(" ORDER BY id DESC OFFSET ")
+            .push_bind(offset)
+            .push(" LIMIT ")
+            .push_bind(WEBHOOK_DELIVERY_PAGE_COUNT);
+
+        match builder
+            .build_query_as::<WebhookDeliveryVo>()
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(rows) => result.data = rows,
+            Err(e) => error!("数据库查询失败: {}", e),
+        }
+
+        return result;
+    }
+}
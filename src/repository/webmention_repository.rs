@@ -0,0 +1,81 @@
+use log::error;
+use sqlx::{Pool, Postgres};
+
+use crate::models::webmention::{WEBMENTION_PENDING, WEBMENTION_VERIFIED, WebmentionVo};
+
+pub struct WebmentionRepository {
+    pool: Pool<Postgres>,
+}
+
+impl WebmentionRepository {
+    pub fn new(db_pool: Pool<Postgres>) -> WebmentionRepository {
+        WebmentionRepository { pool: db_pool }
+    }
+
+    pub async fn blog_exists(&self, id: i64) -> bool {
+        let sql = "SELECT EXISTS(SELECT 1 FROM blogs WHERE id = $1 AND deleted_at IS NULL)";
+        return sqlx::query_scalar::<_, bool>(sql)
+            .bind(&id)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap_or(false);
+    }
+
+    /// 保存待验证的 Webmention，同一来源重复发送时重新验证。
+    pub async fn save_pending(&self, blog_id: i64, source: &str, target: &str) -> Option<i64> {
+        let sql = "INSERT INTO webmentions(blog_id, source, target, status, create_at)
+            VALUES ($1, $2, $3, $4, now())
+            ON CONFLICT (source, blog_id) DO UPDATE SET target = $3, status = $4
+            RETURNING id";
+        let result = sqlx::query_scalar::<_, i64>(sql)
+            .bind(&blog_id)
+            .bind(source)
+            .bind(target)
+            .bind(WEBMENTION_PENDING)
+            .fetch_one(&self.pool)
+            .await;
+        return match result {
+            Ok(id) => Some(id),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                None
+            }
+        };
+    }
+
+    pub async fn update_status(&self, id: i64, status: &str, title: Option<String>) -> i64 {
+        let sql = "UPDATE webmentions SET status = $2, title = coalesce($3, title),
+            verified_at = CASE WHEN $2 = $4 THEN now() ELSE verified_at END WHERE id = $1";
+        let result = sqlx::query(sql)
+            .bind(&id)
+            .bind(status)
+            .bind(&title)
+            .bind(WEBMENTION_VERIFIED)
+            .execute(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r.rows_affected() as i64,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                0
+            }
+        };
+    }
+
+    pub async fn get_verified_list(&self, blog_id: i64) -> Vec<WebmentionVo> {
+        let sql = "SELECT id, source, title, create_at FROM webmentions
+            WHERE blog_id = $1 AND status = $2 ORDER BY verified_at DESC";
+        let result = sqlx::query_as::<_, WebmentionVo>(sql)
+            .bind(&blog_id)
+            .bind(WEBMENTION_VERIFIED)
+            .fetch_all(&self.pool)
+            .await;
+        return match result {
+            Ok(r) => r,
+            Err(e) => {
+                error!("数据库查询失败: {}", e);
+                vec![]
+            }
+        };
+    }
+}
@@ -1,6 +1,7 @@
 use serde::Deserialize;
 
 use crate::common::constants::default_page;
+use crate::conf::config::CONFIG;
 use crate::request::blog_request::Sort;
 
 #[derive(Deserialize, Debug)]
@@ -27,12 +28,89 @@ pub struct OtherAdminFilter {
     pub keyword: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct DashboardRequest {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub top: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReferrerReportRequest {
+    #[serde(rename = "blogId")]
+    pub blog_id: Option<i64>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub top: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookRequest {
+    pub id: Option<i64>,
+    pub url: String,
+    // 为空时自动生成
+    pub secret: Option<String>,
+    pub events: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub description: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookDeliveryFilter {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(rename = "webhookId")]
+    pub webhook_id: Option<i64>,
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ContactMessageFilter {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    // 搜索名字、邮箱、主题和内容
+    pub keyword: Option<String>,
+    pub read: Option<bool>,
+    // 默认只查询未归档的留言
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmailQueueFilter {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    // pending、sent 或 dead，为空时查询全部
+    pub status: Option<String>,
+}
+
+// 批量修改留言的已读或归档状态
+#[derive(Deserialize, Debug)]
+pub struct ContactFlagRequest {
+    pub ids: Vec<i64>,
+    pub flag: bool,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct UpdatePublicRequest {
     pub is_pub: bool,
     pub id: i64,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct UpdateCategoryRequest {
+    pub id: i64,
+    pub name: String,
+    // 为空时不修改父分类，小于等于0时设为顶级分类
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<i64>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct UpdateRole {
     pub role: String,
@@ -77,3 +155,54 @@ impl FileSort {
         };
     }
 }
+
+#[derive(Deserialize, Debug)]
+pub struct MergeTagRequest {
+    // 被合并的标签，合并后软删除并保留为别名
+    #[serde(rename = "sourceIds")]
+    pub source_ids: Vec<i64>,
+    #[serde(rename = "targetId")]
+    pub target_id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RenameTagRequest {
+    pub id: i64,
+    pub name: String,
+    // 是否将旧名称保留为别名
+    #[serde(default)]
+    pub alias: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SplitTarget {
+    pub name: String,
+    #[serde(rename = "blogIds")]
+    pub blog_ids: Vec<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SplitTagRequest {
+    #[serde(rename = "sourceId")]
+    pub source_id: i64,
+    pub targets: Vec<SplitTarget>,
+    // 拆分后是否软删除原标签，原标签下的博客必须全部分配到目标标签
+    #[serde(default, rename = "deleteSource")]
+    pub delete_source: bool,
+}
+
+// 搜索索引设置，index 为空时使用博客索引
+#[derive(Deserialize, Debug)]
+pub struct SearchSettingsRequest {
+    pub index: Option<String>,
+}
+
+impl SearchSettingsRequest {
+    pub fn get_index(&self) -> String {
+        return self
+            .index
+            .to_owned()
+            .filter(|i| !i.trim().is_empty())
+            .unwrap_or(CONFIG.blog_search_index.to_owned());
+    }
+}
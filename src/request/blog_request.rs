@@ -4,9 +4,15 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use sqlx::types::chrono::{DateTime, Local};
 
-use crate::common::constants::default_page;
+use crate::common::constants::{
+    default_page, MAX_REFERRER_LENGTH, MAX_UTM_LENGTH, SEARCH_BLOG_MAX_PAGE_SIZE,
+    SEARCH_BLOG_PAGE_SIZE, SEARCH_CROP_LENGTH, SEARCH_HIGHLIGHT_POST_TAG, SEARCH_HIGHLIGHT_PRE_TAG,
+    SEARCH_HIGHLIGHT_CLASS_MAX_LENGTH, SEARCH_MAX_CROP_LENGTH,
+};
 use crate::common::date_format::time_stamp_to_date;
-use crate::common::is_image_url;
+use crate::common::{get_url_domain, is_image_url};
+use crate::conf::config::CONFIG;
+use crate::search::search_entity::SearchEntity;
 
 fn default_sort() -> Sort {
     return Sort::CREATE;
@@ -23,6 +29,9 @@ pub struct BlogFindRequest {
     pub cid: i64,
     #[serde(default = "default_sort")]
     pub sort: Sort,
+    // 为 true 时包含所有子分类下的博客
+    #[serde(default)]
+    pub children: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -75,6 +84,82 @@ impl ArchiveRangeRequest {
     }
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ViewRangeRequest {
+    pub start: i64,
+    pub end: i64,
+}
+
+// 博客浏览时携带的来源参数，前端是单页应用时 Referer 是本站页面，需要通过 ref 传入 document.referrer
+#[derive(Deserialize, Debug, Default)]
+pub struct ViewSourceQuery {
+    #[serde(rename = "ref")]
+    pub referrer: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub utm_term: Option<String>,
+    pub utm_content: Option<String>,
+}
+
+// 归一化后的访问来源
+#[derive(Debug, Default)]
+pub struct ViewSource {
+    pub referrer: Option<String>,
+    pub domain: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub utm_term: Option<String>,
+    pub utm_content: Option<String>,
+}
+
+impl ViewSource {
+    // 优先使用 ref 参数，其次是 Referer 请求头，来自本站的来源视为直接访问
+    pub fn new(query: ViewSourceQuery, referer_header: Option<&str>) -> ViewSource {
+        let referrer = query
+            .referrer
+            .as_deref()
+            .filter(|r| !r.trim().is_empty())
+            .or(referer_header)
+            .map(|r| r.trim().to_string());
+
+        let domain = referrer.as_deref().and_then(get_url_domain).filter(|d| {
+            !CONFIG
+                .origin
+                .to_url_vec()
+                .iter()
+                .any(|url| get_url_domain(url).as_deref() == Some(d.as_str()))
+        });
+
+        ViewSource {
+            referrer: if domain.is_some() {
+                referrer.map(|r| truncate(&r, MAX_REFERRER_LENGTH))
+            } else {
+                None
+            },
+            domain: domain.map(|d| truncate(&d, MAX_REFERRER_LENGTH)),
+            utm_source: normalize_utm(query.utm_source),
+            utm_medium: normalize_utm(query.utm_medium),
+            utm_campaign: normalize_utm(query.utm_campaign),
+            utm_term: normalize_utm(query.utm_term),
+            utm_content: normalize_utm(query.utm_content),
+        }
+    }
+}
+
+fn normalize_utm(value: Option<String>) -> Option<String> {
+    let value = value?.trim().to_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+    return Some(truncate(&value, MAX_UTM_LENGTH));
+}
+
+fn truncate(value: &str, max_length: usize) -> String {
+    return value.chars().take(max_length).collect();
+}
+
 #[derive(Deserialize, Debug)]
 pub struct GetUserBlogRequest {
     #[serde(default = "default_page")]
@@ -87,7 +172,178 @@ pub struct GetUserBlogRequest {
 pub struct SearchQueryRequest {
     #[serde(default = "default_page")]
     pub page: i64,
+    #[serde(default)]
     pub keyword: String,
+    #[serde(rename = "categoryId")]
+    pub category_id: Option<i64>,
+    pub tag: Option<String>,
+    // 限定在某个专题、分类或作者中搜索，作者也可以用 userId 传入
+    #[serde(rename = "topicId")]
+    pub topic_id: Option<i64>,
+    #[serde(rename = "authorId", alias = "userId")]
+    pub author_id: Option<i64>,
+    // 发布时间范围，毫秒时间戳
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    #[serde(default)]
+    pub sort: SearchSort,
+    // 每页数量，限制在 1 到 SEARCH_BLOG_MAX_PAGE_SIZE 之间
+    pub size: Option<i64>,
+    // 自定义高亮标签，需要同时设置，只允许 <b>、<em>、<mark>、<strong> 和带 class 的 <span>，否则使用默认标签
+    #[serde(rename = "highlightPreTag")]
+    pub highlight_pre_tag: Option<String>,
+    #[serde(rename = "highlightPostTag")]
+    pub highlight_post_tag: Option<String>,
+    // 正文片段长度
+    #[serde(rename = "cropLength")]
+    pub crop_length: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SuggestRequest {
+    #[serde(default)]
+    pub keyword: String,
+}
+
+// 搜索结果排序方式，默认按相关度
+#[derive(Deserialize, Debug, Serialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    Newest,
+    Views,
+}
+
+impl SearchQueryRequest {
+    // 只按关键字搜索第一页
+    pub fn from_keyword(keyword: &str) -> Self {
+        SearchQueryRequest {
+            page: 1,
+            keyword: keyword.to_owned(),
+            category_id: None,
+            tag: None,
+            topic_id: None,
+            author_id: None,
+            start: None,
+            end: None,
+            sort: SearchSort::Relevance,
+            size: None,
+            highlight_pre_tag: None,
+            highlight_post_tag: None,
+            crop_length: None,
+        }
+    }
+
+    pub fn get_page(&self) -> i64 {
+        return self.page.max(1);
+    }
+
+    pub fn get_size(&self) -> i64 {
+        return self
+            .size
+            .unwrap_or(SEARCH_BLOG_PAGE_SIZE)
+            .clamp(1, SEARCH_BLOG_MAX_PAGE_SIZE);
+    }
+
+    pub fn get_offset(&self) -> i64 {
+        return (self.get_page() - 1).saturating_mul(self.get_size());
+    }
+
+    pub fn get_crop_length(&self) -> i64 {
+        return self
+            .crop_length
+            .unwrap_or(SEARCH_CROP_LENGTH)
+            .clamp(1, SEARCH_MAX_CROP_LENGTH);
+    }
+
+    // 返回高亮的开始和结束标签，自定义标签不在白名单中或者没有正确闭合时使用默认标签
+    pub fn get_highlight_tags(&self) -> (String, String) {
+        return match (&self.highlight_pre_tag, &self.highlight_post_tag) {
+            (Some(pre), Some(post)) if highlight_close_tag(pre) == Some(post.as_str()) => {
+                (pre.to_owned(), post.to_owned())
+            }
+            _ => (
+                SEARCH_HIGHLIGHT_PRE_TAG.to_string(),
+                SEARCH_HIGHLIGHT_POST_TAG.to_string(),
+            ),
+        };
+    }
+
+    // 转换为 MeiliSearch 的过滤表达式，没有过滤条件时返回 None
+    pub fn to_filter(&self) -> Option<String> {
+        let mut filters = vec![];
+        if let Some(topic_id) = self.topic_id {
+            filters.push(format!("topicId = {}", topic_id));
+        }
+        if let Some(category_id) = self.category_id {
+            filters.push(format!("categoryId = {}", category_id));
+        }
+        if let Some(tag) = self.tag.as_ref().filter(|t| !t.trim().is_empty()) {
+            filters.push(format!(
+                "tags = \"{}\"",
+                tag.trim().replace('\\', "\\\\").replace('"', "\\\"")
+            ));
+        }
+        if let Some(author_id) = self.author_id {
+            filters.push(format!("authorId = {}", author_id));
+        }
+        if let Some(start) = self.start {
+            filters.push(format!("createAt >= {}", start));
+        }
+        if let Some(end) = self.end {
+            filters.push(format!("createAt <= {}", end));
+        }
+        return if filters.is_empty() {
+            None
+        } else {
+            Some(filters.join(" AND "))
+        };
+    }
+
+    // 搜索范围，依次为专题、分类和作者
+    pub fn get_scope(&self) -> Vec<(SearchEntity, i64)> {
+        return [
+            (SearchEntity::Topic, self.topic_id),
+            (SearchEntity::Category, self.category_id),
+            (SearchEntity::Author, self.author_id),
+        ]
+        .into_iter()
+        .filter_map(|(entity, id)| id.map(|id| (entity, id)))
+        .collect();
+    }
+
+    // 转换为 MeiliSearch 的排序参数
+    pub fn to_sort(&self) -> Vec<String> {
+        return match self.sort {
+            SearchSort::Relevance => vec![],
+            SearchSort::Newest => vec![String::from("createAt:desc")],
+            SearchSort::Views => vec![String::from("views:desc")],
+        };
+    }
+}
+
+// 允许的高亮标签只有 <b>、<em>、<mark>、<strong> 和带 class 的 <span>，返回对应的结束标签
+fn highlight_close_tag(pre: &str) -> Option<&'static str> {
+    return match pre {
+        "<b>" => Some("</b>"),
+        "<em>" => Some("</em>"),
+        "<mark>" => Some("</mark>"),
+        "<strong>" => Some("</strong>"),
+        _ => {
+            let class = pre.strip_prefix("<span class=\"")?.strip_suffix("\">")?;
+            let valid = !class.is_empty()
+                && class.len() <= SEARCH_HIGHLIGHT_CLASS_MAX_LENGTH
+                && class
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if valid {
+                Some("</span>")
+            } else {
+                None
+            }
+        }
+    };
 }
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
@@ -129,3 +385,87 @@ impl BlogRequest {
         return None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_filter_empty() {
+        let req = SearchQueryRequest::from_keyword("rust");
+        assert_eq!(req.to_filter(), None);
+    }
+
+    #[test]
+    fn to_filter_joins_conditions() {
+        let mut req = SearchQueryRequest::from_keyword("rust");
+        req.topic_id = Some(1);
+        req.category_id = Some(2);
+        req.author_id = Some(3);
+        req.start = Some(1000);
+        req.end = Some(2000);
+        assert_eq!(
+            req.to_filter(),
+            Some(String::from(
+                "topicId = 1 AND categoryId = 2 AND authorId = 3 AND createAt >= 1000 AND createAt <= 2000"
+            ))
+        );
+    }
+
+    #[test]
+    fn to_filter_escapes_tag() {
+        let mut req = SearchQueryRequest::from_keyword("rust");
+        req.tag = Some(String::from(r#" a"b\c OR x = 1 "#));
+        assert_eq!(
+            req.to_filter(),
+            Some(String::from(r#"tags = "a\"b\\c OR x = 1""#))
+        );
+
+        req.tag = Some(String::from("  "));
+        assert_eq!(req.to_filter(), None);
+    }
+
+    #[test]
+    fn highlight_tags_whitelist() {
+        let mut req = SearchQueryRequest::from_keyword("rust");
+        let tags = |pre: &str, post: &str, req: &mut SearchQueryRequest| {
+            req.highlight_pre_tag = Some(pre.to_string());
+            req.highlight_post_tag = Some(post.to_string());
+            req.get_highlight_tags()
+        };
+        let default = (String::from("<b>"), String::from("</b>"));
+        assert_eq!(
+            tags("<mark>", "</mark>", &mut req),
+            (String::from("<mark>"), String::from("</mark>"))
+        );
+        assert_eq!(
+            tags("<span class=\"hl-1\">", "</span>", &mut req),
+            (String::from("<span class=\"hl-1\">"), String::from("</span>"))
+        );
+        assert_eq!(tags("<em>", "</b>", &mut req), default);
+        assert_eq!(tags("<img src=x onerror=alert(1)>", "", &mut req), default);
+        assert_eq!(tags("<span class=\"a\" onclick=\"x\">", "</span>", &mut req), default);
+        assert_eq!(tags("<span class=\"a b\">", "</span>", &mut req), default);
+        assert_eq!(tags("<script>", "</script>", &mut req), default);
+    }
+
+    #[test]
+    fn offset_does_not_overflow() {
+        let mut req = SearchQueryRequest::from_keyword("rust");
+        req.page = i64::MAX;
+        req.size = Some(50);
+        assert_eq!(req.get_offset(), i64::MAX);
+        req.page = 3;
+        assert_eq!(req.get_offset(), 100);
+    }
+
+    #[test]
+    fn to_sort() {
+        let mut req = SearchQueryRequest::from_keyword("rust");
+        assert!(req.to_sort().is_empty());
+        req.sort = SearchSort::Newest;
+        assert_eq!(req.to_sort(), vec!["createAt:desc"]);
+        req.sort = SearchSort::Views;
+        assert_eq!(req.to_sort(), vec!["views:desc"]);
+    }
+}
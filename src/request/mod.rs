@@ -1,4 +1,4 @@
 pub mod admin_request;
 pub mod blog_request;
-pub mod email_request;
+pub mod newsletter_request;
 pub(crate) mod user_request;
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+use crate::common::is_valid_email;
+
+#[derive(Deserialize, Debug)]
+pub struct SubscribeRequest {
+    pub email: String,
+    // 订阅的分类和专题，都为空时订阅全部博客
+    #[serde(default)]
+    pub categories: Vec<i64>,
+    #[serde(default)]
+    pub topics: Vec<i64>,
+}
+
+impl SubscribeRequest {
+    pub fn check(&self) -> Option<String> {
+        if self.email.is_empty() || !is_valid_email(&self.email) {
+            return Some("错误的邮箱格式".to_string());
+        } else if self.categories.len() > 50 || self.topics.len() > 50 {
+            return Some("订阅的分类或专题太多".to_string());
+        }
+        return None;
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewsletterTokenRequest {
+    pub token: String,
+}
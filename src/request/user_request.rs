@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 use crate::common::{is_image_url, is_valid_email};
+use crate::common::constants::NICK_NAME_MAX_LENGTH;
+use crate::conf::config::CONFIG;
+use crate::conf::password_config::PasswordVerify;
 use crate::models::user::UserVo;
 
 #[derive(Deserialize, Serialize)]
@@ -13,12 +16,22 @@ impl UserRequest {
     pub fn check(&self) -> bool {
         !self.username.trim().is_empty() && !self.password.trim().is_empty()
     }
-    pub async fn check_user(&self, user: &UserVo) -> bool {
-        let md5_password = format!("{:x}", &md5::compute(&self.password));
-        user.username == self.username && md5_password == user.password
+    // 计算量较大，需要在阻塞线程中调用
+    pub fn check_user(&self, user: &UserVo) -> PasswordVerify {
+        if user.username != self.username {
+            return PasswordVerify::Invalid;
+        }
+        return CONFIG.password.verify(&self.password, &user.password);
     }
 }
 
+// 使用刷新令牌换取新的访问令牌
+#[derive(Deserialize, Debug)]
+pub struct RefreshTokenRequest {
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct UserRegisteredRequest {
     #[serde(rename = "username")]
@@ -41,8 +54,8 @@ impl UserRegisteredRequest {
             return Some(String::from("账号要大于8个并且小于16个字符"));
         } else if self.nick_name.is_empty() {
             return Some(String::from("用户名称不能为空"));
-        } else if self.password.len() < 8 || self.password.len() > 16 {
-            return Some(String::from("密码要大于8个并且小于16个字符"));
+        } else if let Some(e) = check_password(&self.password) {
+            return Some(e);
         } else if self.email.is_empty() || !is_valid_email(&self.email) {
             return Some(String::from("不正确的邮箱格式"));
         } else if !is_image_url(&self.icon) {
@@ -54,6 +67,104 @@ impl UserRegisteredRequest {
     }
 }
 
+// 注册、重置和修改密码时新密码的校验
+pub fn check_password(password: &String) -> Option<String> {
+    if password.len() < 8 || password.len() > 16 {
+        return Some(String::from("密码要大于8个并且小于16个字符"));
+    }
+    return None;
+}
+
+// 通过邮件验证码重置密码
+#[derive(Deserialize, Debug)]
+pub struct PasswordResetRequest {
+    pub email: String,
+    pub code: String,
+    pub password: String,
+}
+
+impl PasswordResetRequest {
+    pub fn check(&self) -> Option<String> {
+        if !is_valid_email(&self.email) {
+            return Some(String::from("不正确的邮箱格式"));
+        } else if self.code.is_empty() {
+            return Some(String::from("验证码不能为空"));
+        }
+        return check_password(&self.password);
+    }
+}
+
+// 修改个人资料，不传的字段保持不变，头像先通过头像上传接口获取地址
+#[derive(Deserialize, Debug)]
+pub struct ProfileRequest {
+    #[serde(rename = "nickName")]
+    pub nick_name: Option<String>,
+    pub icon: Option<String>,
+}
+
+impl ProfileRequest {
+    pub fn check(&mut self) -> Option<String> {
+        self.nick_name = self.nick_name.as_ref().map(|n| n.trim().to_string());
+        if self.nick_name.is_none() && self.icon.is_none() {
+            return Some(String::from("没有需要修改的内容"));
+        }
+        if let Some(nick_name) = &self.nick_name {
+            if nick_name.is_empty() {
+                return Some(String::from("用户名称不能为空"));
+            } else if nick_name.chars().count() > NICK_NAME_MAX_LENGTH {
+                return Some(format!("用户名称不能超过{}个字符", NICK_NAME_MAX_LENGTH));
+            }
+        }
+        if let Some(icon) = &self.icon {
+            if !is_image_url(icon) {
+                return Some(String::from("不正确的图片格式"));
+            }
+        }
+        return None;
+    }
+}
+
+// 修改密码，需要提供旧密码
+#[derive(Deserialize, Debug)]
+pub struct ChangePasswordRequest {
+    #[serde(rename = "oldPassword")]
+    pub old_password: String,
+    pub password: String,
+}
+
+impl ChangePasswordRequest {
+    pub fn check(&self) -> Option<String> {
+        if self.old_password.is_empty() {
+            return Some(String::from("旧密码不能为空"));
+        } else if self.old_password == self.password {
+            return Some(String::from("新密码不能和旧密码相同"));
+        }
+        return check_password(&self.password);
+    }
+}
+
+// 修改邮箱，验证码发送到新邮箱，同时需要验证当前密码
+#[derive(Deserialize, Debug)]
+pub struct ChangeEmailRequest {
+    #[serde(rename = "oldPassword")]
+    pub old_password: String,
+    pub email: String,
+    pub code: String,
+}
+
+impl ChangeEmailRequest {
+    pub fn check(&self) -> Option<String> {
+        if self.old_password.is_empty() {
+            return Some(String::from("密码不能为空"));
+        } else if !is_valid_email(&self.email) {
+            return Some(String::from("不正确的邮箱格式"));
+        } else if self.code.is_empty() {
+            return Some(String::from("验证码不能为空"));
+        }
+        return None;
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ContactRequest {
     pub name: String,
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// 后台统计面板信息
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DashboardInfo {
+    pub start: String,
+    pub end: String,
+    #[serde(rename = "topBlogs")]
+    pub top_blogs: Vec<TopBlogVo>,
+    pub daily: Vec<DailyVisitVo>,
+    pub regions: Vec<NameCountVo>,
+    pub platforms: Vec<NameCountVo>,
+    #[serde(rename = "monthlyPosts")]
+    pub monthly_posts: Vec<NameCountVo>,
+    pub storage: StorageInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TopBlogVo {
+    pub id: i64,
+    pub title: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct DailyVisitVo {
+    pub date: String,
+    pub views: i64,
+    pub visitors: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct NameCountVo {
+    pub name: String,
+    pub count: i64,
+}
+
+// 访问来源报表，blogId 为空时统计全站
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReferrerReport {
+    pub start: String,
+    pub end: String,
+    #[serde(rename = "blogId")]
+    pub blog_id: Option<i64>,
+    // 独立访客总数和没有外部来源的直接访问数
+    pub visitors: i64,
+    pub direct: i64,
+    pub referrers: Vec<NameCountVo>,
+    pub sources: Vec<NameCountVo>,
+    pub mediums: Vec<NameCountVo>,
+    pub campaigns: Vec<NameCountVo>,
+}
+
+// 上传文件占用的存储空间
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StorageInfo {
+    pub total: u64,
+    #[serde(rename = "totalStr")]
+    pub total_str: String,
+    pub items: Vec<StorageItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageItem {
+    pub name: String,
+    pub count: u64,
+    pub size: u64,
+    #[serde(rename = "sizeStr")]
+    pub size_str: String,
+}
@@ -1,3 +1,9 @@
+pub mod dashboard_info;
+
 pub(crate) mod page_info;
 
+pub mod search_info;
+
 pub mod website_info;
+
+pub mod user_info;
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::models::blogs::SimpleBlogVo;
+use crate::models::tag::TagVo;
+use crate::models::topic::SimpleTopicVo;
+use crate::search::meillsearch_response::{EntityHits, Hits};
+use crate::search::search_entity::SearchEntity;
+
+// 搜索索引和数据库对比修复的结果
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SearchReconcileReport {
+    // 数据库中未删除的博客数量
+    pub blogs: usize,
+    // 修复前索引中的文档数量
+    pub indexed: usize,
+    // 索引中缺少并重新添加的文档数量
+    pub added: usize,
+    // 内容和数据库不一致并重新添加的文档数量
+    pub updated: usize,
+    // 博客已删除但仍在索引中的文档数量
+    pub removed: usize,
+    // 专题、标签、分类和作者索引的对比结果
+    pub entities: Vec<SearchIndexReconcile>,
+}
+
+// 单个索引的对比结果
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SearchIndexReconcile {
+    pub index: String,
+    pub documents: usize,
+    pub indexed: usize,
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+// 搜索范围，type 为 topic、category 或 author
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchScope {
+    #[serde(rename = "type")]
+    pub entity: SearchEntity,
+    pub id: i64,
+    pub name: String,
+}
+
+// 专题、标签、分类和作者的搜索文档
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct SearchEntityVo {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub image: String,
+}
+
+// 全站搜索结果，按实体类型分组
+#[derive(Debug, Serialize, Default)]
+pub struct SearchAllResult {
+    pub blogs: Vec<Hits>,
+    pub topics: Vec<EntityHits>,
+    pub tags: Vec<EntityHits>,
+    pub categories: Vec<EntityHits>,
+    pub authors: Vec<EntityHits>,
+}
+
+impl SearchAllResult {
+    pub fn group_mut(&mut self, entity: SearchEntity) -> &mut Vec<EntityHits> {
+        return match entity {
+            SearchEntity::Topic => &mut self.topics,
+            SearchEntity::Tag => &mut self.tags,
+            SearchEntity::Category => &mut self.categories,
+            SearchEntity::Author => &mut self.authors,
+        };
+    }
+}
+
+// 初始化搜索索引的结果，任务都执行完成后才返回
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SearchInitReport {
+    // 写入博客索引的文档数量
+    pub documents: usize,
+    // 写入专题、标签、分类和作者索引的文档数量
+    pub entities: usize,
+    // 写入文档的异步任务 uid，PostgreSQL 搜索没有异步任务
+    #[serde(rename = "taskUid")]
+    pub task_uid: Option<u64>,
+}
+
+// 搜索建议，包含匹配的博客标题、标签和专题
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SearchSuggestion {
+    pub titles: Vec<SimpleBlogVo>,
+    pub tags: Vec<TagVo>,
+    pub topics: Vec<SimpleTopicVo>,
+}
+
+// 搜索关键字报表，只统计第一页的搜索，翻页不重复计数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchQueryReport {
+    pub start: String,
+    pub end: String,
+    pub total: i64,
+    #[serde(rename = "zeroResults")]
+    pub zero_results: i64,
+    #[serde(rename = "topQueries")]
+    pub top_queries: Vec<SearchQueryStat>,
+    #[serde(rename = "zeroResultQueries")]
+    pub zero_result_queries: Vec<SearchQueryStat>,
+    pub daily: Vec<SearchDailyStat>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SearchQueryStat {
+    pub keyword: String,
+    pub count: i64,
+    // 最近一次搜索的结果数量
+    pub results: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SearchDailyStat {
+    pub date: String,
+    pub count: i64,
+    #[serde(rename = "zeroResults")]
+    pub zero_results: i64,
+}
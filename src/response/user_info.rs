@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+// 密码哈希迁移进度，legacy 为仍在使用无盐 MD5 的账号数量
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct PasswordHashReport {
+    pub total: i64,
+    // 使用当前 Argon2id 参数的账号
+    pub current: i64,
+    // Argon2id 参数和当前配置不同的账号，下次登录时重新计算
+    pub outdated: i64,
+    pub legacy: i64,
+}
+
+// 登录和刷新令牌的返回结果，expiresIn 为访问令牌的有效秒数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginToken {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+}
@@ -42,6 +42,9 @@ pub fn admin_router(conf: &mut web::ServiceConfig) {
         .service(controller::admin_controller::un_delete_tag_by_id)
         .service(controller::admin_controller::update_category)
         .service(controller::admin_controller::update_tag)
+        .service(controller::admin_controller::merge_tags)
+        .service(controller::admin_controller::rename_tag)
+        .service(controller::admin_controller::split_tag)
         .service(controller::admin_controller::get_topic_list)
         .service(controller::admin_controller::get_topic_current_list)
         .service(controller::admin_controller::update_topic)
@@ -50,6 +53,7 @@ pub fn admin_router(conf: &mut web::ServiceConfig) {
         .service(controller::admin_controller::set_recommend_blog)
         .service(controller::admin_controller::set_web_site_info)
         .service(controller::admin_controller::update_role)
+        .service(controller::admin_controller::get_password_hash_report)
         .service(controller::admin_controller::set_gpt_token)
         .service(controller::admin_controller::delete_file_by_id)
         .service(controller::admin_controller::delete_file_by_ids)
@@ -58,9 +62,34 @@ pub fn admin_router(conf: &mut web::ServiceConfig) {
         .service(controller::admin_controller::un_delete_topic_by_id)
         .service(controller::admin_controller::un_delete_topic_by_ids)
         .service(controller::admin_controller::init_search_blog)
+        .service(controller::admin_controller::reconcile_search)
+        .service(controller::admin_controller::get_search_task)
+        .service(controller::admin_controller::get_search_settings)
+        .service(controller::admin_controller::reset_search_settings)
+        .service(controller::admin_controller::update_search_settings)
+        .service(controller::admin_controller::get_search_synonyms)
+        .service(controller::admin_controller::update_search_synonyms)
+        .service(controller::admin_controller::get_search_report)
         .service(controller::admin_controller::init_latest_blog)
         .service(controller::admin_controller::init_blog_count)
-        .service(controller::admin_controller::get_log_info);
+        .service(controller::admin_controller::get_log_info)
+        .service(controller::admin_controller::get_dashboard)
+        .service(controller::admin_controller::get_referrer_report)
+        .service(controller::admin_controller::get_webhook_list)
+        .service(controller::admin_controller::add_webhook)
+        .service(controller::admin_controller::update_webhook)
+        .service(controller::admin_controller::delete_webhook)
+        .service(controller::admin_controller::ping_webhook)
+        .service(controller::admin_controller::get_webhook_deliveries)
+        .service(controller::admin_controller::retry_webhook_delivery)
+        .service(controller::admin_controller::get_contact_messages)
+        .service(controller::admin_controller::update_contact_read)
+        .service(controller::admin_controller::update_contact_archived)
+        .service(controller::admin_controller::delete_contact_message)
+        .service(controller::admin_controller::batch_delete_contact_messages)
+        .service(controller::admin_controller::get_email_queue)
+        .service(controller::admin_controller::retry_email)
+        .service(controller::admin_controller::delete_email);
     conf.service(scope);
 }
 
@@ -93,10 +122,20 @@ pub fn user_router(conf: &mut web::ServiceConfig) {
         .service(controller::user_controller::get_user_by_id)
         .service(controller::user_controller::login)
         .service(controller::user_controller::send_email_for_code)
+        .service(controller::user_controller::send_password_reset_mail)
+        .service(controller::user_controller::reset_password)
         .service(controller::user_controller::registered_user)
         .service(controller::user_controller::contact_me)
         .service(controller::user_controller::get_web_site_info)
         .service(controller::user_controller::logout)
+        .service(controller::user_controller::refresh_token)
+        .service(controller::user_controller::get_sessions)
+        .service(controller::user_controller::revoke_session)
+        .service(controller::user_controller::revoke_all_sessions)
+        .service(controller::user_controller::update_profile)
+        .service(controller::user_controller::change_password)
+        .service(controller::user_controller::send_email_change_mail)
+        .service(controller::user_controller::change_email)
         .service(controller::user_controller::is_cn)
         .service(controller::user_controller::chat_gpt);
     conf.service(scope);
@@ -105,6 +144,7 @@ pub fn user_router(conf: &mut web::ServiceConfig) {
 pub fn blog_router(conf: &mut web::ServiceConfig) {
     let scope = web::scope("blog")
         .service(controller::blog_controller::get_blog_info_by_id)
+        .service(controller::blog_controller::get_blog_daily_views)
         .service(controller::blog_controller::get_blog_by_category_list)
         .service(controller::blog_controller::get_hot_blogs_list)
         .service(controller::blog_controller::get_range_blog_list)
@@ -113,6 +153,8 @@ pub fn blog_router(conf: &mut web::ServiceConfig) {
         .service(controller::blog_controller::get_user_top_blog)
         .service(controller::blog_controller::create_search_index)
         .service(controller::blog_controller::search_blog_list)
+        .service(controller::blog_controller::get_search_suggest)
+        .service(controller::blog_controller::search_all)
         .service(controller::blog_controller::init_search_blog)
         .service(controller::blog_controller::get_similar_blog)
         .service(controller::blog_controller::get_recommend_blog)
@@ -123,3 +165,20 @@ pub fn blog_router(conf: &mut web::ServiceConfig) {
         .service(controller::blog_controller::get_save_edit_blog_content);
     conf.service(scope);
 }
+
+pub fn webmention_router(conf: &mut web::ServiceConfig) {
+    let scope = web::scope("webmention")
+        .service(controller::webmention_controller::receive_webmention)
+        .service(controller::webmention_controller::get_blog_webmentions);
+    conf.service(scope);
+}
+
+pub fn newsletter_router(conf: &mut web::ServiceConfig) {
+    let scope = web::scope("newsletter")
+        .service(controller::newsletter_controller::subscribe)
+        .service(controller::newsletter_controller::confirm_page)
+        .service(controller::newsletter_controller::confirm_subscribe)
+        .service(controller::newsletter_controller::unsubscribe_page)
+        .service(controller::newsletter_controller::unsubscribe);
+    conf.service(scope);
+}
@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use log::warn;
+use serde_json::Value;
+
+use crate::error::custom_error::{E, Status};
+use crate::request::blog_request::SearchQueryRequest;
+use crate::search::meilisearch_request::IndexSettings;
+use crate::search::meillsearch_response::{
+    EntitySearchResponse, SearchResponse, SuggestHits, SuggestResponse, Task,
+};
+
+// 搜索后端，MeiliSearch 和 PostgreSQL 全文搜索都实现它，返回相同的搜索结果格式
+pub trait SearchBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // 写入操作返回异步任务的 uid，同步完成写入的后端返回 None
+    fn create_index<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>>;
+
+    fn delete_all_documents<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>>;
+
+    // 添加或更新文档，documents 为单个文档或文档数组
+    fn save_documents<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Value,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>>;
+
+    fn delete_documents<'a>(
+        &'a self,
+        index: &'a str,
+        ids: Vec<i64>,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>>;
+
+    // 获取索引中所有文档的指定字段，用于和数据库对比
+    fn get_documents<'a>(
+        &'a self,
+        index: &'a str,
+        fields: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<Vec<Value>, E>>;
+
+    fn search_documents<'a>(
+        &'a self,
+        index: &'a str,
+        req: &'a SearchQueryRequest,
+    ) -> BoxFuture<'a, Result<SearchResponse, E>>;
+
+    // 搜索建议只需要博客标题，默认使用普通搜索的结果
+    fn suggest_documents<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<SuggestResponse, E>> {
+        Box::pin(async move {
+            let mut req = SearchQueryRequest::from_keyword(keyword);
+            req.size = Some(limit);
+            let response = self.search_documents(index, &req).await?;
+            let hits = response
+                .hits
+                .into_iter()
+                .map(|h| SuggestHits {
+                    id: h.id,
+                    title: h.title,
+                    _formatted: h._formatted,
+                })
+                .collect();
+            Ok(SuggestResponse { hits })
+        })
+    }
+
+    // 按名称和描述搜索专题、标签、分类和作者
+    fn search_entities<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<EntitySearchResponse, E>>;
+
+    // 索引设置只有 MeiliSearch 支持
+    fn get_settings<'a>(&'a self, _index: &'a str) -> BoxFuture<'a, Result<IndexSettings, E>> {
+        Box::pin(async move { Err(unsupported_settings(self.name())) })
+    }
+
+    fn update_settings<'a>(
+        &'a self,
+        _index: &'a str,
+        _settings: &'a IndexSettings,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move { Err(unsupported_settings(self.name())) })
+    }
+
+    // 查询异步任务的状态，只有 MeiliSearch 有异步任务
+    fn get_task<'a>(&'a self, _task_uid: u64) -> BoxFuture<'a, Result<Task, E>> {
+        Box::pin(async move {
+            Err(E::error(
+                Status::QUERY_OR_PARAMS_ERROR,
+                format!("搜索后端 {} 没有异步任务", self.name()),
+            ))
+        })
+    }
+
+    // 等待异步任务执行完成，任务失败时返回错误，没有异步任务的后端直接返回
+    fn wait_for_task<'a>(&'a self, _task_uid: u64) -> BoxFuture<'a, Result<(), E>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+// 搜索服务不可用或索引不支持过滤条件时才改用备用后端或数据库，其他错误（例如参数错误）直接返回
+pub fn should_fallback(e: &E) -> bool {
+    return e.code == Status::SEARCH_UNAVAILABLE_ERROR || e.code == Status::SEARCH_FILTER_ERROR;
+}
+
+fn unsupported_settings(name: &str) -> E {
+    E::error(
+        Status::QUERY_OR_PARAMS_ERROR,
+        format!("搜索后端 {} 不支持索引设置", name),
+    )
+}
+
+// 主后端不可用或不支持过滤条件时使用备用后端搜索，写入同时发给两个后端，保证备用后端的数据是最新的
+pub struct FallbackSearch {
+    primary: Arc<dyn SearchBackend>,
+    fallback: Arc<dyn SearchBackend>,
+}
+
+impl FallbackSearch {
+    pub fn new(primary: Arc<dyn SearchBackend>, fallback: Arc<dyn SearchBackend>) -> Self {
+        FallbackSearch { primary, fallback }
+    }
+}
+
+impl SearchBackend for FallbackSearch {
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+
+    fn create_index<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let result = self.primary.create_index(index).await;
+            let _ = self.fallback.create_index(index).await;
+            result
+        })
+    }
+
+    fn delete_all_documents<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let result = self.primary.delete_all_documents(index).await;
+            if let Err(e) = self.fallback.delete_all_documents(index).await {
+                warn!("{} 删除所有文档失败: {}", self.fallback.name(), e.message);
+            }
+            result
+        })
+    }
+
+    fn save_documents<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Value,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            if let Err(e) = self.fallback.save_documents(index, documents.clone()).await {
+                warn!("{} 添加文档失败: {}", self.fallback.name(), e.message);
+            }
+            self.primary.save_documents(index, documents).await
+        })
+    }
+
+    fn delete_documents<'a>(
+        &'a self,
+        index: &'a str,
+        ids: Vec<i64>,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            if let Err(e) = self.fallback.delete_documents(index, ids.clone()).await {
+                warn!("{} 删除文档失败: {}", self.fallback.name(), e.message);
+            }
+            self.primary.delete_documents(index, ids).await
+        })
+    }
+
+    fn get_documents<'a>(
+        &'a self,
+        index: &'a str,
+        fields: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<Vec<Value>, E>> {
+        self.primary.get_documents(index, fields)
+    }
+
+    fn get_settings<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<IndexSettings, E>> {
+        self.primary.get_settings(index)
+    }
+
+    fn update_settings<'a>(
+        &'a self,
+        index: &'a str,
+        settings: &'a IndexSettings,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        self.primary.update_settings(index, settings)
+    }
+
+    // 返回的任务 uid 都来自主后端
+    fn get_task<'a>(&'a self, task_uid: u64) -> BoxFuture<'a, Result<Task, E>> {
+        self.primary.get_task(task_uid)
+    }
+
+    fn wait_for_task<'a>(&'a self, task_uid: u64) -> BoxFuture<'a, Result<(), E>> {
+        self.primary.wait_for_task(task_uid)
+    }
+
+    fn search_documents<'a>(
+        &'a self,
+        index: &'a str,
+        req: &'a SearchQueryRequest,
+    ) -> BoxFuture<'a, Result<SearchResponse, E>> {
+        Box::pin(async move {
+            match self.primary.search_documents(index, req).await {
+                Err(e) if should_fallback(&e) => {
+                    warn!(
+                        "{} 搜索失败，改用 {} 搜索: {}",
+                        self.primary.name(),
+                        self.fallback.name(),
+                        e.message
+                    );
+                    self.fallback.search_documents(index, req).await
+                }
+                r => r,
+            }
+        })
+    }
+
+    fn suggest_documents<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<SuggestResponse, E>> {
+        Box::pin(async move {
+            match self.primary.suggest_documents(index, keyword, limit).await {
+                Err(e) if should_fallback(&e) => {
+                    warn!(
+                        "{} 搜索失败，改用 {} 搜索: {}",
+                        self.primary.name(),
+                        self.fallback.name(),
+                        e.message
+                    );
+                    self.fallback.suggest_documents(index, keyword, limit).await
+                }
+                r => r,
+            }
+        })
+    }
+
+    fn search_entities<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<EntitySearchResponse, E>> {
+        Box::pin(async move {
+            match self.primary.search_entities(index, keyword, limit).await {
+                Err(e) if should_fallback(&e) => {
+                    warn!(
+                        "{} 搜索失败，改用 {} 搜索: {}",
+                        self.primary.name(),
+                        self.fallback.name(),
+                        e.message
+                    );
+                    self.fallback.search_entities(index, keyword, limit).await
+                }
+                r => r,
+            }
+        })
+    }
+}
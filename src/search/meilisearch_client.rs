@@ -1,19 +1,34 @@
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use log::error;
 use reqwest::{Client, Response};
 use reqwest::header::{HeaderMap, HeaderValue};
+use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
 
-use crate::common::constants::SEARCH_BLOG_PAGE_SIZE;
-use crate::error::custom_error::{E, Status};
+use crate::error::custom_error::E;
 use crate::request::blog_request::SearchQueryRequest;
-use crate::search::meillsearch_response::SearchResponse;
+use crate::search::backend::SearchBackend;
+use crate::search::meilisearch_error::{MeiliSearchApiError, MeiliSearchError};
+use crate::search::meilisearch_request::{IndexSettings, SearchQuery};
+use crate::search::meillsearch_response::{
+    EntitySearchResponse, SearchResponse, SuggestResponse, Task, TaskInfo,
+};
+
+// 轮询任务状态的间隔
+const TASK_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 pub struct MeiliSearchClient {
     uri: String,
-    headers: HeaderMap,
+    // reqwest::Client 内部带连接池，所有请求共用同一个
+    client: Client,
+    // 等待异步任务完成的最长时间
+    task_timeout: Duration,
 }
 
 impl MeiliSearchClient {
-    pub fn new(host: &str, api_key: &str) -> Self {
+    pub fn new(host: &str, api_key: &str, timeout: u64, task_timeout: u64) -> Self {
         let mut headers = HeaderMap::new();
         headers.insert(
             "Authorization",
@@ -23,23 +38,29 @@ impl MeiliSearchClient {
             "Content-Type",
             HeaderValue::from_static("application/json;charset=utf-8"),
         );
+        let client = Client::builder()
+            .default_headers(headers)
+            .connect_timeout(Duration::from_secs(timeout))
+            .timeout(Duration::from_secs(timeout))
+            .build()
+            .unwrap();
         MeiliSearchClient {
-            uri: host.to_string(),
-            headers,
+            uri: host.trim_end_matches('/').to_string(),
+            client,
+            task_timeout: Duration::from_secs(task_timeout),
         }
     }
 
+    // 发送请求，连接失败、超时和非 2xx 的响应都返回错误
     async fn send_request(
         &self,
         method: reqwest::Method,
         endpoint: &str,
         body: Option<Value>,
-    ) -> Result<Response, E> {
+    ) -> Result<Response, MeiliSearchError> {
         let url = format!("{}/{}", self.uri, endpoint);
 
-        let client = Client::new();
-
-        let request = client.request(method, &url).headers(self.headers.clone());
+        let request = self.client.request(method, &url);
 
         let request = if let Some(body) = body {
             request.json(&body)
@@ -47,104 +68,343 @@ impl MeiliSearchClient {
             request
         };
 
-        let response = request.send().await.unwrap();
+        // MeiliSearch 不可用时返回错误，由调用方决定是否改用其他搜索后端
+        let response = request.send().await.map_err(|e| {
+            error!("请求 MeiliSearch 失败 {}: {}", url, e);
+            MeiliSearchError::Request(e.to_string())
+        })?;
 
-        return Ok(response);
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let text = response.text().await.unwrap_or_default();
+        let api_error = serde_json::from_str::<MeiliSearchApiError>(&text).unwrap_or(
+            MeiliSearchApiError {
+                message: text,
+                code: String::from("unknown"),
+            },
+        );
+        let e = MeiliSearchError::Api {
+            status: status.as_u16(),
+            error: api_error,
+        };
+        error!("请求 MeiliSearch 失败 {}: {}", url, e);
+        return Err(e);
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+        body: Option<Value>,
+    ) -> Result<T, MeiliSearchError> {
+        let response = self.send_request(method, endpoint, body).await?;
+        return response.json::<T>().await.map_err(|e| {
+            error!("解析 MeiliSearch 返回内容失败 {}: {}", endpoint, e);
+            MeiliSearchError::Decode(e.to_string())
+        });
     }
 
-    pub async fn create_index(&self, index: &str) -> Option<E> {
+    // 写入操作都是异步任务，返回的任务 uid 可以用 get_task 或 wait_for_task 查询执行结果
+    async fn send_task(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+        body: Option<Value>,
+    ) -> Result<TaskInfo, MeiliSearchError> {
+        return self.send_json::<TaskInfo>(method, endpoint, body).await;
+    }
+
+    pub async fn get_task(&self, task_uid: u64) -> Result<Task, MeiliSearchError> {
+        let endpoint = format!("tasks/{}", task_uid);
+        return self
+            .send_json::<Task>(reqwest::Method::GET, &endpoint, None)
+            .await;
+    }
+
+    // 轮询任务状态直到结束，任务失败或取消时返回错误
+    pub async fn wait_for_task(
+        &self,
+        task_uid: u64,
+        timeout: Duration,
+    ) -> Result<Task, MeiliSearchError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let task = self.get_task(task_uid).await?;
+            if task.is_finished() {
+                if task.status == "succeeded" {
+                    return Ok(task);
+                }
+                let error = task.error.unwrap_or(MeiliSearchApiError {
+                    message: format!("任务状态为 {}", task.status),
+                    code: task.status,
+                });
+                return Err(MeiliSearchError::TaskFailed { task_uid, error });
+            }
+            if Instant::now() + TASK_POLL_INTERVAL > deadline {
+                return Err(MeiliSearchError::TaskTimeout(task_uid));
+            }
+            actix_web::rt::time::sleep(TASK_POLL_INTERVAL).await;
+        }
+    }
+
+    // 创建索引并应用默认设置，返回设置任务，索引已存在时返回 None
+    pub async fn create_index(&self, index: &str) -> Result<Option<TaskInfo>, MeiliSearchError> {
         let endpoint = "indexes";
         let payload = json!({
             "uid":index,
             "primaryKey":"id"
         });
-        match self
-            .send_request(reqwest::Method::POST, endpoint, Some(payload))
-            .await
-        {
-            Ok(_) => None,
-            Err(_) => Some(E::error(
-                Status::HTTP_REQUEST_ERROR,
-                String::from("创建索引失败"),
-            )),
+        let task = self
+            .send_task(reqwest::Method::POST, endpoint, Some(payload))
+            .await?;
+        // 索引已存在时创建任务会失败，这种情况不算错误，也不覆盖管理员修改过的设置
+        if let Err(e) = self.wait_for_task(task.task_uid, self.task_timeout).await {
+            return match e {
+                MeiliSearchError::TaskFailed { ref error, .. }
+                    if error.code == "index_already_exists" => Ok(None),
+                e => Err(e),
+            };
         }
+        // 新建的索引应用默认设置，过滤、排序和分面统计使用的属性需要在设置中声明
+        return self
+            .update_settings(index, &IndexSettings::default_for(index))
+            .await
+            .map(Some);
     }
 
-    pub async fn drop_index(&self, index: &str) -> Option<E> {
-        let endpoint = format!("indexes/{}", index);
-        match self
-            .send_request(reqwest::Method::DELETE, &endpoint, None)
-            .await
-        {
-            Ok(_) => None,
-            Err(_) => Some(E::error(
-                Status::HTTP_REQUEST_ERROR,
-                String::from("删除索引失败"),
-            )),
-        }
+    pub async fn get_settings(&self, index: &str) -> Result<IndexSettings, MeiliSearchError> {
+        let endpoint = format!("indexes/{}/settings", index);
+        return self
+            .send_json::<IndexSettings>(reqwest::Method::GET, &endpoint, None)
+            .await;
+    }
+
+    // 只更新 settings 中不为空的设置项
+    pub async fn update_settings(
+        &self,
+        index: &str,
+        settings: &IndexSettings,
+    ) -> Result<TaskInfo, MeiliSearchError> {
+        let endpoint = format!("indexes/{}/settings", index);
+        let body =
+            serde_json::to_value(settings).map_err(|e| MeiliSearchError::Decode(e.to_string()))?;
+        return self
+            .send_task(reqwest::Method::PATCH, &endpoint, Some(body))
+            .await;
     }
 
-    pub async fn delete_all_documents(&self, index: &str) -> Option<E> {
+    pub async fn delete_all_documents(&self, index: &str) -> Result<TaskInfo, MeiliSearchError> {
         let endpoint = format!("indexes/{}/documents", index);
-        match self
-            .send_request(reqwest::Method::DELETE, &endpoint, None)
-            .await
-        {
-            Ok(_) => None,
-            Err(_) => Some(E::error(
-                Status::HTTP_REQUEST_ERROR,
-                String::from("删除所有文档失败"),
-            )),
-        }
+        return self
+            .send_task(reqwest::Method::DELETE, &endpoint, None)
+            .await;
     }
 
-    pub async fn save_documents(&self, index: &str, document_json: Value) -> Option<E> {
+    pub async fn save_documents(
+        &self,
+        index: &str,
+        document_json: Value,
+    ) -> Result<TaskInfo, MeiliSearchError> {
         let endpoint = format!("indexes/{}/documents", index);
-        match self
-            .send_request(reqwest::Method::POST, &endpoint, Some(document_json))
-            .await
-        {
-            Ok(_) => None,
-            Err(_) => Some(E::error(
-                Status::HTTP_REQUEST_ERROR,
-                String::from("添加文档失败"),
-            )),
+        return self
+            .send_task(reqwest::Method::POST, &endpoint, Some(document_json))
+            .await;
+    }
+
+    pub async fn delete_documents(
+        &self,
+        index: &str,
+        ids: Vec<i64>,
+    ) -> Result<TaskInfo, MeiliSearchError> {
+        let endpoint = format!("indexes/{}/documents/delete-batch", index);
+        return self
+            .send_task(reqwest::Method::POST, &endpoint, Some(json!(ids)))
+            .await;
+    }
+
+    // 分页获取索引中的所有文档，只返回 fields 中的字段
+    pub async fn get_documents(
+        &self,
+        index: &str,
+        fields: &[&str],
+    ) -> Result<Vec<Value>, MeiliSearchError> {
+        let limit = 1000;
+        let mut offset = 0;
+        let mut documents = vec![];
+        loop {
+            let endpoint = format!(
+                "indexes/{}/documents?offset={}&limit={}&fields={}",
+                index,
+                offset,
+                limit,
+                fields.join(",")
+            );
+            let mut page = self
+                .send_json::<Value>(reqwest::Method::GET, &endpoint, None)
+                .await?;
+            let results = match page["results"].take() {
+                Value::Array(results) => results,
+                _ => vec![],
+            };
+            let count = results.len();
+            documents.extend(results);
+            if count < limit {
+                break;
+            }
+            offset += limit;
         }
+        return Ok(documents);
     }
 
     pub async fn search_documents(
         &self,
         index: &str,
         req: &SearchQueryRequest,
-    ) -> Option<SearchResponse> {
+    ) -> Result<SearchResponse, MeiliSearchError> {
         let endpoint = format!("indexes/{}/search", index);
-        let size = SEARCH_BLOG_PAGE_SIZE;
-
-        let search_request = json!({
-            "q": req.keyword,
-            "offset": (req.page - 1) * size,
-            "attributesToHighlight": ["*"],
-            "limit": size,
-            "showMatchesPosition": false,
-            "highlightPreTag": "<b>",
-            "highlightPostTag": "</b>"
-        });
+        let query = SearchQuery::from_request(req);
+        let body =
+            serde_json::to_value(&query).map_err(|e| MeiliSearchError::Decode(e.to_string()))?;
+        return self
+            .send_json::<SearchResponse>(reqwest::Method::POST, &endpoint, Some(body))
+            .await;
+    }
+
+    pub async fn suggest_documents(
+        &self,
+        index: &str,
+        keyword: &str,
+        limit: i64,
+    ) -> Result<SuggestResponse, MeiliSearchError> {
+        let endpoint = format!("indexes/{}/search", index);
+        let query = SearchQuery::for_suggest(keyword, limit);
+        let body =
+            serde_json::to_value(&query).map_err(|e| MeiliSearchError::Decode(e.to_string()))?;
+        return self
+            .send_json::<SuggestResponse>(reqwest::Method::POST, &endpoint, Some(body))
+            .await;
+    }
 
-        let response = self
-            .send_request(reqwest::Method::POST, &endpoint, Some(search_request))
+    pub async fn search_entities(
+        &self,
+        index: &str,
+        keyword: &str,
+        limit: i64,
+    ) -> Result<EntitySearchResponse, MeiliSearchError> {
+        let endpoint = format!("indexes/{}/search", index);
+        let query = SearchQuery::for_entity(keyword, limit);
+        let body =
+            serde_json::to_value(&query).map_err(|e| MeiliSearchError::Decode(e.to_string()))?;
+        return self
+            .send_json::<EntitySearchResponse>(reqwest::Method::POST, &endpoint, Some(body))
             .await;
+    }
+}
 
-        match response {
-            Ok(response) => {
-                if response.status().is_success() {
-                    let search_response: SearchResponse =
-                        serde_json::from_str(&response.text().await.unwrap()).unwrap();
-                    return Some(search_response);
-                } else {
-                    return None;
-                }
-            }
-            Err(_) => None,
-        }
+impl SearchBackend for MeiliSearchClient {
+    fn name(&self) -> &'static str {
+        "meilisearch"
+    }
+
+    fn create_index<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let task = MeiliSearchClient::create_index(self, index).await?;
+            Ok(task.map(|t| t.task_uid))
+        })
+    }
+
+    fn delete_all_documents<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let task = MeiliSearchClient::delete_all_documents(self, index).await?;
+            Ok(Some(task.task_uid))
+        })
+    }
+
+    fn save_documents<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Value,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let task = MeiliSearchClient::save_documents(self, index, documents).await?;
+            Ok(Some(task.task_uid))
+        })
+    }
+
+    fn delete_documents<'a>(
+        &'a self,
+        index: &'a str,
+        ids: Vec<i64>,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let task = MeiliSearchClient::delete_documents(self, index, ids).await?;
+            Ok(Some(task.task_uid))
+        })
+    }
+
+    fn get_documents<'a>(
+        &'a self,
+        index: &'a str,
+        fields: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<Vec<Value>, E>> {
+        Box::pin(async move { Ok(MeiliSearchClient::get_documents(self, index, fields).await?) })
+    }
+
+    fn search_documents<'a>(
+        &'a self,
+        index: &'a str,
+        req: &'a SearchQueryRequest,
+    ) -> BoxFuture<'a, Result<SearchResponse, E>> {
+        Box::pin(async move { Ok(MeiliSearchClient::search_documents(self, index, req).await?) })
+    }
+
+    fn suggest_documents<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<SuggestResponse, E>> {
+        Box::pin(async move {
+            Ok(MeiliSearchClient::suggest_documents(self, index, keyword, limit).await?)
+        })
+    }
+
+    fn search_entities<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<EntitySearchResponse, E>> {
+        Box::pin(async move {
+            Ok(MeiliSearchClient::search_entities(self, index, keyword, limit).await?)
+        })
+    }
+
+    fn get_settings<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<IndexSettings, E>> {
+        Box::pin(async move { Ok(MeiliSearchClient::get_settings(self, index).await?) })
+    }
+
+    fn update_settings<'a>(
+        &'a self,
+        index: &'a str,
+        settings: &'a IndexSettings,
+    ) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let task = MeiliSearchClient::update_settings(self, index, settings).await?;
+            Ok(Some(task.task_uid))
+        })
+    }
+
+    fn get_task<'a>(&'a self, task_uid: u64) -> BoxFuture<'a, Result<Task, E>> {
+        Box::pin(async move { Ok(MeiliSearchClient::get_task(self, task_uid).await?) })
+    }
+
+    fn wait_for_task<'a>(&'a self, task_uid: u64) -> BoxFuture<'a, Result<(), E>> {
+        Box::pin(async move {
+            MeiliSearchClient::wait_for_task(self, task_uid, self.task_timeout).await?;
+            Ok(())
+        })
     }
 }
@@ -0,0 +1,105 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::custom_error::{E, Status};
+
+// MeiliSearch 接口返回的错误信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MeiliSearchApiError {
+    pub message: String,
+    pub code: String,
+}
+
+#[derive(Debug)]
+pub enum MeiliSearchError {
+    // 连接失败或请求超时
+    Request(String),
+    // 返回了非 2xx 的状态码
+    Api {
+        status: u16,
+        error: MeiliSearchApiError,
+    },
+    // 返回内容无法解析
+    Decode(String),
+    // 异步任务执行失败
+    TaskFailed {
+        task_uid: u64,
+        error: MeiliSearchApiError,
+    },
+    // 等待异步任务超时
+    TaskTimeout(u64),
+}
+
+impl Display for MeiliSearchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeiliSearchError::Request(e) => write!(f, "搜索服务不可用: {}", e),
+            MeiliSearchError::Api { status, error } => write!(
+                f,
+                "搜索服务返回错误 {} {}: {}",
+                status, error.code, error.message
+            ),
+            MeiliSearchError::Decode(e) => write!(f, "解析搜索服务返回内容失败: {}", e),
+            MeiliSearchError::TaskFailed { task_uid, error } => write!(
+                f,
+                "搜索任务 {} 执行失败 {}: {}",
+                task_uid, error.code, error.message
+            ),
+            MeiliSearchError::TaskTimeout(task_uid) => {
+                write!(f, "等待搜索任务 {} 完成超时", task_uid)
+            }
+        }
+    }
+}
+
+// 索引没有把过滤的字段声明为 filterableAttributes 时返回 invalid_search_filter，单独使用一个状态码
+impl From<MeiliSearchError> for E {
+    fn from(e: MeiliSearchError) -> Self {
+        let code = match &e {
+            MeiliSearchError::Api { error, .. } if error.code == "invalid_search_filter" => {
+                Status::SEARCH_FILTER_ERROR
+            }
+            MeiliSearchError::Request(_) => Status::SEARCH_UNAVAILABLE_ERROR,
+            MeiliSearchError::Api { status, .. } if *status >= 500 => {
+                Status::SEARCH_UNAVAILABLE_ERROR
+            }
+            _ => Status::HTTP_REQUEST_ERROR,
+        };
+        E::error(code, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::backend::should_fallback;
+
+    fn api_error(status: u16, code: &str) -> MeiliSearchError {
+        return MeiliSearchError::Api {
+            status,
+            error: MeiliSearchApiError {
+                message: String::from("error"),
+                code: String::from(code),
+            },
+        };
+    }
+
+    #[test]
+    fn fallback_only_on_unavailable_or_filter_errors() {
+        let unavailable: E = MeiliSearchError::Request(String::from("timeout")).into();
+        assert_eq!(unavailable.code, Status::SEARCH_UNAVAILABLE_ERROR);
+        assert!(should_fallback(&unavailable));
+
+        let server: E = api_error(503, "internal").into();
+        assert!(should_fallback(&server));
+
+        let filter: E = api_error(400, "invalid_search_filter").into();
+        assert_eq!(filter.code, Status::SEARCH_FILTER_ERROR);
+        assert!(should_fallback(&filter));
+
+        let bad_request: E = api_error(400, "invalid_search_q").into();
+        assert_eq!(bad_request.code, Status::HTTP_REQUEST_ERROR);
+        assert!(!should_fallback(&bad_request));
+    }
+}
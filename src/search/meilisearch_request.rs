@@ -1,5 +1,12 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::common::constants::{SEARCH_HIGHLIGHT_POST_TAG, SEARCH_HIGHLIGHT_PRE_TAG};
+use crate::request::blog_request::SearchQueryRequest;
+use crate::search::meillsearch_response::SEARCH_FACETS;
+use crate::search::search_entity::SearchEntity;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchQuery {
     q: String,
@@ -14,6 +21,15 @@ pub struct SearchQuery {
     sort: Vec<String>,
     #[serde(rename = "attributesToHighlight")]
     attributes_to_highlight: Vec<String>,
+    #[serde(rename = "attributesToCrop")]
+    attributes_to_crop: Vec<String>,
+    #[serde(rename = "cropLength", skip_serializing_if = "Option::is_none")]
+    crop_length: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+    facets: Vec<String>,
+    #[serde(rename = "attributesToRetrieve", skip_serializing_if = "Option::is_none")]
+    attributes_to_retrieve: Option<Vec<String>>,
 }
 
 impl SearchQuery {
@@ -27,14 +43,78 @@ impl SearchQuery {
             show_matches_position: false,
             sort: Vec::new(),
             attributes_to_highlight: Vec::new(),
+            attributes_to_crop: Vec::new(),
+            crop_length: None,
+            filter: None,
+            facets: Vec::new(),
+            attributes_to_retrieve: None,
         }
     }
 
+    // 按搜索请求的关键字、分页、过滤、排序和高亮参数构建查询
+    pub fn from_request(req: &SearchQueryRequest) -> Self {
+        let (pre_tag, post_tag) = req.get_highlight_tags();
+        return SearchQuery::new()
+            .set_q(req.keyword.to_owned())
+            .set_offset(req.get_offset())
+            .set_limit(req.get_size())
+            .set_attributes_to_highlight(vec![String::from("*")])
+            .set_attributes_to_crop(vec![String::from("content")])
+            .set_crop_length(req.get_crop_length())
+            .set_show_matches_position(false)
+            .set_highlight_pre_tag(pre_tag)
+            .set_highlight_post_tag(post_tag)
+            .set_filter(req.to_filter())
+            .set_sort(req.to_sort())
+            .set_facets(SEARCH_FACETS.iter().map(|f| f.to_string()).collect())
+            .build();
+    }
+
+    // 专题、标签、分类和作者的查询，只高亮名称和描述
+    pub fn for_entity(keyword: &str, limit: i64) -> Self {
+        return SearchQuery::new()
+            .set_q(keyword.to_owned())
+            .set_offset(0)
+            .set_limit(limit)
+            .set_attributes_to_highlight(vec![String::from("name"), String::from("description")])
+            .set_highlight_pre_tag(SEARCH_HIGHLIGHT_PRE_TAG.to_string())
+            .set_highlight_post_tag(SEARCH_HIGHLIGHT_POST_TAG.to_string())
+            .build();
+    }
+
+    // 搜索建议的查询，只返回 id 和标题，只高亮标题，不裁剪也不统计分面
+    pub fn for_suggest(keyword: &str, limit: i64) -> Self {
+        return SearchQuery::new()
+            .set_q(keyword.to_owned())
+            .set_offset(0)
+            .set_limit(limit)
+            .set_attributes_to_retrieve(vec![String::from("id"), String::from("title")])
+            .set_attributes_to_highlight(vec![String::from("title")])
+            .set_highlight_pre_tag(SEARCH_HIGHLIGHT_PRE_TAG.to_string())
+            .set_highlight_post_tag(SEARCH_HIGHLIGHT_POST_TAG.to_string())
+            .build();
+    }
+
+    pub fn set_attributes_to_retrieve(mut self, attributes: Vec<String>) -> Self {
+        self.attributes_to_retrieve = Some(attributes);
+        self
+    }
+
     pub fn set_attributes_to_highlight(mut self, highlight: Vec<String>) -> Self {
         self.attributes_to_highlight = highlight;
         self
     }
 
+    pub fn set_attributes_to_crop(mut self, crop: Vec<String>) -> Self {
+        self.attributes_to_crop = crop;
+        self
+    }
+
+    pub fn set_crop_length(mut self, crop_length: i64) -> Self {
+        self.crop_length = Some(crop_length);
+        self
+    }
+
     pub fn set_show_matches_position(mut self, show: bool) -> Self {
         self.show_matches_position = show;
         self
@@ -70,7 +150,118 @@ impl SearchQuery {
         self
     }
 
+    pub fn set_filter(mut self, filter: Option<String>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn set_facets(mut self, facets: Vec<String>) -> Self {
+        self.facets = facets;
+        self
+    }
+
     pub fn build(self) -> SearchQuery {
         self
     }
 }
+
+// 索引设置，更新时只修改不为空的字段
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct IndexSettings {
+    #[serde(rename = "searchableAttributes", skip_serializing_if = "Option::is_none")]
+    pub searchable_attributes: Option<Vec<String>>,
+    #[serde(rename = "filterableAttributes", skip_serializing_if = "Option::is_none")]
+    pub filterable_attributes: Option<Vec<String>>,
+    #[serde(rename = "sortableAttributes", skip_serializing_if = "Option::is_none")]
+    pub sortable_attributes: Option<Vec<String>>,
+    #[serde(rename = "rankingRules", skip_serializing_if = "Option::is_none")]
+    pub ranking_rules: Option<Vec<String>>,
+    #[serde(rename = "stopWords", skip_serializing_if = "Option::is_none")]
+    pub stop_words: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synonyms: Option<HashMap<String, Vec<String>>>,
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    return values.iter().map(|v| v.to_string()).collect();
+}
+
+impl IndexSettings {
+    // 创建索引时自动应用的默认设置，专题、标签、分类和作者索引只搜索名称和描述
+    pub fn default_for(index: &str) -> Self {
+        return match SearchEntity::from_index(index) {
+            Some(_) => IndexSettings::entity_default(),
+            None => IndexSettings::blog_default(),
+        };
+    }
+
+    pub fn entity_default() -> Self {
+        IndexSettings {
+            searchable_attributes: Some(to_strings(&["name", "description"])),
+            filterable_attributes: Some(vec![]),
+            sortable_attributes: Some(vec![]),
+            ranking_rules: Some(to_strings(&[
+                "words", "typo", "proximity", "attribute", "exactness",
+            ])),
+            stop_words: None,
+            synonyms: None,
+        }
+    }
+
+    // 博客索引的默认设置
+    pub fn blog_default() -> Self {
+        let synonyms = [
+            ("js", vec!["javascript"]),
+            ("javascript", vec!["js"]),
+            ("ts", vec!["typescript"]),
+            ("typescript", vec!["ts"]),
+            ("k8s", vec!["kubernetes"]),
+            ("kubernetes", vec!["k8s"]),
+            ("postgres", vec!["postgresql"]),
+            ("postgresql", vec!["postgres"]),
+            ("数据库", vec!["database"]),
+            ("database", vec!["数据库"]),
+        ];
+        IndexSettings {
+            searchable_attributes: Some(to_strings(&[
+                "title", "tags", "description", "content", "category", "topic", "author",
+            ])),
+            filterable_attributes: Some(to_strings(&[
+                "categoryId", "category", "tags", "authorId", "author", "topicId", "createAt",
+            ])),
+            sortable_attributes: Some(to_strings(&["createAt", "views"])),
+            ranking_rules: Some(to_strings(&[
+                "words", "typo", "proximity", "attribute", "sort", "exactness",
+            ])),
+            stop_words: Some(to_strings(&[
+                "的", "了", "是", "在", "和", "与", "及", "或", "也", "就", "都", "而", "the", "a",
+                "an", "of", "to", "and", "or", "in", "on", "is", "for",
+            ])),
+            synonyms: Some(
+                synonyms
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), to_strings(&v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn suggest_query_is_lightweight() {
+        let query = serde_json::to_value(SearchQuery::for_suggest("rust", 5)).unwrap();
+        assert_eq!(query["limit"], json!(5));
+        assert_eq!(query["attributesToRetrieve"], json!(["id", "title"]));
+        assert_eq!(query["attributesToHighlight"], json!(["title"]));
+        assert_eq!(query["attributesToCrop"], json!([]));
+        assert_eq!(query["facets"], json!([]));
+        assert!(query.get("cropLength").is_none());
+        assert!(query.get("filter").is_none());
+    }
+}
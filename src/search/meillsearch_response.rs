@@ -1,21 +1,50 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::response::search_info::SearchScope;
+use crate::search::meilisearch_error::MeiliSearchApiError;
+
+// 分面统计，属性名 -> (属性值 -> 文档数量)
+pub type FacetDistribution = HashMap<String, HashMap<String, i64>>;
+
+// 返回分面统计的属性
+pub const SEARCH_FACETS: [&str; 3] = ["category", "tags", "author"];
+
+// 对比索引和数据库时获取的字段，博客不包含 content，
+// 也不包含每次访问都会变化的 views，否则每次对比都会更新大部分文档
+pub const SEARCH_BLOG_FIELDS: [&str; 12] = [
+    "id", "title", "description", "tags", "categoryId", "category", "topicId", "topic",
+    "authorId", "author", "createAt", "updateAt",
+];
+pub const SEARCH_ENTITY_FIELDS: [&str; 4] = ["id", "name", "description", "image"];
+
 #[derive(Serialize, Debug, Deserialize)]
 pub struct SearchResponse {
     #[serde(rename = "hits")]
     pub hits: Vec<Hits>,
     pub offset: i64,
     pub limit: i64,
-    #[serde(rename = "estimatedTotalHits")]
+    #[serde(rename = "estimatedTotalHits", default)]
     pub total_hits: i64,
+    #[serde(default)]
     pub query: String,
+    #[serde(rename = "facetDistribution", default)]
+    pub facet_distribution: FacetDistribution,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+// 高亮后的字段，索引设置中没有返回的字段为空
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct Formatted {
+    #[serde(default)]
     pub description: String,
+    #[serde(default)]
     pub id: String,
+    #[serde(default)]
     pub title: String,
+    // 正文中匹配关键字的片段
+    #[serde(default)]
+    pub content: String,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -23,5 +52,112 @@ pub struct Hits {
     pub id: i64,
     pub title: String,
     pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(rename = "categoryId", default)]
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(rename = "topicId", default)]
+    pub topic_id: Option<i64>,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(rename = "authorId", default)]
+    pub author_id: Option<i64>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(rename = "createAt", default)]
+    pub create_at: Option<i64>,
+    #[serde(default)]
+    pub views: Option<i64>,
+    #[serde(default)]
     pub _formatted: Formatted,
 }
+
+// 搜索接口返回的分页结果，facets 为当前条件下各分类、标签和作者的文档数量
+#[derive(Serialize, Debug)]
+pub struct SearchPage {
+    pub page: i64,
+    pub size: i64,
+    pub total: i64,
+    pub data: Vec<Hits>,
+    pub facets: FacetDistribution,
+    // 请求中限定的专题、分类或作者
+    pub scope: Vec<SearchScope>,
+}
+
+// 专题、标签、分类和作者的搜索结果
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EntitySearchResponse {
+    pub hits: Vec<EntityHits>,
+    #[serde(rename = "estimatedTotalHits", default)]
+    pub total_hits: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct EntityFormatted {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EntityHits {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub _formatted: EntityFormatted,
+}
+
+// 搜索建议的结果，只包含博客 id 和标题
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SuggestResponse {
+    pub hits: Vec<SuggestHits>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SuggestHits {
+    pub id: i64,
+    pub title: String,
+    #[serde(default)]
+    pub _formatted: Formatted,
+}
+
+// 写入操作返回的异步任务
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TaskInfo {
+    #[serde(rename = "taskUid")]
+    pub task_uid: u64,
+    #[serde(rename = "indexUid")]
+    pub index_uid: Option<String>,
+    pub status: String,
+    #[serde(rename = "type")]
+    pub task_type: String,
+}
+
+// 异步任务的状态，status 为 enqueued、processing、succeeded、failed 或 canceled
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Task {
+    pub uid: u64,
+    #[serde(rename = "indexUid")]
+    pub index_uid: Option<String>,
+    pub status: String,
+    #[serde(rename = "type")]
+    pub task_type: String,
+    pub error: Option<MeiliSearchApiError>,
+    #[serde(rename = "enqueuedAt")]
+    pub enqueued_at: Option<String>,
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<String>,
+}
+
+impl Task {
+    pub fn is_finished(&self) -> bool {
+        return matches!(self.status.as_str(), "succeeded" | "failed" | "canceled");
+    }
+}
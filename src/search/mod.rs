@@ -1,3 +1,7 @@
+pub mod backend;
 pub mod meilisearch_client;
+pub mod meilisearch_error;
 pub mod meilisearch_request;
 pub mod meillsearch_response;
+pub mod postgres_search;
+pub mod search_entity;
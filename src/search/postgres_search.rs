@@ -0,0 +1,651 @@
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+use log::error;
+use serde_json::Value;
+use sqlx::{Pool, Postgres, QueryBuilder};
+
+use crate::common::constants::{SEARCH_HIGHLIGHT_POST_TAG, SEARCH_HIGHLIGHT_PRE_TAG};
+use crate::common::escape_html;
+use crate::error::custom_error::{E, Status};
+use crate::request::blog_request::{SearchQueryRequest, SearchSort};
+use crate::search::backend::SearchBackend;
+use crate::search::meillsearch_response::{
+    EntityFormatted, EntityHits, EntitySearchResponse, FacetDistribution, Formatted, Hits,
+    SEARCH_FACETS, SearchResponse,
+};
+
+// 基于 PostgreSQL tsvector 的全文搜索，中文按单字和相邻两字切分后使用 simple 配置建立索引
+pub struct PostgresSearch {
+    pool: Pool<Postgres>,
+}
+
+impl PostgresSearch {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        PostgresSearch { pool: db_pool }
+    }
+
+    async fn save(&self, index: &str, documents: Value) -> Result<Option<u64>, E> {
+        let documents = match documents {
+            Value::Array(documents) => documents,
+            document => vec![document],
+        };
+
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                return Err(E::error(Status::DATABASE_ERROR, String::from("添加文档失败")));
+            }
+        };
+
+        let sql = "INSERT INTO search_documents(index_uid, id, document, search_vector, update_at)
+            VALUES ($1, $2, $3, setweight(to_tsvector('simple', $4), 'A') || setweight(to_tsvector('simple', $5), 'B'), now())
+            ON CONFLICT (index_uid, id) DO UPDATE SET document = excluded.document,
+            search_vector = excluded.search_vector, update_at = now()";
+
+        for document in documents {
+            let id = match document.get("id").and_then(|id| id.as_i64()) {
+                Some(id) => id,
+                None => continue,
+            };
+            let (title, other) = document_text(&document);
+            let result = sqlx::query(sql)
+                .bind(index)
+                .bind(&id)
+                .bind(&document)
+                .bind(tokenize(&title).join(" "))
+                .bind(tokenize(&other).join(" "))
+                .execute(&mut *tx)
+                .await;
+            if let Err(e) = result {
+                error!("数据库执行失败: {}", e);
+                return Err(E::error(Status::DATABASE_ERROR, String::from("添加文档失败")));
+            }
+        }
+
+        // 写入是同步完成的，没有异步任务
+        return match tx.commit().await {
+            Ok(_) => Ok(None),
+            Err(e) => {
+                error!("数据库执行失败: {}", e);
+                Err(E::error(Status::DATABASE_ERROR, String::from("添加文档失败")))
+            }
+        };
+    }
+
+    async fn search(&self, index: &str, req: &SearchQueryRequest) -> Result<SearchResponse, E> {
+        let size = req.get_size();
+        let offset = req.get_offset();
+
+        let mut response = SearchResponse {
+            hits: vec![],
+            offset,
+            limit: size,
+            total_hits: 0,
+            query: req.keyword.to_owned(),
+            facet_distribution: HashMap::new(),
+        };
+
+        // 关键字为空时只按条件过滤，和 MeiliSearch 的行为保持一致
+        let ts_query = build_ts_query(&req.keyword);
+        if ts_query.is_none() && !req.keyword.trim().is_empty() {
+            return Ok(response);
+        }
+
+        let mut count_builder =
+            QueryBuilder::<Postgres>::new("SELECT count(*) FROM search_documents");
+        push_conditions(&mut count_builder, index, &ts_query, req);
+        response.total_hits = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(search_error)?;
+
+        if response.total_hits == 0 {
+            return Ok(response);
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT document FROM search_documents");
+        push_conditions(&mut builder, index, &ts_query, req);
+        builder.push(" ORDER BY ");
+        match (req.sort, &ts_query) {
+            (SearchSort::Newest, _) => {
+                builder.push("(document->>'createAt')::bigint DESC NULLS LAST, ");
+            }
+            (SearchSort::Views, _) => {
+                builder.push("(document->>'views')::bigint DESC NULLS LAST, ");
+            }
+            (SearchSort::Relevance, Some(q)) => {
+                builder
+                    .push("ts_rank(search_vector, to_tsquery('simple', ")
+                    .push_bind(q.to_owned())
+                    .push(")) DESC, ");
+            }
+            (SearchSort::Relevance, None) => {}
+        }
+        builder
+            .push("id DESC OFFSET ")
+            .push_bind(offset)
+            .push(" LIMIT ")
+            .push_bind(size);
+
+        let documents = builder
+            .build_query_scalar::<Value>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(search_error)?;
+
+        let terms = query_terms(&req.keyword);
+        let tags = req.get_highlight_tags();
+        // MeiliSearch 的片段长度按词计算，中文每个字算一个词，这里按字符数的两倍近似
+        let crop_length = req.get_crop_length() as usize * 2;
+        response.hits = documents
+            .into_iter()
+            .filter_map(|document| to_hits(document, &terms, &tags, crop_length))
+            .collect();
+
+        response.facet_distribution = self.get_facets(index, &ts_query, req).await?;
+
+        return Ok(response);
+    }
+
+    // 按名称和描述搜索专题、标签、分类和作者，名称的权重和博客标题相同
+    async fn search_entity(
+        &self,
+        index: &str,
+        keyword: &str,
+        limit: i64,
+    ) -> Result<EntitySearchResponse, E> {
+        let mut response = EntitySearchResponse {
+            hits: vec![],
+            total_hits: 0,
+        };
+
+        let ts_query = match build_ts_query(keyword) {
+            Some(q) => q,
+            None => return Ok(response),
+        };
+
+        let documents = sqlx::query_scalar::<_, Value>(
+            "SELECT document FROM search_documents
+            WHERE index_uid = $1 AND search_vector @@ to_tsquery('simple', $2)
+            ORDER BY ts_rank(search_vector, to_tsquery('simple', $2)) DESC, id LIMIT $3",
+        )
+        .bind(index)
+        .bind(&ts_query)
+        .bind(&limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(search_error)?;
+
+        let terms = query_terms(keyword);
+        let tags = (
+            SEARCH_HIGHLIGHT_PRE_TAG.to_string(),
+            SEARCH_HIGHLIGHT_POST_TAG.to_string(),
+        );
+        for document in documents {
+            match serde_json::from_value::<EntityHits>(document) {
+                Ok(mut hits) => {
+                    hits._formatted = EntityFormatted {
+                        name: highlight(&hits.name, &terms, &tags),
+                        description: highlight(&hits.description, &terms, &tags),
+                    };
+                    response.hits.push(hits);
+                }
+                Err(e) => error!("搜索文档格式错误: {}", e),
+            }
+        }
+        response.total_hits = response.hits.len() as i64;
+
+        return Ok(response);
+    }
+
+    // 统计符合条件的文档中各分面属性值的数量
+    async fn get_facets(
+        &self,
+        index: &str,
+        ts_query: &Option<String>,
+        req: &SearchQueryRequest,
+    ) -> Result<FacetDistribution, E> {
+        let mut builder = QueryBuilder::<Postgres>::new("");
+        for (i, facet) in SEARCH_FACETS.iter().enumerate() {
+            if i > 0 {
+                builder.push(" UNION ALL ");
+            }
+            let value = if *facet == "tags" {
+                String::from("jsonb_array_elements_text(document->'tags')")
+            } else {
+                format!("document->>'{}'", facet)
+            };
+            builder
+                .push("SELECT ")
+                .push_bind(facet.to_string())
+                .push(format!("::text, v, count(*) FROM (SELECT {} AS v FROM search_documents", value));
+            push_conditions(&mut builder, index, ts_query, req);
+            builder.push(") f WHERE v IS NOT NULL GROUP BY v");
+        }
+
+        let rows = builder
+            .build_query_as::<(String, String, i64)>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(search_error)?;
+
+        let mut facets: FacetDistribution = HashMap::new();
+        for (facet, value, count) in rows {
+            facets.entry(facet).or_default().insert(value, count);
+        }
+        return Ok(facets);
+    }
+}
+
+impl SearchBackend for PostgresSearch {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    // 所有索引共用 search_documents 表，不需要创建
+    fn create_index<'a>(&'a self, _index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    fn delete_all_documents<'a>(&'a self, index: &'a str) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM search_documents WHERE index_uid = $1")
+                .bind(index)
+                .execute(&self.pool)
+                .await;
+            match result {
+                Ok(_) => Ok(None),
+                Err(e) => {
+                    error!("数据库执行失败: {}", e);
+                    Err(E::error(Status::DATABASE_ERROR, String::from("删除所有文档失败")))
+                }
+            }
+        })
+    }
+
+    fn save_documents<'a>(&'a self, index: &'a str, documents: Value) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(self.save(index, documents))
+    }
+
+    fn delete_documents<'a>(&'a self, index: &'a str, ids: Vec<i64>) -> BoxFuture<'a, Result<Option<u64>, E>> {
+        Box::pin(async move {
+            let result =
+                sqlx::query("DELETE FROM search_documents WHERE index_uid = $1 AND id = ANY($2)")
+                    .bind(index)
+                    .bind(&ids)
+                    .execute(&self.pool)
+                    .await;
+            match result {
+                Ok(_) => Ok(None),
+                Err(e) => {
+                    error!("数据库执行失败: {}", e);
+                    Err(E::error(Status::DATABASE_ERROR, String::from("删除文档失败")))
+                }
+            }
+        })
+    }
+
+    fn get_documents<'a>(
+        &'a self,
+        index: &'a str,
+        fields: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<Vec<Value>, E>> {
+        Box::pin(async move {
+            let documents = sqlx::query_scalar::<_, Value>(
+                "SELECT document - 'content' FROM search_documents WHERE index_uid = $1",
+            )
+            .bind(index)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(search_error)?;
+            return Ok(documents
+                .into_iter()
+                .map(|mut document| {
+                    if let Value::Object(map) = &mut document {
+                        map.retain(|k, _| fields.contains(&k.as_str()));
+                    }
+                    document
+                })
+                .collect());
+        })
+    }
+
+    fn search_documents<'a>(
+        &'a self,
+        index: &'a str,
+        req: &'a SearchQueryRequest,
+    ) -> BoxFuture<'a, Result<SearchResponse, E>> {
+        Box::pin(self.search(index, req))
+    }
+
+    fn search_entities<'a>(
+        &'a self,
+        index: &'a str,
+        keyword: &'a str,
+        limit: i64,
+    ) -> BoxFuture<'a, Result<EntitySearchResponse, E>> {
+        Box::pin(self.search_entity(index, keyword, limit))
+    }
+}
+
+// 索引、关键字和过滤条件，对应 MeiliSearch 的 filter 表达式
+fn push_conditions(
+    builder: &mut QueryBuilder<Postgres>,
+    index: &str,
+    ts_query: &Option<String>,
+    req: &SearchQueryRequest,
+) {
+    builder.push(" WHERE index_uid = ").push_bind(index.to_owned());
+    if let Some(q) = ts_query {
+        builder
+            .push(" AND search_vector @@ to_tsquery('simple', ")
+            .push_bind(q.to_owned())
+            .push(")");
+    }
+    if let Some(topic_id) = req.topic_id {
+        builder
+            .push(" AND (document->>'topicId')::bigint = ")
+            .push_bind(topic_id);
+    }
+    if let Some(category_id) = req.category_id {
+        builder
+            .push(" AND (document->>'categoryId')::bigint = ")
+            .push_bind(category_id);
+    }
+    if let Some(tag) = req.tag.as_ref().filter(|t| !t.trim().is_empty()) {
+        builder
+            .push(" AND document->'tags' ? ")
+            .push_bind(tag.trim().to_owned());
+    }
+    if let Some(author_id) = req.author_id {
+        builder
+            .push(" AND (document->>'authorId')::bigint = ")
+            .push_bind(author_id);
+    }
+    if let Some(start) = req.start {
+        builder
+            .push(" AND (document->>'createAt')::bigint >= ")
+            .push_bind(start);
+    }
+    if let Some(end) = req.end {
+        builder
+            .push(" AND (document->>'createAt')::bigint <= ")
+            .push_bind(end);
+    }
+}
+
+fn search_error(e: sqlx::Error) -> E {
+    error!("数据库查询失败: {}", e);
+    E::error(Status::DATABASE_ERROR, String::from("搜索失败"))
+}
+
+// 标题（专题、标签、分类和作者为名称）单独加权，其余字符串字段合并为一段文本
+fn document_text(document: &Value) -> (String, String) {
+    let mut title = String::new();
+    let mut other = String::new();
+    if let Value::Object(map) = document {
+        for (key, value) in map {
+            if let Value::String(s) = value {
+                if key == "title" || key == "name" {
+                    title = s.to_owned();
+                } else {
+                    other.push_str(s);
+                    other.push(' ');
+                }
+            }
+        }
+    }
+    return (title, other);
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{AC00}'..='\u{D7AF}')
+}
+
+// 把文本切分为连续的中文片段和英文数字单词
+fn split_runs(text: &str) -> Vec<(bool, String)> {
+    let mut runs: Vec<(bool, String)> = vec![];
+    for c in text.chars() {
+        let cjk = is_cjk(c);
+        if !cjk && !c.is_alphanumeric() {
+            if runs.last().map_or(false, |(_, s)| !s.is_empty()) {
+                runs.push((false, String::new()));
+            }
+            continue;
+        }
+        match runs.last_mut() {
+            Some((last_cjk, s)) if s.is_empty() || *last_cjk == cjk => {
+                *last_cjk = cjk;
+                s.extend(c.to_lowercase());
+            }
+            _ => runs.push((cjk, c.to_lowercase().collect())),
+        }
+    }
+    runs.retain(|(_, s)| !s.is_empty());
+    return runs;
+}
+
+// 文档分词，中文输出单字和相邻两字，英文数字输出小写单词
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    for (cjk, run) in split_runs(text) {
+        if !cjk {
+            tokens.push(run);
+            continue;
+        }
+        let chars: Vec<char> = run.chars().collect();
+        for (i, c) in chars.iter().enumerate() {
+            tokens.push(c.to_string());
+            if i + 1 < chars.len() {
+                tokens.push(chars[i..i + 2].iter().collect());
+            }
+        }
+    }
+    return tokens;
+}
+
+// 构建 tsquery，所有词都需要匹配，英文单词支持前缀匹配
+fn build_ts_query(keyword: &str) -> Option<String> {
+    let mut parts = vec![];
+    for (cjk, run) in split_runs(keyword) {
+        if !cjk {
+            parts.push(format!("'{}':*", run));
+            continue;
+        }
+        let chars: Vec<char> = run.chars().collect();
+        if chars.len() == 1 {
+            parts.push(format!("'{}'", run));
+        } else {
+            for pair in chars.windows(2) {
+                parts.push(format!("'{}'", pair.iter().collect::<String>()));
+            }
+        }
+    }
+    return if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" & "))
+    };
+}
+
+fn query_terms(keyword: &str) -> Vec<Vec<char>> {
+    return split_runs(keyword)
+        .into_iter()
+        .map(|(_, run)| run.chars().collect())
+        .collect();
+}
+
+// 不区分大小写地标记出所有匹配的词，文档内容会先转义，结果中只有高亮标签是 HTML
+fn highlight(text: &str, terms: &[Vec<char>], tags: &(String, String)) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let mut marked = vec![false; chars.len()];
+    for term in terms.iter().filter(|t| !t.is_empty()) {
+        if term.len() > lower.len() {
+            continue;
+        }
+        for i in 0..=lower.len() - term.len() {
+            if lower[i..i + term.len()] == term[..] {
+                marked[i..i + term.len()].iter_mut().for_each(|m| *m = true);
+            }
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in chars.iter().enumerate() {
+        if marked[i] && (i == 0 || !marked[i - 1]) {
+            result.push_str(&tags.0);
+        }
+        result.push_str(&escape_html(c.encode_utf8(&mut [0; 4])));
+        if marked[i] && (i + 1 == chars.len() || !marked[i + 1]) {
+            result.push_str(&tags.1);
+        }
+    }
+    return result;
+}
+
+// 截取正文中第一个匹配位置附近的片段
+fn crop(text: &str, terms: &[Vec<char>], length: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let first = terms
+        .iter()
+        .filter(|t| !t.is_empty() && t.len() <= lower.len())
+        .filter_map(|t| lower.windows(t.len()).position(|w| w == &t[..]))
+        .min()
+        .unwrap_or(0);
+
+    let start = first.saturating_sub(length / 4);
+    let end = (start + length).min(chars.len());
+
+    let mut result = String::new();
+    if start > 0 {
+        result.push('…');
+    }
+    result.extend(&chars[start..end]);
+    if end < chars.len() {
+        result.push('…');
+    }
+    return result;
+}
+
+// 在文档上加入高亮后的 _formatted 字段，转换为和 MeiliSearch 相同的结果格式
+fn to_hits(
+    mut document: Value,
+    terms: &[Vec<char>],
+    tags: &(String, String),
+    crop_length: usize,
+) -> Option<Hits> {
+    let get = |key: &str| {
+        document
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_owned()
+    };
+    let id = document.get("id").and_then(|v| v.as_i64()).unwrap_or_default();
+    let formatted = Formatted {
+        id: id.to_string(),
+        title: highlight(&get("title"), terms, tags),
+        description: highlight(&get("description"), terms, tags),
+        content: highlight(&crop(&get("content"), terms, crop_length), terms, tags),
+    };
+    document
+        .as_object_mut()?
+        .insert(String::from("_formatted"), serde_json::to_value(formatted).ok()?);
+    return match serde_json::from_value::<Hits>(document) {
+        Ok(hits) => Some(hits),
+        Err(e) => {
+            error!("搜索文档格式错误: {}", e);
+            None
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags() -> (String, String) {
+        return (String::from("<em>"), String::from("</em>"));
+    }
+
+    #[test]
+    fn tokenize_cjk_and_words() {
+        assert_eq!(
+            tokenize("Rust异步编程, Tokio!"),
+            vec!["rust", "异", "异步", "步", "步编", "编", "编程", "程", "tokio"]
+        );
+        assert_eq!(tokenize("中"), vec!["中"]);
+        assert!(tokenize("  ,.!? ").is_empty());
+    }
+
+    #[test]
+    fn build_ts_query_uses_bigrams_and_prefixes() {
+        assert_eq!(
+            build_ts_query("Rust 异步编程"),
+            Some(String::from("'rust':* & '异步' & '步编' & '编程'"))
+        );
+        assert_eq!(build_ts_query("中"), Some(String::from("'中'")));
+        assert_eq!(build_ts_query("   "), None);
+    }
+
+    #[test]
+    fn build_ts_query_escapes_operators() {
+        assert_eq!(
+            build_ts_query("it's \"a\" & b | !c :* (d) \\e"),
+            Some(String::from("'it':* & 's':* & 'a':* & 'b':* & 'c':* & 'd':* & 'e':*"))
+        );
+        assert_eq!(build_ts_query("' & | ! :* ()"), None);
+    }
+
+    #[test]
+    fn highlight_marks_terms_case_insensitive() {
+        let terms = query_terms("rust 异步");
+        assert_eq!(
+            highlight("RUST 的异步运行时 Rusty", &terms, &tags()),
+            "<em>RUST</em> 的<em>异步</em>运行时 <em>Rust</em>y"
+        );
+        assert_eq!(highlight("没有匹配", &terms, &tags()), "没有匹配");
+    }
+
+    #[test]
+    fn highlight_escapes_document_text() {
+        let terms = query_terms("rust");
+        assert_eq!(
+            highlight("<script>rust</script> & \"x\"", &terms, &tags()),
+            "&lt;script&gt;<em>rust</em>&lt;/script&gt; &amp; &quot;x&quot;"
+        );
+    }
+
+    #[test]
+    fn highlight_merges_adjacent_terms() {
+        let terms = query_terms("异步 编程");
+        assert_eq!(highlight("异步编程", &terms, &tags()), "<em>异步编程</em>");
+    }
+
+    #[test]
+    fn crop_around_first_match() {
+        let text = "abcdefghijklmnopqrstuvwxyz0123456789";
+        let terms = query_terms("uvw");
+        assert_eq!(crop(text, &terms, 8), "…stuvwxyz…");
+        assert_eq!(crop(text, &query_terms("abc"), 8), "abcdefgh…");
+        assert_eq!(crop("短文本", &query_terms("不存在"), 8), "短文本");
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::conf::config::CONFIG;
+
+// 除博客外单独建立搜索索引的实体，文档格式都是 SearchEntityVo
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchEntity {
+    Topic,
+    Tag,
+    Category,
+    Author,
+}
+
+impl SearchEntity {
+    pub const ALL: [SearchEntity; 4] = [
+        SearchEntity::Topic,
+        SearchEntity::Tag,
+        SearchEntity::Category,
+        SearchEntity::Author,
+    ];
+
+    pub fn index(&self) -> &'static str {
+        let indexes = &CONFIG.search;
+        return match self {
+            SearchEntity::Topic => &indexes.topic_index,
+            SearchEntity::Tag => &indexes.tag_index,
+            SearchEntity::Category => &indexes.category_index,
+            SearchEntity::Author => &indexes.author_index,
+        };
+    }
+
+    // 根据索引名称找到对应的实体，博客索引返回 None
+    pub fn from_index(index: &str) -> Option<SearchEntity> {
+        return SearchEntity::ALL.into_iter().find(|e| e.index() == index);
+    }
+}
@@ -6,22 +6,25 @@ use crate::cache::{
     clear_category_info_keys, clear_page_info_keys, clear_tag_info_key, clear_topic_info_key,
 };
 use crate::conf::config::CONFIG;
+use crate::error::custom_error::E;
 use crate::models::blogs::BlogAdminVo;
 use crate::models::category::{CategoryVo, OtherAdminVo};
 use crate::models::file::FileAdminVo;
 use crate::models::topic::{AdminTopicVo, TopicRequest};
 use crate::repository::admin_repository::AdminRepository;
 use crate::request::admin_request::{
-    AdminBlogFilter, OtherAdminFilter, UpdatePublicRequest, UpdateRole,
+    AdminBlogFilter, OtherAdminFilter, UpdateCategoryRequest, UpdatePublicRequest, UpdateRole,
 };
 use crate::response::page_info::PageInfo;
+use crate::search::search_entity::SearchEntity;
+use crate::service::search_service::SearchService;
 
-pub struct AdminService(Arc<AdminRepository>);
+pub struct AdminService(Arc<AdminRepository>, Arc<SearchService>);
 
 impl AdminService {
-    pub fn new(db_conn: Pool<Postgres>) -> AdminService {
+    pub fn new(db_conn: Pool<Postgres>, search_service: Arc<SearchService>) -> AdminService {
         let tag_repository = AdminRepository::new(db_conn);
-        AdminService(Arc::new(tag_repository))
+        AdminService(Arc::new(tag_repository), search_service)
     }
 
     pub async fn get_admin_blog_list(
@@ -43,50 +46,92 @@ impl AdminService {
             if CONFIG.blog_page_cache {
                 clear_page_info_keys()
             }
+            // 删除的博客从搜索索引中移除，恢复的博客重新加入
+            self.1.sync_blogs(ids);
         }
 
         return i;
     }
 
-    pub async fn delete_category_ids(&self, ids: &Vec<i64>, deleted: bool) -> i64 {
+    // 返回修改的行数、受影响的分类 id（包括子分类和恢复的父分类）和连带删除或恢复的博客 id
+    pub async fn delete_category_ids(
+        &self,
+        ids: &Vec<i64>,
+        deleted: bool,
+    ) -> (i64, Vec<i64>, Vec<i64>) {
+        // 删除或恢复分类时连同所有子分类一起处理
+        let mut ids = match self.0.get_category_subtree_ids(ids).await {
+            Ok(ids) => ids,
+            Err(_) => return (0, vec![], vec![]),
+        };
+
+        // 恢复分类时同时恢复父分类，避免子分类挂在已删除的分类下
+        if !deleted {
+            for pid in self.0.get_category_ancestor_ids(&ids).await {
+                if !ids.contains(&pid) {
+                    ids.push(pid);
+                }
+            }
+        }
+
+        if ids.is_empty() {
+            return (0, vec![], vec![]);
+        }
+
         let i = self
             .0
-            .global_delete_by_ids("categories", ids, -1, deleted)
+            .global_delete_by_ids("categories", &ids, -1, deleted)
             .await;
 
-        if i > 0 {
-            clear_category_info_keys();
-            &self.0.delete_blog_by_categories(ids, deleted, -1).await;
-            if CONFIG.blog_page_cache {
-                clear_page_info_keys()
-            }
+        if i <= 0 {
+            return (i, vec![], vec![]);
         }
 
-        return i;
+        clear_category_info_keys();
+        self.1.sync_entities(SearchEntity::Category, ids.to_owned());
+        let blog_ids = self.0.delete_blog_by_categories(&ids, deleted, -1).await;
+        self.1.sync_blogs(blog_ids.clone());
+        if CONFIG.blog_page_cache {
+            clear_page_info_keys()
+        }
+
+        return (i, ids, blog_ids);
     }
 
     pub async fn delete_tag_ids(&self, ids: &Vec<i64>, deleted: bool) -> i64 {
         let i = self.0.global_delete_by_ids("tags", ids, -1, deleted).await;
 
         if i > 0 {
-            clear_tag_info_key()
+            clear_tag_info_key();
+            self.1.sync_entities(SearchEntity::Tag, ids.to_owned());
+            self.1.sync_blogs(self.0.get_blog_ids_by_tags(ids).await);
         }
 
         return i;
     }
 
-    pub async fn delete_topics_ids(&self, ids: &Vec<i64>, deleted: bool, uid: i64) -> i64 {
+    // 返回修改的行数和连带删除或恢复的博客 id
+    pub async fn delete_topics_ids(
+        &self,
+        ids: &Vec<i64>,
+        deleted: bool,
+        uid: i64,
+    ) -> (i64, Vec<i64>) {
         let i = self
             .0
             .global_delete_by_ids("topics", ids, uid, deleted)
             .await;
 
-        if i > 0 {
-            clear_topic_info_key();
-            self.0.delete_blog_by_topics(ids, deleted, uid).await;
+        if i <= 0 {
+            return (i, vec![]);
         }
 
-        return i;
+        clear_topic_info_key();
+        self.1.sync_entities(SearchEntity::Topic, ids.to_owned());
+        let blog_ids = self.0.delete_blog_by_topics(ids, deleted, uid).await;
+        self.1.sync_blogs(blog_ids.clone());
+
+        return (i, blog_ids);
     }
 
     pub async fn get_admin_category_list(
@@ -114,22 +159,50 @@ impl AdminService {
             .await;
     }
 
-    pub async fn update_category(&self, c: CategoryVo) -> i64 {
-        return self
-            .0
-            .update_category_or_tag_name(String::from("categories"), c)
-            .await;
+    pub async fn update_category(&self, req: UpdateCategoryRequest) -> Result<i64, E> {
+        // 为空时不修改父分类，小于等于0时设为顶级分类
+        let parent = req
+            .parent_id
+            .map(|pid| if pid > 0 { Some(pid) } else { None });
+
+        let id = req.id;
+
+        let i = self.0.update_category(id, &req.name, parent).await?;
+
+        // 搜索文档中保存了分类名称，改名后需要同步
+        self.1.sync_entities(SearchEntity::Category, vec![id]);
+        let blog_ids = self.0.get_blog_ids_by_column("category_id", &vec![id]).await;
+        self.1.sync_blogs(blog_ids);
+
+        return Ok(i);
     }
 
     pub async fn update_tag(&self, c: CategoryVo) -> i64 {
-        return self
+        let id = c.id;
+
+        let i = self
             .0
             .update_category_or_tag_name(String::from("tags"), c)
             .await;
+
+        if i > 0 {
+            self.1.sync_entities(SearchEntity::Tag, vec![id]);
+            self.1.sync_blogs(self.0.get_blog_ids_by_tags(&vec![id]).await);
+        }
+
+        return i;
     }
 
     pub async fn update_topic(&self, topic: TopicRequest, uid: i64) -> i64 {
-        return self.0.update_topic(topic, uid).await;
+        let id = topic.id;
+
+        let i = self.0.update_topic(topic, uid).await;
+
+        if let (true, Some(id)) = (i > 0, id) {
+            self.1.sync_topics(vec![id]);
+        }
+
+        return i;
     }
 
     pub async fn get_admin_files(&self, req: OtherAdminFilter, uid: i64) -> PageInfo<FileAdminVo> {
@@ -6,16 +6,20 @@ use sqlx::{Pool, Postgres};
 
 use crate::cache::blog_cache::BlogCache;
 use crate::cache::clear_page_info_keys;
-use crate::common::redis_keys::{BLOG_LIST_PAGE_INFO_KEY, BLOG_MAP_KEY, EYE_COUNT_MAP, HOUR};
+use crate::common::{get_client_ip_city, get_client_platform_info, markdown_to_text};
+use crate::common::constants::{MAX_VIEW_RANGE_DAYS, SEARCH_CONTENT_MAX_LENGTH};
+use crate::common::date_format::try_time_stamp_to_date;
+use crate::common::redis_keys::{BLOG_LIST_PAGE_INFO_KEY, BLOG_MAP_KEY, EYE_COUNT_MAP, HOUR, MIN};
 use crate::conf::config::CONFIG;
 use crate::conf::redis_config::get_pool_connection;
 use crate::error::custom_error::{E, Status};
 use crate::models::blogs::{
-    ArchiveBlogVo, BlogContentVo, BlogVo, RecommendBlogVo, SearchBlogVo, SimpleBlogVo,
+    ArchiveBlogVo, BlogContentVo, BlogViewDailyVo, BlogVo, RecommendBlogVo, SearchBlogVo,
+    SimpleBlogVo,
 };
 use crate::repository::blog_repository::BlogRepository;
 use crate::request::blog_request::{
-    ArchiveRange, BlogFindRequest, BlogRequest, GetUserBlogRequest,
+    ArchiveRange, BlogFindRequest, BlogRequest, GetUserBlogRequest, ViewRangeRequest, ViewSource,
 };
 use crate::response::page_info::PageInfo;
 
@@ -32,8 +36,8 @@ impl BlogService {
     pub async fn get_blog_list_by_category(&self, request: &BlogFindRequest) -> PageInfo<BlogVo> {
         if CONFIG.blog_page_cache {
             let key = format!(
-                "{}_{}_{:?}_{}",
-                BLOG_LIST_PAGE_INFO_KEY, request.page, request.sort, request.cid
+                "{}_{}_{:?}_{}_{}",
+                BLOG_LIST_PAGE_INFO_KEY, request.page, request.sort, request.cid, request.children
             );
 
             // 尝试从 Redis 缓存中获取博客列表页信息
@@ -128,11 +132,70 @@ impl BlogService {
         };
     }
 
-    // 增加博客浏览次数
-    pub async fn increase_in_view(&self, default_count: i64, id: i64) -> i64 {
+    // 增加博客浏览次数，同一访客（IP + User-Agent）在去重时间内只记录一次，爬虫不记录
+    pub async fn increase_in_view(
+        &self,
+        default_count: i64,
+        id: i64,
+        ip: &str,
+        user_agent: &str,
+        source: &ViewSource,
+    ) -> i64 {
+        if CONFIG.view.is_crawler(user_agent) {
+            return self.1.get_view_count(default_count, id);
+        }
+
+        let visitor = format!("{:x}", md5::compute(format!("{}|{}", ip, user_agent)));
+
+        if !self
+            .1
+            .mark_visitor(id, &visitor, CONFIG.view.dedup_window * MIN)
+        {
+            return self.1.get_view_count(default_count, id);
+        }
+
+        self.0.increase_daily_view(id).await;
+
+        let city = get_client_ip_city(ip);
+
+        let platform = get_client_platform_info(user_agent);
+
+        self.0
+            .insert_visit_log(id, &visitor, ip, &city, &platform, source)
+            .await;
+
         return self.1.increase_in_view(default_count, id);
     }
 
+    // 获取博客在日期范围内每天的浏览量
+    pub async fn get_daily_views(
+        &self,
+        id: i64,
+        req: &ViewRangeRequest,
+    ) -> Result<Vec<BlogViewDailyVo>, E> {
+        let (start, end) = match (
+            try_time_stamp_to_date(req.start),
+            try_time_stamp_to_date(req.end),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => {
+                return Err(E::error(
+                    Status::CHECK_DATA_ERROR,
+                    String::from("不正确的时间戳"),
+                ))
+            }
+        };
+
+        if start > end || (end - start).num_days() > MAX_VIEW_RANGE_DAYS {
+            return Err(E::error(
+                Status::QUERY_OR_PARAMS_ERROR,
+                format!("日期范围不正确，最多查询{}天", MAX_VIEW_RANGE_DAYS),
+            ));
+        }
+
+        return Ok(self.0.get_daily_views(id, &start, &end).await);
+    }
+
     // 获取用户的博客列表
     pub async fn get_blog_list_by_user(
         &self,
@@ -164,9 +227,26 @@ impl BlogService {
         return result;
     }
 
-    // 获取所有博客的简要信息
-    pub async fn get_all_simple_blog(&self) -> Vec<SearchBlogVo> {
-        return self.0.get_all_simple_blog().await;
+    // 获取搜索索引使用的博客文档，正文转换为纯文本摘录，ids 为空时获取所有博客
+    pub async fn get_search_blogs(&self, ids: Option<&Vec<i64>>) -> Vec<SearchBlogVo> {
+        let mut blogs = self.0.get_search_blogs(ids).await;
+        for blog in blogs.iter_mut() {
+            blog.content = markdown_to_text(&blog.content, SEARCH_CONTENT_MAX_LENGTH);
+        }
+        return blogs;
+    }
+
+    // 按 id 顺序分批获取搜索索引使用的博客文档
+    pub async fn get_search_blogs_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<SearchBlogVo>, E> {
+        let mut blogs = self.0.get_search_blogs_after(after_id, limit).await?;
+        for blog in blogs.iter_mut() {
+            blog.content = markdown_to_text(&blog.content, SEARCH_CONTENT_MAX_LENGTH);
+        }
+        return Ok(blogs);
     }
 
     pub async fn init_blog_eye_couunt(&self) {
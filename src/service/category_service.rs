@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use log::info;
@@ -6,15 +7,18 @@ use sqlx::{Pool, Postgres};
 
 use crate::common::redis_keys::CATEGORY_LIST_KEY;
 use crate::conf::redis_config::get_pool_connection;
-use crate::models::category::CategoryVo;
+use crate::error::custom_error::{E, Status};
+use crate::models::category::{CategoryTreeVo, CategoryVo};
 use crate::repository::category_repository::CategoryRepository;
+use crate::search::search_entity::SearchEntity;
+use crate::service::search_service::SearchService;
 
-pub struct CategoryService(Arc<CategoryRepository>);
+pub struct CategoryService(Arc<CategoryRepository>, Arc<SearchService>);
 
 impl CategoryService {
-    pub fn new(db_conn: Pool<Postgres>) -> CategoryService {
+    pub fn new(db_conn: Pool<Postgres>, search_service: Arc<SearchService>) -> CategoryService {
         let category_repository = CategoryRepository::new(db_conn);
-        CategoryService(Arc::new(category_repository))
+        CategoryService(Arc::new(category_repository), search_service)
     }
 
     // 从数据库获取分类列表
@@ -23,28 +27,74 @@ impl CategoryService {
         self.0.get_category_list().await
     }
 
-    // 添加分类
-    pub async fn add_category(&self, name: &String) -> Option<CategoryVo> {
+    // 添加分类，parent_id 不为空时必须是已存在的分类
+    pub async fn add_category(&self, name: &String, parent_id: Option<i64>) -> Result<CategoryVo, E> {
+        if let Some(pid) = parent_id {
+            if !self.0.category_exists(pid).await {
+                return Err(E::error(
+                    Status::CHECK_DATA_ERROR,
+                    String::from("父分类不存在"),
+                ));
+            }
+        }
+
         // 添加分类到数据库
-        return self.0.add_category(name).await;
+        return match self.0.add_category(name, parent_id).await {
+            Some(r) => {
+                self.1.sync_entities(SearchEntity::Category, vec![r.id]);
+                Ok(r)
+            }
+            None => Err(E::error(Status::ADD_ERROR, String::from("添加分类失败"))),
+        };
     }
 
-    // 从缓存获取分类列表
-    pub async fn get_category_for_cache(&self) -> Vec<CategoryVo> {
+    // 从缓存获取分类树
+    pub async fn get_category_for_cache(&self) -> Vec<CategoryTreeVo> {
         let result = get_pool_connection().get::<String, String>(CATEGORY_LIST_KEY.to_owned());
         return if let Ok(r) = result {
             serde_json::from_str(&r).unwrap()
         } else {
-            // 如果缓存中没有数据，从数据库中获取分类列表
-            let category_list = self.get_category_for_db().await;
+            // 如果缓存中没有数据，从数据库中获取分类节点并构建分类树
+            let category_tree = build_category_tree(self.0.get_category_nodes().await);
             // 将数据存入缓存
-            let json_str = serde_json::to_string(&category_list).unwrap();
+            let json_str = serde_json::to_string(&category_tree).unwrap();
             get_pool_connection()
                 .set::<String, String, String>(CATEGORY_LIST_KEY.to_owned(), json_str)
                 .unwrap();
-            info!("将分类列表存入缓存");
-            // 返回从数据库中获取的分类列表
-            category_list
+            info!("将分类树存入缓存");
+            // 返回从数据库中获取的分类树
+            category_tree
         };
     }
 }
+
+// 将扁平的分类节点构建成树，父分类不存在（已删除）的节点作为顶级分类
+fn build_category_tree(nodes: Vec<CategoryTreeVo>) -> Vec<CategoryTreeVo> {
+    let ids: Vec<i64> = nodes.iter().map(|n| n.id).collect();
+
+    let mut children_map: HashMap<i64, Vec<CategoryTreeVo>> = HashMap::new();
+
+    let mut roots: Vec<CategoryTreeVo> = Vec::new();
+
+    for node in nodes {
+        match node.parent_id {
+            Some(pid) if ids.contains(&pid) => children_map.entry(pid).or_default().push(node),
+            _ => roots.push(node),
+        }
+    }
+
+    fn attach(node: &mut CategoryTreeVo, children_map: &mut HashMap<i64, Vec<CategoryTreeVo>>) {
+        if let Some(mut children) = children_map.remove(&node.id) {
+            for child in children.iter_mut() {
+                attach(child, children_map);
+            }
+            node.children = children;
+        }
+    }
+
+    for root in roots.iter_mut() {
+        attach(root, &mut children_map);
+    }
+
+    return roots;
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use log::{error, info};
+use sqlx::{Pool, Postgres};
+
+use crate::conf::config::CONFIG;
+use crate::email::template::{CONTACT_MESSAGE, render};
+use crate::error::custom_error::{E, Status};
+use crate::models::contact::ContactMessageVo;
+use crate::repository::contact_repository::ContactRepository;
+use crate::request::admin_request::ContactMessageFilter;
+use crate::request::user_request::ContactRequest;
+use crate::response::page_info::PageInfo;
+use crate::service::email_queue_service::EmailQueueService;
+
+pub struct ContactService(Arc<ContactRepository>, Arc<EmailQueueService>);
+
+impl ContactService {
+    pub fn new(db_conn: Pool<Postgres>, email_queue_service: Arc<EmailQueueService>) -> ContactService {
+        let contact_repository = ContactRepository::new(db_conn);
+        ContactService(Arc::new(contact_repository), email_queue_service)
+    }
+
+    // 联系我，留言先保存到数据库再把邮件加入发送队列，邮件发送失败时留言不会丢失
+    pub async fn contact_me(&self, req: ContactRequest, ip: &str) -> Result<i64, E> {
+        if let Some(e) = req.check() {
+            return Err(E::error(Status::CHECK_DATA_ERROR, e));
+        }
+
+        let id = match self.0.insert_message(&req, ip).await {
+            Some(id) => id,
+            None => {
+                return Err(E::error(
+                    Status::DATABASE_ERROR,
+                    String::from("保存留言失败"),
+                ))
+            }
+        };
+
+        let rendered = render(
+            CONTACT_MESSAGE,
+            &[
+                ("subject", &req.subject),
+                ("name", &req.name),
+                ("email", &req.email),
+                ("content", &req.content),
+            ],
+            None,
+        )?;
+
+        match self.1.enqueue(&CONFIG.my_email, rendered).await {
+            Ok(email_id) => {
+                self.0.set_email_id(id, email_id).await;
+                info!("留言邮件已加入发送队列 id:{} email_id:{}", id, email_id);
+            }
+            Err(_) => error!("留言邮件加入发送队列失败，留言已保存 id:{}", id),
+        }
+
+        return Ok(id);
+    }
+
+    pub async fn get_message_list(&self, req: &ContactMessageFilter) -> PageInfo<ContactMessageVo> {
+        return self.0.get_message_list(req).await;
+    }
+
+    pub async fn update_read(&self, ids: &Vec<i64>, read: bool) -> i64 {
+        return self.0.update_read(ids, read).await;
+    }
+
+    pub async fn update_archived(&self, ids: &Vec<i64>, archived: bool) -> i64 {
+        return self.0.update_archived(ids, archived).await;
+    }
+
+    pub async fn delete_messages(&self, ids: &Vec<i64>) -> i64 {
+        return self.0.delete_messages(ids).await;
+    }
+}
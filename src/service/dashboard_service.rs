@@ -0,0 +1,277 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::web;
+use log::{error, info};
+use r2d2_redis::redis::Commands;
+use sqlx::{Pool, Postgres};
+use sqlx::types::chrono::{DateTime, Local};
+
+use crate::common::constants::{
+    DASHBOARD_DEFAULT_DAYS, DASHBOARD_MONTH_COUNT, DASHBOARD_TOP_COUNT, MAX_VIEW_RANGE_DAYS,
+};
+use crate::common::date_format::{FORMAT_DATE, try_time_stamp_to_date};
+use crate::common::get_size_str;
+use crate::common::redis_keys::{
+    DASHBOARD_INFO_EXPIRE, DASHBOARD_INFO_KEY, REFERRER_REPORT_KEY, STORAGE_INFO_EXPIRE,
+    STORAGE_INFO_KEY,
+};
+use crate::conf::config::CONFIG;
+use crate::conf::redis_config::get_pool_connection;
+use crate::error::custom_error::{E, Status};
+use crate::repository::dashboard_repository::DashboardRepository;
+use crate::request::admin_request::{DashboardRequest, ReferrerReportRequest};
+use crate::response::dashboard_info::{DashboardInfo, ReferrerReport, StorageInfo, StorageItem};
+
+pub struct DashboardService(Arc<DashboardRepository>);
+
+impl DashboardService {
+    pub fn new(db_conn: Pool<Postgres>) -> DashboardService {
+        let dashboard_repository = DashboardRepository::new(db_conn);
+        DashboardService(Arc::new(dashboard_repository))
+    }
+
+    // 获取统计面板信息，结果按日期范围缓存
+    pub async fn get_dashboard(&self, req: &DashboardRequest) -> Result<DashboardInfo, E> {
+        let (start, end) = get_date_range(req.start, req.end)?;
+
+        let top = req.top.unwrap_or(DASHBOARD_TOP_COUNT).clamp(1, 50);
+
+        let start_str = start.format(FORMAT_DATE).to_string();
+
+        let end_str = end.format(FORMAT_DATE).to_string();
+
+        let key = format!("{}{}_{}_{}", DASHBOARD_INFO_KEY, start_str, end_str, top);
+
+        if let Ok(r) = get_pool_connection().get::<&String, String>(&key) {
+            if let Ok(info) = serde_json::from_str(&r) {
+                return Ok(info);
+            }
+        }
+
+        let info = DashboardInfo {
+            top_blogs: self.0.get_top_blogs(&start, &end, top).await,
+            daily: self.0.get_daily_visits(&start, &end).await,
+            regions: self
+                .0
+                .get_visit_group_count("city", None, &start, &end, top)
+                .await,
+            platforms: self
+                .0
+                .get_visit_group_count("platform", None, &start, &end, top)
+                .await,
+            monthly_posts: self.0.get_monthly_posts(&end, DASHBOARD_MONTH_COUNT).await,
+            storage: get_storage_info().await,
+            start: start_str,
+            end: end_str,
+        };
+
+        let _ = get_pool_connection().set_ex::<&String, String, String>(
+            &key,
+            serde_json::to_string(&info).unwrap(),
+            DASHBOARD_INFO_EXPIRE,
+        );
+
+        info!("统计面板已更新: {}", key);
+
+        return Ok(info);
+    }
+
+    // 获取访问来源报表（来源域名、utm 来源/媒介/活动），可以按博客统计
+    pub async fn get_referrer_report(
+        &self,
+        req: &ReferrerReportRequest,
+    ) -> Result<ReferrerReport, E> {
+        let (start, end) = get_date_range(req.start, req.end)?;
+
+        let top = req.top.unwrap_or(DASHBOARD_TOP_COUNT).clamp(1, 50);
+
+        let blog_id = req.blog_id.filter(|id| *id > 0);
+
+        let start_str = start.format(FORMAT_DATE).to_string();
+
+        let end_str = end.format(FORMAT_DATE).to_string();
+
+        let key = format!(
+            "{}{}_{}_{}_{}",
+            REFERRER_REPORT_KEY,
+            blog_id.unwrap_or(0),
+            start_str,
+            end_str,
+            top
+        );
+
+        if let Ok(r) = get_pool_connection().get::<&String, String>(&key) {
+            if let Ok(report) = serde_json::from_str(&r) {
+                return Ok(report);
+            }
+        }
+
+        let (visitors, direct) = self
+            .0
+            .get_visitor_and_direct_count(blog_id, &start, &end)
+            .await;
+
+        let report = ReferrerReport {
+            blog_id,
+            visitors,
+            direct,
+            referrers: self
+                .0
+                .get_visit_group_count("referrer_domain", blog_id, &start, &end, top)
+                .await,
+            sources: self
+                .0
+                .get_visit_group_count("utm_source", blog_id, &start, &end, top)
+                .await,
+            mediums: self
+                .0
+                .get_visit_group_count("utm_medium", blog_id, &start, &end, top)
+                .await,
+            campaigns: self
+                .0
+                .get_visit_group_count("utm_campaign", blog_id, &start, &end, top)
+                .await,
+            start: start_str,
+            end: end_str,
+        };
+
+        let _ = get_pool_connection().set_ex::<&String, String, String>(
+            &key,
+            serde_json::to_string(&report).unwrap(),
+            DASHBOARD_INFO_EXPIRE,
+        );
+
+        return Ok(report);
+    }
+}
+
+// 解析统计的日期范围，默认统计截止今天的最近 DASHBOARD_DEFAULT_DAYS 天
+pub fn get_date_range(
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<(DateTime<Local>, DateTime<Local>), E> {
+    let invalid = || E::error(Status::CHECK_DATA_ERROR, String::from("不正确的时间戳"));
+
+    let end = match end {
+        Some(end) => try_time_stamp_to_date(end).ok_or_else(invalid)?,
+        None => Local::now(),
+    };
+
+    let start = match start {
+        Some(start) => try_time_stamp_to_date(start).ok_or_else(invalid)?,
+        None => end - Duration::from_secs(60 * 60 * 24 * (DASHBOARD_DEFAULT_DAYS as u64 - 1)),
+    };
+
+    if start > end {
+        return Err(E::error(
+            Status::CHECK_DATA_ERROR,
+            String::from("开始日期不能晚于结束日期"),
+        ));
+    }
+
+    if (end - start).num_days() > MAX_VIEW_RANGE_DAYS {
+        return Err(E::error(
+            Status::QUERY_OR_PARAMS_ERROR,
+            format!("日期范围不正确，最多查询{}天", MAX_VIEW_RANGE_DAYS),
+        ));
+    }
+
+    return Ok((start, end));
+}
+
+// 统计上传目录占用的存储空间，遍历目录会阻塞线程，放到阻塞线程池中执行
+async fn get_storage_info() -> StorageInfo {
+    return match web::block(load_storage_info).await {
+        Ok(info) => info,
+        Err(e) => {
+            error!("统计存储空间失败: {}", e);
+            StorageInfo::default()
+        }
+    };
+}
+
+// 遍历目录开销较大所以单独缓存
+fn load_storage_info() -> StorageInfo {
+    if let Ok(r) = get_pool_connection().get::<&str, String>(STORAGE_INFO_KEY) {
+        if let Ok(info) = serde_json::from_str(&r) {
+            return info;
+        }
+    }
+
+    let mut items: Vec<StorageItem> = Vec::new();
+
+    for name in [
+        &CONFIG.upload.image,
+        &CONFIG.upload.avatar,
+        &CONFIG.upload.files,
+    ] {
+        let path = format!("{}/{}", CONFIG.upload.path, name);
+        let (count, size) = get_dir_size(&path);
+        items.push(StorageItem {
+            name: name.to_owned(),
+            count,
+            size,
+            size_str: get_size_str(size as f64),
+        });
+    }
+
+    let total: u64 = items.iter().map(|i| i.size).sum();
+
+    let info = StorageInfo {
+        total,
+        total_str: get_size_str(total as f64),
+        items,
+    };
+
+    let _ = get_pool_connection().set_ex::<&str, String, String>(
+        STORAGE_INFO_KEY,
+        serde_json::to_string(&info).unwrap(),
+        STORAGE_INFO_EXPIRE,
+    );
+
+    return info;
+}
+
+// 递归统计目录下的文件数量和大小
+fn get_dir_size(path: &str) -> (u64, u64) {
+    let mut count = 0;
+    let mut size = 0;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_dir() {
+                    let (c, s) = get_dir_size(&entry.path().to_string_lossy());
+                    count += c;
+                    size += s;
+                } else {
+                    count += 1;
+                    size += metadata.len();
+                }
+            }
+        }
+    }
+    return (count, size);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_range_rejects_out_of_range_timestamps() {
+        let e = get_date_range(Some(i64::MAX), None).unwrap_err();
+        assert_eq!(e.code, Status::CHECK_DATA_ERROR);
+        let e = get_date_range(None, Some(i64::MIN)).unwrap_err();
+        assert_eq!(e.code, Status::CHECK_DATA_ERROR);
+    }
+
+    #[test]
+    fn date_range_rejects_start_after_end() {
+        let day = 24 * 60 * 60 * 1000;
+        let e = get_date_range(Some(1_700_000_000_000 + day), Some(1_700_000_000_000)).unwrap_err();
+        assert_eq!(e.code, Status::CHECK_DATA_ERROR);
+        assert!(get_date_range(Some(1_700_000_000_000), Some(1_700_000_000_000 + day)).is_ok());
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use sqlx::{Pool, Postgres};
+
+use crate::conf::config::CONFIG;
+use crate::email::template::RenderedEmail;
+use crate::email::transport::{EmailTransport, FileEmailTransport, SmtpEmailTransport};
+use crate::error::custom_error::{E, Status};
+use crate::models::email_queue::{EmailQueueVo, QueuedEmail};
+use crate::repository::email_queue_repository::EmailQueueRepository;
+use crate::request::admin_request::EmailQueueFilter;
+use crate::response::page_info::PageInfo;
+
+// 邮件先写入队列，由后台任务发送，失败后按 retry_interval * 2^(已尝试次数) 退避重试
+#[derive(Clone)]
+pub struct EmailQueueService(Arc<EmailQueueRepository>, Arc<dyn EmailTransport>);
+
+impl EmailQueueService {
+    pub fn new(db_conn: Pool<Postgres>) -> EmailQueueService {
+        let transport: Arc<dyn EmailTransport> = if CONFIG.email_queue.transport == "file" {
+            Arc::new(FileEmailTransport::new(&CONFIG.email_queue.file_dir))
+        } else {
+            Arc::new(SmtpEmailTransport)
+        };
+        EmailQueueService::with_transport(db_conn, transport)
+    }
+
+    pub fn with_transport(
+        db_conn: Pool<Postgres>,
+        transport: Arc<dyn EmailTransport>,
+    ) -> EmailQueueService {
+        let email_queue_repository = EmailQueueRepository::new(db_conn);
+        EmailQueueService(Arc::new(email_queue_repository), transport)
+    }
+
+    // 加入发送队列并立即在后台尝试发送，返回队列中的邮件id
+    pub async fn enqueue(&self, to: &str, email: RenderedEmail) -> Result<i64, E> {
+        return self.push(to, email, None).await;
+    }
+
+    // 加入发送队列，超过 max_age 秒还没有发送成功就丢弃，用于验证码等有时效的邮件
+    pub async fn enqueue_with_max_age(
+        &self,
+        to: &str,
+        email: RenderedEmail,
+        max_age: i64,
+    ) -> Result<i64, E> {
+        return self.push(to, email, Some(max_age)).await;
+    }
+
+    async fn push(&self, to: &str, email: RenderedEmail, max_age: Option<i64>) -> Result<i64, E> {
+        let id = self.0.insert_email(to, &email, max_age).await.ok_or(E::error(
+            Status::EMAIL_ERROR,
+            String::from("邮件加入发送队列失败"),
+        ))?;
+
+        self.process_in_background();
+
+        return Ok(id);
+    }
+
+    // 在后台立即发送到期的邮件，不等待下一次定时检查
+    pub fn process_in_background(&self) {
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            service.process_due().await;
+        });
+    }
+
+    // 发送到期的邮件，单次最多发送 batch_size 封，返回发送成功的数量，由入队和定时任务调用
+    pub async fn process_due(&self) -> usize {
+        let expired = self.0.expire_emails().await;
+        if expired > 0 {
+            warn!("{}封邮件超过有效期未发送，已进入死信", expired);
+        }
+
+        let mut count = 0;
+
+        // 逐封领取，租约只覆盖这一封邮件的发送时间，没有处理完的邮件会被重新取出
+        for _ in 0..CONFIG.email_queue.batch_size {
+            let email = match self.0.claim_due_email(300).await {
+                Some(email) => email,
+                None => break,
+            };
+            if self.send(email).await {
+                count += 1;
+            }
+        }
+
+        return count;
+    }
+
+    async fn send(&self, email: QueuedEmail) -> bool {
+        let transport = self.1.clone();
+
+        let (id, to, attempts) = (email.id, email.to_email.to_owned(), email.attempts + 1);
+
+        // 发送是阻塞的，放到线程池中执行
+        let result = actix_web::web::block(move || {
+            let rendered = RenderedEmail {
+                subject: email.subject,
+                html: email.html,
+                text: email.text,
+            };
+            transport.send(&email.to_email, &rendered)
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+        return match result {
+            Ok(_) => {
+                self.0.mark_sent(id).await;
+                info!("邮件发送成功 id:{} to:{}", id, to);
+                true
+            }
+            Err(e) => {
+                let retry_after = if attempts >= CONFIG.email_queue.max_attempts {
+                    error!("邮件多次发送失败，进入死信 id:{} to:{} error:{}", id, to, e);
+                    None
+                } else {
+                    warn!("邮件发送失败 id:{} to:{} error:{} 第{}次", id, to, e, attempts);
+                    Some(CONFIG.email_queue.retry_interval * 2_i64.pow((attempts - 1).min(20) as u32))
+                };
+                self.0.mark_failed(id, &e, retry_after).await;
+                false
+            }
+        };
+    }
+
+    pub async fn get_email_list(&self, req: &EmailQueueFilter) -> PageInfo<EmailQueueVo> {
+        return self.0.get_email_list(req).await;
+    }
+
+    // 重新发送死信
+    pub async fn retry_email(&self, id: i64) -> i64 {
+        let result = self.0.retry_email(id).await;
+        if result > 0 {
+            let service = self.clone();
+            actix_web::rt::spawn(async move {
+                service.process_due().await;
+            });
+        }
+        return result;
+    }
+
+    pub async fn delete_email(&self, id: i64) -> i64 {
+        return self.0.delete_email(id).await;
+    }
+}
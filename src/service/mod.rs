@@ -1,8 +1,16 @@
 pub mod admin_service;
 pub mod blog_service;
 pub mod category_service;
+pub mod contact_service;
+pub mod dashboard_service;
+pub mod email_queue_service;
 pub mod file_service;
 pub mod gpt_service;
+pub mod newsletter_service;
+pub mod search_service;
+pub mod session_service;
 pub mod tag_service;
 pub mod topic_service;
 pub mod user_service;
+pub mod webhook_service;
+pub mod webmention_service;
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use r2d2_redis::redis;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::cache::incr_rate_limit;
+use crate::common::constants::NEWSLETTER_SUBSCRIBE_IP_LIMIT;
+use crate::common::redis_keys::{
+    NEWSLETTER_SUBSCRIBE_EXPIRE, NEWSLETTER_SUBSCRIBE_IP_EXPIRE, NEWSLETTER_SUBSCRIBE_IP_KEY,
+    NEWSLETTER_SUBSCRIBE_KEY,
+};
+use crate::conf::config::CONFIG;
+use crate::conf::redis_config::get_pool_connection;
+use crate::email::template::{NEWSLETTER_CONFIRM, NEWSLETTER_POST, render, RenderedEmail};
+use crate::error::custom_error::{E, Status};
+use crate::models::newsletter::{NewsletterBlogVo, SubscriberVo};
+use crate::repository::newsletter_repository::NewsletterRepository;
+use crate::request::newsletter_request::SubscribeRequest;
+use crate::service::email_queue_service::EmailQueueService;
+
+#[derive(Clone)]
+pub struct NewsletterService(Arc<NewsletterRepository>, Arc<EmailQueueService>);
+
+impl NewsletterService {
+    pub fn new(db_conn: Pool<Postgres>, email_queue_service: Arc<EmailQueueService>) -> NewsletterService {
+        let newsletter_repository = NewsletterRepository::new(db_conn);
+        NewsletterService(Arc::new(newsletter_repository), email_queue_service)
+    }
+
+    // 订阅，保存后发送确认邮件，点击确认链接后才会收到通知
+    pub async fn subscribe(&self, req: SubscribeRequest, ip: &str) -> Result<(), E> {
+        if let Some(e) = req.check() {
+            return Err(E::error(Status::CHECK_DATA_ERROR, e));
+        }
+
+        // 同一 IP 限制订阅次数，避免用不同的邮箱批量发送确认邮件
+        let count = incr_rate_limit(
+            format!("{}{}", NEWSLETTER_SUBSCRIBE_IP_KEY, ip),
+            NEWSLETTER_SUBSCRIBE_IP_EXPIRE,
+        );
+
+        if count > NEWSLETTER_SUBSCRIBE_IP_LIMIT {
+            return Err(E::error(
+                Status::EMAIL_ERROR,
+                String::from("请求太频繁，请稍后再试"),
+            ));
+        }
+
+        let email = req.email.trim().to_lowercase();
+
+        // 同一邮箱限制确认邮件的发送频率
+        let allowed: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{}", NEWSLETTER_SUBSCRIBE_KEY, email))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(NEWSLETTER_SUBSCRIBE_EXPIRE)
+            .query(&mut *get_pool_connection())
+            .unwrap_or(Some(String::from("OK")));
+
+        if allowed.is_none() {
+            return Err(E::error(
+                Status::EMAIL_ERROR,
+                String::from("请求太频繁，请稍后再试"),
+            ));
+        }
+
+        let mut categories = req.categories.clone();
+        categories.sort();
+        categories.dedup();
+
+        let mut topics = req.topics.clone();
+        topics.sort();
+        topics.dedup();
+
+        let confirm_token = Uuid::new_v4().simple().to_string();
+
+        let unsubscribe_token = Uuid::new_v4().simple().to_string();
+
+        if !self
+            .0
+            .save_subscriber(&email, &categories, &topics, &confirm_token, &unsubscribe_token)
+            .await
+        {
+            return Err(E::error(
+                Status::DATABASE_ERROR,
+                String::from("订阅失败"),
+            ));
+        }
+
+        let link = format!("{}/confirm?token={}", CONFIG.newsletter.api_url, confirm_token);
+
+        let rendered = render(NEWSLETTER_CONFIRM, &[("link", &link)], None)?;
+
+        if self.1.enqueue(&email, rendered).await.is_err() {
+            error!("发送订阅确认邮件失败：{}", email);
+            return Err(E::error(
+                Status::EMAIL_ERROR,
+                String::from("发送确认邮件失败"),
+            ));
+        }
+
+        info!("发送订阅确认邮件至：{}", email);
+
+        return Ok(());
+    }
+
+    pub async fn confirm(&self, token: &str) -> Result<(), E> {
+        let next_token = Uuid::new_v4().simple().to_string();
+        if token.is_empty() || !self.0.confirm_subscriber(token, &next_token).await {
+            return Err(E::error(
+                Status::INVALID_TOKEN_ERROR,
+                String::from("确认链接无效或已使用"),
+            ));
+        }
+        return Ok(());
+    }
+
+    pub async fn unsubscribe(&self, token: &str) -> Result<(), E> {
+        if token.is_empty() || !self.0.unsubscribe(token).await {
+            return Err(E::error(
+                Status::INVALID_TOKEN_ERROR,
+                String::from("退订链接无效"),
+            ));
+        }
+        return Ok(());
+    }
+
+    // 博客发布后在后台发送通知，不阻塞保存博客的请求
+    pub fn notify_on_publish(&self, blog_id: i64) {
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            service.send_notifications(blog_id).await;
+        });
+    }
+
+    // 服务重启后继续发送上次没有发送完的通知
+    pub async fn resume_notifications(&self) {
+        for blog_id in self.0.get_unfinished_issue_ids().await {
+            info!("继续发送订阅通知 博客id:{}", blog_id);
+            self.send_notifications(blog_id).await;
+        }
+    }
+
+    // 分批把新博客通知加入邮件队列，每批之间间隔 batch_interval 秒，返回加入队列的数量。
+    // 每批邮件和发送进度在同一事务中保存，中途退出后从上次的进度继续，渲染失败时停止整个发送
+    pub async fn send_notifications(&self, blog_id: i64) -> i64 {
+        let blog = match self.0.get_newsletter_blog(blog_id).await {
+            Some(blog) => blog,
+            None => return 0,
+        };
+
+        let mut after_id = match self.0.start_issue(blog_id).await {
+            Some(id) => id,
+            None => {
+                info!("博客已经发送过订阅通知 博客id:{}", blog_id);
+                return 0;
+            }
+        };
+
+        let category_ids = match blog.category_id {
+            Some(id) => self.0.get_category_with_ancestors(id).await,
+            None => vec![],
+        };
+
+        let mut count = 0;
+
+        loop {
+            let subscribers = self
+                .0
+                .get_subscriber_batch(&category_ids, blog.topic_id, after_id, CONFIG.newsletter.batch_size)
+                .await;
+
+            if subscribers.is_empty() {
+                break;
+            }
+
+            let mut emails = Vec::with_capacity(subscribers.len());
+
+            for subscriber in &subscribers {
+                match build_notification(&blog, subscriber) {
+                    Ok(r) => emails.push((subscriber.email.to_owned(), r)),
+                    Err(e) => {
+                        error!("渲染订阅通知失败，停止发送 博客id:{} {}", blog_id, e.message);
+                        return count;
+                    }
+                }
+            }
+
+            let last_id = subscribers.last().unwrap().id;
+
+            if !self.0.save_issue_batch(blog_id, after_id, last_id, &emails).await {
+                error!("保存订阅通知失败，停止发送 博客id:{}", blog_id);
+                return count;
+            }
+
+            after_id = last_id;
+
+            count += emails.len() as i64;
+
+            self.1.process_in_background();
+
+            if (subscribers.len() as i64) < CONFIG.newsletter.batch_size {
+                break;
+            }
+
+            actix_web::rt::time::sleep(Duration::from_secs(CONFIG.newsletter.batch_interval)).await;
+        }
+
+        self.0.finish_issue(blog_id).await;
+
+        info!("发送订阅通知完成 博客id:{} 数量:{}", blog_id, count);
+
+        return count;
+    }
+}
+
+fn build_notification(
+    blog: &NewsletterBlogVo,
+    subscriber: &SubscriberVo,
+) -> Result<RenderedEmail, E> {
+    let link = CONFIG.webmention.get_blog_url(blog.id);
+    let unsubscribe = format!(
+        "{}/unsubscribe?token={}",
+        CONFIG.newsletter.api_url, subscriber.unsubscribe_token
+    );
+    return render(
+        NEWSLETTER_POST,
+        &[
+            ("title", &blog.title),
+            ("description", &blog.description),
+            ("link", &link),
+            ("unsubscribe", &unsubscribe),
+        ],
+        None,
+    );
+}
@@ -0,0 +1,655 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use futures::future::join_all;
+use log::{error, info, warn};
+use r2d2_redis::redis::Commands;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+
+use crate::common::constants::{
+    DASHBOARD_TOP_COUNT, SEARCH_ALL_GROUP_SIZE, SEARCH_QUERY_MAX_CHARS,
+    SEARCH_QUERY_RETENTION_DAYS, SEARCH_RECONCILE_BATCH_SIZE, SEARCH_SUGGEST_CACHE_CHARS,
+    SEARCH_SUGGEST_COUNT, SEARCH_SUGGEST_MAX_CHARS,
+};
+use crate::common::date_format::FORMAT_DATE;
+use crate::common::escape_html;
+use crate::common::redis_keys::{
+    SEARCH_REPORT_EXPIRE, SEARCH_REPORT_KEY, SEARCH_SUGGEST_EXPIRE, SEARCH_SUGGEST_KEY,
+};
+use crate::conf::config::CONFIG;
+use crate::conf::redis_config::get_pool_connection;
+use crate::error::custom_error::{E, Status};
+use crate::models::blogs::{SearchBlogVo, SimpleBlogVo};
+use crate::repository::search_repository::SearchRepository;
+use crate::request::admin_request::DashboardRequest;
+use crate::request::blog_request::SearchQueryRequest;
+use crate::response::search_info::{
+    SearchAllResult, SearchIndexReconcile, SearchQueryReport, SearchReconcileReport,
+    SearchScope, SearchSuggestion,
+};
+use crate::search::backend::{should_fallback, SearchBackend};
+use crate::search::meillsearch_response::{
+    Formatted, Hits, SEARCH_BLOG_FIELDS, SEARCH_ENTITY_FIELDS, SearchPage, SearchResponse,
+};
+use crate::search::search_entity::SearchEntity;
+use crate::service::blog_service::BlogService;
+use crate::service::dashboard_service::get_date_range;
+
+// 搜索索引的同步，博客、专题、标签、分类和作者变更后都需要调用
+#[derive(Clone)]
+pub struct SearchService(Arc<BlogService>, Arc<dyn SearchBackend>, Arc<SearchRepository>);
+
+impl SearchService {
+    pub fn new(
+        db_conn: Pool<Postgres>,
+        blog_service: Arc<BlogService>,
+        search_client: Arc<dyn SearchBackend>,
+    ) -> SearchService {
+        let search_repository = SearchRepository::new(db_conn);
+        SearchService(blog_service, search_client, Arc::new(search_repository))
+    }
+
+    // 搜索博客，搜索服务不可用或搜索索引不支持过滤条件时（例如索引还没有更新设置）改为直接查询数据库
+    pub async fn search(&self, req: &SearchQueryRequest) -> Result<SearchPage, E> {
+        let scope = self.get_scope(req).await?;
+
+        let r = match self
+            .1
+            .search_documents(&CONFIG.blog_search_index, req)
+            .await
+        {
+            Ok(r) => r,
+            // 和备用搜索后端使用相同的规则：搜索服务不可用或旧索引没有声明过滤字段时改为查询数据库，
+            // 其他错误直接返回
+            Err(e) if should_fallback(&e) => {
+                warn!("搜索失败，改为查询数据库: {}", e.message);
+                self.search_in_database(req).await
+            }
+            Err(e) => return Err(e),
+        };
+
+        return Ok(SearchPage {
+            page: req.get_page(),
+            size: req.get_size(),
+            total: r.total_hits,
+            data: r.hits,
+            facets: r.facet_distribution,
+            scope,
+        });
+    }
+
+    // 查询范围对应的专题、分类和作者名称，不存在时返回错误
+    async fn get_scope(&self, req: &SearchQueryRequest) -> Result<Vec<SearchScope>, E> {
+        let mut scope = vec![];
+        for (entity, id) in req.get_scope() {
+            let entity_name = match entity {
+                SearchEntity::Topic => "专题",
+                SearchEntity::Category => "分类",
+                _ => "作者",
+            };
+            let name = self
+                .2
+                .get_entity_documents(entity, Some(&vec![id]))
+                .await
+                .pop()
+                .map(|d| d.name)
+                .ok_or(E::error(
+                    Status::CHECK_DATA_ERROR,
+                    format!("{}不存在", entity_name),
+                ))?;
+            scope.push(SearchScope { entity, id, name });
+        }
+        return Ok(scope);
+    }
+
+    // 数据库查询没有相关度和分面统计，只按标题是否匹配排序
+    async fn search_in_database(&self, req: &SearchQueryRequest) -> SearchResponse {
+        let (total, ids) = self.2.find_blog_ids(req).await;
+        let mut blogs: HashMap<i64, SearchBlogVo> = self
+            .0
+            .get_search_blogs(Some(&ids))
+            .await
+            .into_iter()
+            .map(|b| (b.id, b))
+            .collect();
+
+        let crop_length = req.get_crop_length() as usize * 2;
+        let hits = ids
+            .iter()
+            .filter_map(|id| blogs.remove(id))
+            .map(|b| Hits {
+                id: b.id,
+                _formatted: Formatted {
+                    id: b.id.to_string(),
+                    title: escape_html(&b.title),
+                    description: escape_html(&b.description),
+                    content: escape_html(&b.content.chars().take(crop_length).collect::<String>()),
+                },
+                title: b.title,
+                description: b.description,
+                tags: b.tags,
+                category_id: b.category_id,
+                category: b.category,
+                topic_id: b.topic_id,
+                topic: b.topic,
+                author_id: Some(b.author_id),
+                author: Some(b.author),
+                create_at: Some(b.create_at),
+                views: Some(b.views),
+            })
+            .collect();
+
+        return SearchResponse {
+            hits,
+            offset: req.get_offset(),
+            limit: req.get_size(),
+            total_hits: total,
+            query: req.keyword.to_owned(),
+            facet_distribution: HashMap::new(),
+        };
+    }
+
+    // 搜索建议，输入时每次按键都会调用，短关键字的结果缓存在 Redis 中
+    pub async fn suggest(&self, keyword: &str) -> Result<SearchSuggestion, E> {
+        let keyword = keyword.trim().to_lowercase();
+
+        if keyword.is_empty() {
+            return Ok(SearchSuggestion::default());
+        }
+
+        if keyword.chars().count() > SEARCH_SUGGEST_MAX_CHARS {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                format!("关键字不能超过{}个字符", SEARCH_SUGGEST_MAX_CHARS),
+            ));
+        }
+
+        let cache = keyword.chars().count() <= SEARCH_SUGGEST_CACHE_CHARS;
+        let key = format!("{}{}", SEARCH_SUGGEST_KEY, keyword);
+
+        if cache {
+            if let Ok(r) = get_pool_connection().get::<&String, String>(&key) {
+                if let Ok(suggestion) = serde_json::from_str(&r) {
+                    return Ok(suggestion);
+                }
+            }
+        }
+
+        // 搜索服务不可用时只返回标签和专题
+        let titles = match self
+            .1
+            .suggest_documents(
+                &CONFIG.blog_search_index,
+                &keyword,
+                SEARCH_SUGGEST_COUNT as i64,
+            )
+            .await
+        {
+            Ok(r) => r
+                .hits
+                .into_iter()
+                .take(SEARCH_SUGGEST_COUNT)
+                .map(|h| SimpleBlogVo {
+                    id: h.id,
+                    title: h.title,
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        let suggestion = SearchSuggestion {
+            titles,
+            tags: self.2.find_tags(&keyword, SEARCH_SUGGEST_COUNT as i64).await,
+            topics: self.2.find_topics(&keyword, SEARCH_SUGGEST_COUNT as i64).await,
+        };
+
+        if cache {
+            let _ = get_pool_connection().set_ex::<&String, String, ()>(
+                &key,
+                serde_json::to_string(&suggestion).unwrap(),
+                SEARCH_SUGGEST_EXPIRE,
+            );
+        }
+
+        return Ok(suggestion);
+    }
+
+    // 在后台记录搜索关键字和结果数量，爬虫的搜索和没有文字的关键字不记录
+    pub fn record_query(&self, keyword: &str, result_count: i64, page: i64, user_agent: &str) {
+        if CONFIG.view.is_crawler(user_agent) {
+            return;
+        }
+        let keyword: String = keyword
+            .trim()
+            .to_lowercase()
+            .chars()
+            .take(SEARCH_QUERY_MAX_CHARS)
+            .collect();
+        if !keyword.chars().any(|c| c.is_alphanumeric()) {
+            return;
+        }
+        let repository = self.2.clone();
+        actix_web::rt::spawn(async move {
+            repository.insert_query(&keyword, result_count, page).await;
+        });
+    }
+
+    // 删除超过保留天数的搜索记录，由每日定时任务调用
+    pub async fn delete_old_queries(&self) {
+        let count = self.2.delete_old_queries(SEARCH_QUERY_RETENTION_DAYS).await;
+        if count > 0 {
+            info!("删除过期的搜索记录 {} 条", count);
+        }
+    }
+
+    // 搜索关键字报表：热门关键字、无结果的关键字和每日搜索趋势，结果按日期范围缓存
+    pub async fn get_query_report(&self, req: &DashboardRequest) -> Result<SearchQueryReport, E> {
+        let (start, end) = get_date_range(req.start, req.end)?;
+
+        let top = req.top.unwrap_or(DASHBOARD_TOP_COUNT).clamp(1, 50);
+
+        let start_str = start.format(FORMAT_DATE).to_string();
+
+        let end_str = end.format(FORMAT_DATE).to_string();
+
+        let key = format!("{}{}_{}_{}", SEARCH_REPORT_KEY, start_str, end_str, top);
+
+        if let Ok(r) = get_pool_connection().get::<&String, String>(&key) {
+            if let Ok(report) = serde_json::from_str(&r) {
+                return Ok(report);
+            }
+        }
+
+        let (total, zero_results) = self.2.get_query_count(&start, &end).await;
+
+        let report = SearchQueryReport {
+            total,
+            zero_results,
+            top_queries: self.2.get_top_queries(&start, &end, false, top).await,
+            zero_result_queries: self.2.get_top_queries(&start, &end, true, top).await,
+            daily: self.2.get_daily_queries(&start, &end).await,
+            start: start_str,
+            end: end_str,
+        };
+
+        let _ = get_pool_connection().set_ex::<&String, String, ()>(
+            &key,
+            serde_json::to_string(&report).unwrap(),
+            SEARCH_REPORT_EXPIRE,
+        );
+
+        return Ok(report);
+    }
+
+    // 在后台按数据库中的最新状态同步这些博客，已删除的博客从索引中移除
+    pub fn sync_blogs(&self, ids: Vec<i64>) {
+        if ids.is_empty() {
+            return;
+        }
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = service.sync_blogs_now(ids).await {
+                error!("同步博客搜索索引失败: {}", e.message);
+            }
+        });
+    }
+
+    // 等待索引任务执行完成，任务失败时返回错误
+    pub async fn sync_blogs_now(&self, ids: Vec<i64>) -> Result<(), E> {
+        let index = &CONFIG.blog_search_index;
+        let documents = self.0.get_search_blogs(Some(&ids)).await;
+
+        let exists: HashSet<i64> = documents.iter().map(|d| d.id).collect();
+        let removed: Vec<i64> = ids.into_iter().filter(|id| !exists.contains(id)).collect();
+
+        if !documents.is_empty() {
+            let task = self
+                .1
+                .save_documents(index, serde_json::to_value(&documents).unwrap())
+                .await?;
+            self.wait_for_task(task).await?;
+        }
+
+        if !removed.is_empty() {
+            let task = self.1.delete_documents(index, removed).await?;
+            self.wait_for_task(task).await?;
+        }
+
+        return Ok(());
+    }
+
+    // 等待搜索后端的异步任务完成，没有任务时直接返回
+    pub async fn wait_for_task(&self, task_uid: Option<u64>) -> Result<(), E> {
+        return match task_uid {
+            Some(task_uid) => self.1.wait_for_task(task_uid).await,
+            None => Ok(()),
+        };
+    }
+
+    // 在后台同步专题、标签、分类或作者，已删除的记录从索引中移除
+    pub fn sync_entities(&self, entity: SearchEntity, ids: Vec<i64>) {
+        if ids.is_empty() {
+            return;
+        }
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = service.sync_entities_now(entity, ids).await {
+                error!("同步 {} 搜索索引失败: {}", entity.index(), e.message);
+            }
+        });
+    }
+
+    // 在后台同步专题以及专题下的博客，博客文档中保存了专题名称，新增和修改专题都使用这个方法
+    pub fn sync_topics(&self, ids: Vec<i64>) {
+        if ids.is_empty() {
+            return;
+        }
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = service.sync_entities_now(SearchEntity::Topic, ids.clone()).await {
+                error!("同步 {} 搜索索引失败: {}", SearchEntity::Topic.index(), e.message);
+            }
+            let blog_ids = service.2.get_blog_ids_by_topics(&ids).await;
+            if blog_ids.is_empty() {
+                return;
+            }
+            if let Err(e) = service.sync_blogs_now(blog_ids).await {
+                error!("同步博客搜索索引失败: {}", e.message);
+            }
+        });
+    }
+
+    pub async fn sync_entities_now(&self, entity: SearchEntity, ids: Vec<i64>) -> Result<(), E> {
+        let index = entity.index();
+        let documents = self.2.get_entity_documents(entity, Some(&ids)).await;
+
+        let exists: HashSet<i64> = documents.iter().map(|d| d.id).collect();
+        let removed: Vec<i64> = ids.into_iter().filter(|id| !exists.contains(id)).collect();
+
+        if !documents.is_empty() {
+            let task = self
+                .1
+                .save_documents(index, serde_json::to_value(&documents).unwrap())
+                .await?;
+            self.wait_for_task(task).await?;
+        }
+
+        if !removed.is_empty() {
+            let task = self.1.delete_documents(index, removed).await?;
+            self.wait_for_task(task).await?;
+        }
+
+        return Ok(());
+    }
+
+    // 在后台对比单个实体的索引，用于无法确定变更记录的情况，例如拆分标签
+    pub fn refresh_entities(&self, entity: SearchEntity) {
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = service.reconcile_entity(entity).await {
+                error!("同步 {} 搜索索引失败: {}", entity.index(), e.message);
+            }
+        });
+    }
+
+    // 初始化专题、标签、分类和作者的索引，返回写入的文档数量
+    pub async fn init_entities(&self) -> Result<usize, E> {
+        let mut count = 0;
+        for entity in SearchEntity::ALL {
+            let index = entity.index();
+            let documents = self.2.get_entity_documents(entity, None).await;
+            count += documents.len();
+            let task = self.1.create_index(index).await?;
+            self.wait_for_task(task).await?;
+            let task = self.1.delete_all_documents(index).await?;
+            self.wait_for_task(task).await?;
+            if !documents.is_empty() {
+                let documents = serde_json::to_value(&documents).map_err(|e| {
+                    E::error(Status::SERVER_ERROR, format!("序列化搜索文档失败: {}", e))
+                })?;
+                let task = self.1.save_documents(index, documents).await?;
+                self.wait_for_task(task).await?;
+            }
+        }
+        return Ok(count);
+    }
+
+    // 全站搜索，同时搜索博客、专题、标签、分类和作者，每种最多返回 SEARCH_ALL_GROUP_SIZE 个
+    pub async fn search_all(&self, keyword: &str) -> Result<SearchAllResult, E> {
+        let keyword = keyword.trim();
+
+        if keyword.chars().count() > SEARCH_QUERY_MAX_CHARS {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                format!("关键字不能超过{}个字符", SEARCH_QUERY_MAX_CHARS),
+            ));
+        }
+
+        let mut result = SearchAllResult::default();
+        if keyword.is_empty() {
+            return Ok(result);
+        }
+
+        let mut req = SearchQueryRequest::from_keyword(keyword);
+        req.size = Some(SEARCH_ALL_GROUP_SIZE);
+
+        let blogs = self.1.search_documents(&CONFIG.blog_search_index, &req);
+        let entities = join_all(SearchEntity::ALL.into_iter().map(|entity| async move {
+            let r = self
+                .1
+                .search_entities(entity.index(), keyword, SEARCH_ALL_GROUP_SIZE)
+                .await;
+            (entity, r)
+        }));
+        let (blogs, entities) = futures::join!(blogs, entities);
+
+        // 某个索引搜索失败时只影响对应的分组
+        match blogs {
+            Ok(r) => result.blogs = r.hits,
+            Err(e) => error!("全站搜索博客失败: {}", e.message),
+        }
+        for (entity, r) in entities {
+            match r {
+                Ok(r) => *result.group_mut(entity) = r.hits,
+                Err(e) => error!("全站搜索 {} 失败: {}", entity.index(), e.message),
+            }
+        }
+
+        return Ok(result);
+    }
+
+    // 对比索引和数据库，补充缺少的文档、更新过期的文档并删除多余的文档
+    pub async fn reconcile(&self) -> Result<SearchReconcileReport, E> {
+        let index = &CONFIG.blog_search_index;
+        let (mut indexed, mut blog) = self.load_indexed(index, &SEARCH_BLOG_FIELDS).await?;
+
+        // 按 id 分批读取博客，避免一次把所有博客加载到内存中
+        let mut after_id = 0;
+        loop {
+            let blogs = self
+                .0
+                .get_search_blogs_after(after_id, SEARCH_RECONCILE_BATCH_SIZE)
+                .await?;
+            let count = blogs.len() as i64;
+            match blogs.last() {
+                Some(b) => after_id = b.id,
+                None => break,
+            }
+            let documents = to_documents(&blogs)?;
+            self.reconcile_documents(
+                index,
+                &mut indexed,
+                documents,
+                &SEARCH_BLOG_FIELDS,
+                &mut blog,
+            )
+            .await?;
+            if count < SEARCH_RECONCILE_BATCH_SIZE {
+                break;
+            }
+        }
+
+        self.remove_stale_documents(index, indexed, &mut blog).await?;
+
+        let mut report = SearchReconcileReport {
+            blogs: blog.documents,
+            indexed: blog.indexed,
+            added: blog.added,
+            updated: blog.updated,
+            removed: blog.removed,
+            entities: vec![],
+        };
+
+        for entity in SearchEntity::ALL {
+            report.entities.push(self.reconcile_entity(entity).await?);
+        }
+
+        return Ok(report);
+    }
+
+    pub async fn reconcile_entity(&self, entity: SearchEntity) -> Result<SearchIndexReconcile, E> {
+        let index = entity.index();
+        let (mut indexed, mut report) = self.load_indexed(index, &SEARCH_ENTITY_FIELDS).await?;
+
+        let mut after_id = 0;
+        loop {
+            let entities = self
+                .2
+                .get_entity_documents_after(entity, after_id, SEARCH_RECONCILE_BATCH_SIZE)
+                .await?;
+            let count = entities.len() as i64;
+            match entities.last() {
+                Some(e) => after_id = e.id,
+                None => break,
+            }
+            let documents = to_documents(&entities)?;
+            self.reconcile_documents(
+                index,
+                &mut indexed,
+                documents,
+                &SEARCH_ENTITY_FIELDS,
+                &mut report,
+            )
+            .await?;
+            if count < SEARCH_RECONCILE_BATCH_SIZE {
+                break;
+            }
+        }
+
+        self.remove_stale_documents(index, indexed, &mut report).await?;
+        return Ok(report);
+    }
+
+    // 读取索引中所有文档的 fields 字段，按 id 保存
+    async fn load_indexed(
+        &self,
+        index: &str,
+        fields: &[&str],
+    ) -> Result<(HashMap<i64, Value>, SearchIndexReconcile), E> {
+        let indexed: HashMap<i64, Value> = self
+            .1
+            .get_documents(index, fields)
+            .await?
+            .into_iter()
+            .filter_map(|d| d.get("id").and_then(|id| id.as_i64()).map(|id| (id, d)))
+            .collect();
+
+        let report = SearchIndexReconcile {
+            index: index.to_string(),
+            indexed: indexed.len(),
+            ..Default::default()
+        };
+
+        return Ok((indexed, report));
+    }
+
+    // 对比一批数据库文档，只比较 fields 中的字段，比较过的文档从 indexed 中移除
+    async fn reconcile_documents(
+        &self,
+        index: &str,
+        indexed: &mut HashMap<i64, Value>,
+        documents: Vec<Value>,
+        fields: &[&str],
+        report: &mut SearchIndexReconcile,
+    ) -> Result<(), E> {
+        report.documents += documents.len();
+
+        let mut changed = vec![];
+        for current in documents {
+            let id = match current.get("id").and_then(|id| id.as_i64()) {
+                Some(id) => id,
+                None => continue,
+            };
+            let mut document = current.clone();
+            if let Value::Object(map) = &mut document {
+                map.retain(|k, _| fields.contains(&k.as_str()));
+            }
+            match indexed.remove(&id) {
+                None => {
+                    report.added += 1;
+                    changed.push(current);
+                }
+                Some(d) if !same_document(&d, &document) => {
+                    report.updated += 1;
+                    changed.push(current);
+                }
+                _ => {}
+            }
+        }
+
+        if !changed.is_empty() {
+            let task = self.1.save_documents(index, Value::Array(changed)).await?;
+            self.wait_for_task(task).await?;
+        }
+
+        return Ok(());
+    }
+
+    // indexed 中剩下的文档在数据库中已经不存在，从索引中删除
+    async fn remove_stale_documents(
+        &self,
+        index: &str,
+        indexed: HashMap<i64, Value>,
+        report: &mut SearchIndexReconcile,
+    ) -> Result<(), E> {
+        let removed: Vec<i64> = indexed.into_keys().collect();
+        report.removed = removed.len();
+
+        if !removed.is_empty() {
+            let task = self.1.delete_documents(index, removed).await?;
+            self.wait_for_task(task).await?;
+        }
+
+        info!(
+            "搜索索引对比完成 索引:{} 文档:{} 已索引:{} 新增:{} 更新:{} 删除:{}",
+            report.index,
+            report.documents,
+            report.indexed,
+            report.added,
+            report.updated,
+            report.removed
+        );
+
+        return Ok(());
+    }
+}
+
+fn to_documents<T: Serialize>(items: &[T]) -> Result<Vec<Value>, E> {
+    return items
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|e| E::error(Status::SERVER_ERROR, format!("序列化搜索文档失败: {}", e)));
+}
+
+// 索引中的文档可能缺少字段，缺少的字段按 null 处理
+fn same_document(indexed: &Value, document: &Value) -> bool {
+    return match (indexed, document) {
+        (Value::Object(a), Value::Object(b)) => b
+            .iter()
+            .all(|(k, v)| a.get(k).unwrap_or(&Value::Null) == v),
+        _ => false,
+    };
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use sqlx::types::chrono::Local;
+use uuid::Uuid;
+
+use crate::cache::session_cache::SessionCache;
+use crate::common::constants::{SESSION_DEVICE_MAX_LENGTH, SESSION_RETENTION_DAYS};
+use crate::conf::config::CONFIG;
+use crate::conf::token_config::Claims;
+use crate::error::custom_error::{E, Status};
+use crate::models::session::{SessionClient, UserSessionVo};
+use crate::models::user::UserVo;
+use crate::repository::session_repository::SessionRepository;
+use crate::response::user_info::LoginToken;
+
+// 登录会话，访问令牌只在短时间内有效，刷新令牌每次使用后都会更换
+pub struct SessionService(Arc<SessionRepository>, SessionCache);
+
+impl SessionService {
+    pub fn new(db_conn: Pool<Postgres>) -> SessionService {
+        return SessionService(
+            Arc::new(SessionRepository::new(db_conn)),
+            SessionCache::new(),
+        );
+    }
+
+    // 登录成功后新建会话，返回访问令牌和刷新令牌
+    pub async fn create_session(
+        &self,
+        user: &UserVo,
+        mut client: SessionClient,
+    ) -> Result<LoginToken, E> {
+        let session_id = Uuid::new_v4().simple().to_string();
+        let secret = new_refresh_secret();
+        client.device = client
+            .device
+            .chars()
+            .take(SESSION_DEVICE_MAX_LENGTH)
+            .collect();
+
+        let inserted = self
+            .0
+            .insert_session(
+                &session_id,
+                user.id,
+                &hash_refresh_secret(&secret),
+                &client,
+                CONFIG.token.refresh_expire_seconds(),
+            )
+            .await;
+        if !inserted {
+            return Err(E::error(
+                Status::DATABASE_ERROR,
+                String::from("创建登录会话失败"),
+            ));
+        }
+
+        self.1.set_session(&session_id, user.id);
+        info!(
+            "新建登录会话：{} 设备：{} IP：{}",
+            user.username, client.device, client.ip
+        );
+        return Ok(login_token(user.id, &user.username, &session_id, &secret).await);
+    }
+
+    // 使用刷新令牌换取新的访问令牌，旧的刷新令牌同时失效
+    pub async fn refresh(&self, refresh_token: &String) -> Result<LoginToken, E> {
+        let invalid = || {
+            E::error(
+                Status::INVALID_TOKEN_ERROR,
+                String::from("刷新令牌已失效，请重新登录"),
+            )
+        };
+
+        let (session_id, secret) = match refresh_token.split_once('.') {
+            Some((id, secret)) if !id.is_empty() && !secret.is_empty() => {
+                (id.to_owned(), secret.to_owned())
+            }
+            _ => return Err(invalid()),
+        };
+
+        let session = match self.0.get_session_token(&session_id).await {
+            Some(s) => s,
+            None => return Err(invalid()),
+        };
+
+        if session.revoked_at.is_some() || session.expire_at <= Local::now() {
+            return Err(invalid());
+        }
+
+        let old_hash = hash_refresh_secret(&secret);
+        let new_secret = new_refresh_secret();
+        let rotated = self
+            .0
+            .rotate_refresh_token(
+                &session_id,
+                &old_hash,
+                &hash_refresh_secret(&new_secret),
+                CONFIG.token.refresh_expire_seconds(),
+            )
+            .await;
+
+        if !rotated {
+            // 上一个已经轮换掉的刷新令牌再次使用，可能已经泄露，撤销整个会话，
+            // 其他不匹配的令牌只返回错误，避免随便一个错误的令牌就能让用户下线
+            if session.previous_refresh_token_hash.as_ref() == Some(&old_hash) {
+                warn!(
+                    "刷新令牌被重复使用，撤销会话：{} 用户：{}",
+                    session_id, session.username
+                );
+                self.0.revoke_session(session.user_id, &session_id).await;
+                self.1.remove_sessions(&vec![session_id]);
+            }
+            return Err(invalid());
+        }
+
+        self.1.set_session(&session_id, session.user_id);
+        return Ok(login_token(session.user_id, &session.username, &session_id, &new_secret).await);
+    }
+
+    // 确认访问令牌对应的会话没有被撤销或过期
+    pub async fn verify_session(&self, claims: &Claims) -> bool {
+        if let Some(user_id) = self.1.get_session(&claims.sid) {
+            return user_id == claims.iss;
+        }
+        return match self.0.touch_session(&claims.sid).await {
+            Some(user_id) => {
+                self.1.set_session(&claims.sid, user_id);
+                user_id == claims.iss
+            }
+            None => false,
+        };
+    }
+
+    // 获取用户所有有效的会话，标记当前会话
+    pub async fn get_sessions(&self, user_id: i64, current: &String) -> Vec<UserSessionVo> {
+        let mut sessions = self.0.get_user_sessions(user_id).await;
+        for session in sessions.iter_mut() {
+            session.current = &session.id == current;
+        }
+        return sessions;
+    }
+
+    // 撤销用户的一个会话
+    pub async fn revoke_session(&self, user_id: i64, session_id: &String) -> Result<(), E> {
+        if !self.0.revoke_session(user_id, session_id).await {
+            return Err(E::error(
+                Status::DATA_EMPTY_ERROR,
+                String::from("会话不存在或已失效"),
+            ));
+        }
+        self.1.remove_sessions(&vec![session_id.to_owned()]);
+        return Ok(());
+    }
+
+    // 撤销用户的全部会话，返回撤销的会话数量
+    pub async fn revoke_all_sessions(&self, user_id: i64) -> usize {
+        let session_ids = self.0.revoke_user_sessions(user_id, None).await;
+        self.1.remove_sessions(&session_ids);
+        return session_ids.len();
+    }
+
+    // 撤销用户除当前会话以外的其他会话，返回撤销的会话数量
+    pub async fn revoke_other_sessions(&self, user_id: i64, current: &String) -> usize {
+        let session_ids = self.0.revoke_user_sessions(user_id, Some(current)).await;
+        self.1.remove_sessions(&session_ids);
+        return session_ids.len();
+    }
+
+    // 删除过期或撤销很久的会话记录
+    pub async fn delete_stale_sessions(&self) {
+        let count = self.0.delete_stale_sessions(SESSION_RETENTION_DAYS).await;
+        if count > 0 {
+            info!("删除失效的登录会话 {} 条", count);
+        }
+    }
+}
+
+async fn login_token(
+    user_id: i64,
+    username: &String,
+    session_id: &String,
+    secret: &String,
+) -> LoginToken {
+    return LoginToken {
+        access_token: CONFIG
+            .token
+            .create_token(user_id, username, session_id)
+            .await,
+        refresh_token: format!("{}.{}", session_id, secret),
+        expires_in: CONFIG.token.access_expire_seconds(),
+    };
+}
+
+// 刷新令牌的随机部分，数据库中只保存它的哈希
+fn new_refresh_secret() -> String {
+    return format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+}
+
+fn hash_refresh_secret(secret: &String) -> String {
+    return hex::encode(Sha256::digest(secret.as_bytes()));
+}
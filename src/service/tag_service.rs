@@ -1,22 +1,29 @@
 use std::sync::Arc;
 
+use log::info;
 use r2d2_redis::redis::Commands;
 use sqlx::{Pool, Postgres};
 
+use crate::cache::blog_cache::BlogCache;
+use crate::cache::clear_tag_info_key;
 use crate::common::constants::TAG_RANDOM_LIST_COUNT;
 use crate::common::redis_keys::{RANDOM_TAG_KEY, TAG_MAP_KEY};
 use crate::conf::redis_config::get_pool_connection;
+use crate::error::custom_error::{E, Status};
 use crate::models::blogs::BlogVo;
 use crate::models::tag::TagVo;
 use crate::repository::tag_repository::TagRepository;
+use crate::request::admin_request::{MergeTagRequest, RenameTagRequest, SplitTagRequest};
 use crate::response::page_info::PageInfo;
+use crate::search::search_entity::SearchEntity;
+use crate::service::search_service::SearchService;
 
-pub struct TagService(Arc<TagRepository>);
+pub struct TagService(Arc<TagRepository>, Arc<SearchService>);
 
 impl TagService {
-    pub fn new(db_conn: Pool<Postgres>) -> TagService {
+    pub fn new(db_conn: Pool<Postgres>, search_service: Arc<SearchService>) -> TagService {
         let tag_repository = TagRepository::new(db_conn);
-        TagService(Arc::new(tag_repository))
+        TagService(Arc::new(tag_repository), search_service)
     }
 
     // 获取标签列表
@@ -28,7 +35,11 @@ impl TagService {
     // 添加标签
     pub async fn add_tag(&self, name: &String) -> Option<TagVo> {
         // 添加标签到数据库
-        return self.0.add_tag(name).await;
+        let result = self.0.add_tag(name).await;
+        if let Some(tag) = &result {
+            self.1.sync_entities(SearchEntity::Tag, vec![tag.id]);
+        }
+        return result;
     }
 
     // 获取随机标签
@@ -61,8 +72,11 @@ impl TagService {
             let redis_tag = serde_json::from_str(&r).unwrap();
             Some(redis_tag)
         } else {
-            // 从数据库中获取标签信息
-            let result = self.0.get_tag_by_id(tid).await;
+            // 从数据库中获取标签信息，已合并的旧标签ID解析到新标签
+            let result = match self.0.resolve_tag_id(tid).await {
+                Some(id) => self.0.get_tag_by_id(id).await,
+                None => None,
+            };
             if let Some(tag) = &result {
                 let json_str = serde_json::to_string(tag).unwrap();
                 let _ = get_pool_connection()
@@ -73,10 +87,143 @@ impl TagService {
         };
     }
 
-    // 获取带有标签的博客列表
-    pub async fn get_tag_blog_list(&self, page: i64, tid: i64) -> PageInfo<BlogVo> {
-        // 从数据库中获取带有标签的博客列表
-        self.0.get_tag_blogs(page, tid).await
+    // 获取带有标签的博客列表，标签ID或名称可以是合并前的旧标签
+    pub async fn get_tag_blog_list(
+        &self,
+        page: i64,
+        tid: Option<i64>,
+        name: Option<String>,
+    ) -> PageInfo<BlogVo> {
+        let resolved = if let Some(tid) = tid {
+            self.0.resolve_tag_id(tid).await
+        } else if let Some(name) = &name {
+            self.0.resolve_tag_name(name).await
+        } else {
+            None
+        };
+
+        return match resolved {
+            // 从数据库中获取带有标签的博客列表
+            Some(id) => self.0.get_tag_blogs(page, id).await,
+            None => PageInfo {
+                page,
+                size: 0,
+                total: 0,
+                data: vec![],
+            },
+        };
+    }
+
+    // 合并多个标签到目标标签
+    pub async fn merge_tags(&self, req: &MergeTagRequest) -> Result<i64, E> {
+        let source_ids: Vec<i64> = req
+            .source_ids
+            .iter()
+            .filter(|id| **id != req.target_id)
+            .cloned()
+            .collect();
+
+        if source_ids.is_empty() {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                String::from("至少需要一个被合并的标签"),
+            ));
+        }
+
+        if self.0.get_tag_by_id(req.target_id).await.is_none() {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                String::from("目标标签不存在"),
+            ));
+        }
+
+        let blog_ids = self.0.merge_tags(&source_ids, req.target_id).await?;
+
+        clear_tag_cache(&blog_ids);
+
+        // 博客文档中保存了标签名称，受影响的博客需要重新索引
+        self.1.sync_blogs(blog_ids.clone());
+
+        // 被合并的标签已删除，从索引中移除
+        let mut tag_ids = source_ids.clone();
+        tag_ids.push(req.target_id);
+        self.1.sync_entities(SearchEntity::Tag, tag_ids);
+
+        info!("合并标签 {:?} => {}", source_ids, req.target_id);
+
+        return Ok(blog_ids.len() as i64);
+    }
+
+    // 重命名标签，可以保留旧名称为别名
+    pub async fn rename_tag(&self, req: &RenameTagRequest) -> Result<i64, E> {
+        let name = req.name.trim().to_string();
+
+        if name.is_empty() {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                String::from("标签名称不能为空"),
+            ));
+        }
+
+        let blog_ids = self.0.rename_tag(req.id, &name, req.alias).await?;
+
+        clear_tag_cache(&blog_ids);
+
+        self.1.sync_blogs(blog_ids.clone());
+
+        self.1.sync_entities(SearchEntity::Tag, vec![req.id]);
+
+        info!("重命名标签 {} => {}", req.id, name);
+
+        return Ok(blog_ids.len() as i64);
+    }
+
+    // 拆分标签，将源标签下的博客分配到多个标签
+    pub async fn split_tag(&self, req: &SplitTagRequest) -> Result<i64, E> {
+        if req.targets.is_empty() || req.targets.iter().any(|t| t.name.trim().is_empty()) {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                String::from("拆分目标标签名称不能为空"),
+            ));
+        }
+
+        if self.0.get_tag_by_id(req.source_id).await.is_none() {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                String::from("该标签不存在"),
+            ));
+        }
+
+        let targets: Vec<(String, Vec<i64>)> = req
+            .targets
+            .iter()
+            .map(|t| (t.name.trim().to_string(), t.blog_ids.to_owned()))
+            .collect();
+
+        let blog_ids = self
+            .0
+            .split_tag(req.source_id, &targets, req.delete_source)
+            .await?;
+
+        clear_tag_cache(&blog_ids);
+
+        self.1.sync_blogs(blog_ids.clone());
+
+        // 拆分可能新建标签，重新对比整个标签索引
+        self.1.refresh_entities(SearchEntity::Tag);
+
+        info!("拆分标签 {} => {:?}", req.source_id, targets);
+
+        return Ok(blog_ids.len() as i64);
+    }
+}
+
+// 标签变更后清除标签缓存以及受影响博客的缓存
+fn clear_tag_cache(blog_ids: &Vec<i64>) {
+    clear_tag_info_key();
+    let blog_cache = BlogCache::new();
+    for id in blog_ids {
+        blog_cache.delete_blog_info_by_id(*id);
     }
 }
 
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use r2d2_redis::redis::Commands;
 use sqlx::{Pool, Postgres};
 
+use crate::cache::clear_topic_info_key;
 use crate::common::is_image_url;
 use crate::common::redis_keys::{FIRST_PAGE_TOPIC_EXPIRE, FIRST_PAGE_TOPIC_KEY, TOPIC_MAP_KEY};
 use crate::conf::redis_config::get_pool_connection;
@@ -11,13 +12,14 @@ use crate::models::blogs::{SimpleBlogVo, TopicBlogVo};
 use crate::models::topic::{SimpleTopicVo, TopicRequest, TopicVo, UserSimpleTopicVo};
 use crate::repository::topic_repository::TopicRepository;
 use crate::response::page_info::PageInfo;
+use crate::service::search_service::SearchService;
 
-pub struct TopicService(Arc<TopicRepository>);
+pub struct TopicService(Arc<TopicRepository>, Arc<SearchService>);
 
 impl TopicService {
-    pub fn new(db_conn: Pool<Postgres>) -> TopicService {
+    pub fn new(db_conn: Pool<Postgres>, search_service: Arc<SearchService>) -> TopicService {
         let topic_repository = TopicRepository::new(db_conn);
-        TopicService(Arc::new(topic_repository))
+        TopicService(Arc::new(topic_repository), search_service)
     }
 
     pub async fn get_topic_list_by_page(&self, page: i64) -> PageInfo<TopicVo> {
@@ -89,15 +91,13 @@ impl TopicService {
                 String::from("这不是一个正确的图片链接"),
             ));
         }
-        let result = self.0.add_topic(uid, topic).await;
-
-        if let Some(e) = result {
-            return Some(E::error(Status::ADD_ERROR, String::from("添加专题失败")));
-        } else {
-            get_pool_connection()
-                .del::<&str, String>(FIRST_PAGE_TOPIC_KEY)
-                .unwrap_or_default();
-            return None;
-        }
+        return match self.0.add_topic(uid, topic).await {
+            Ok(id) => {
+                clear_topic_info_key();
+                self.1.sync_topics(vec![id]);
+                None
+            }
+            Err(e) => Some(e),
+        };
     }
 }
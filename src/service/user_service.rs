@@ -1,29 +1,61 @@
 use std::sync::Arc;
 
-use lettre::message::header::ContentType;
-use log::{error, info};
+use actix_web::web;
+use log::{error, info, warn};
 use sqlx::{Pool, Postgres};
 
 use crate::cache::user_cache::UserCache;
+use crate::cache::clear_user_info;
 use crate::common::{get_random_code_number, is_valid_email};
+use crate::common::constants::EMAIL_CODE_MAX_ATTEMPTS;
+use crate::common::redis_keys::{
+    EMAIL_CHANGE_CODE_EXPIRE, EMAIL_CHANGE_CODE_KEY, EMAIL_CODE_KEY, EMAIL_CODE_KEY_EXPIRE, MIN,
+    PASSWORD_RESET_CODE_EXPIRE, PASSWORD_RESET_CODE_KEY,
+};
 use crate::conf::config::CONFIG;
+use crate::conf::password_config::PasswordVerify;
+use crate::email::template::{render, EMAIL_CHANGED, PASSWORD_RESET_CODE, VERIFICATION_CODE};
 use crate::error::custom_error::{E, Status};
+use crate::models::session::SessionClient;
 use crate::models::user::UserVo;
 use crate::repository::user_repository::UserRepository;
-use crate::request::email_request::EmailRequest;
-use crate::request::user_request::{ContactRequest, UserRegisteredRequest, UserRequest};
+use crate::request::user_request::{
+    ChangeEmailRequest, ChangePasswordRequest, PasswordResetRequest, ProfileRequest,
+    UserRegisteredRequest, UserRequest,
+};
+use crate::response::user_info::{LoginToken, PasswordHashReport};
 use crate::response::website_info::{BlogConfigInfo, get_default_blog_config_info};
+use crate::search::search_entity::SearchEntity;
+use crate::service::email_queue_service::EmailQueueService;
+use crate::service::search_service::SearchService;
+use crate::service::session_service::SessionService;
 
-pub struct UserService(Arc<UserRepository>, UserCache, String);
+#[derive(Clone)]
+pub struct UserService(
+    Arc<UserRepository>,
+    UserCache,
+    String,
+    Arc<EmailQueueService>,
+    Arc<SearchService>,
+    Arc<SessionService>,
+);
 
 impl UserService {
-    pub fn new(db_conn: Pool<Postgres>) -> UserService {
+    pub fn new(
+        db_conn: Pool<Postgres>,
+        email_queue_service: Arc<EmailQueueService>,
+        search_service: Arc<SearchService>,
+        session_service: Arc<SessionService>,
+    ) -> UserService {
         let user_cache = UserCache::new();
         let user_repository = UserRepository::new(db_conn);
         UserService(
             Arc::new(user_repository),
             user_cache,
             CONFIG.gpt.token.to_owned(),
+            email_queue_service,
+            search_service,
+            session_service,
         )
     }
 
@@ -46,8 +78,12 @@ impl UserService {
         };
     }
 
-    // 用户登录
-    pub async fn login_user(&self, user_request: &UserRequest) -> Result<String, E> {
+    // 用户登录，成功后为当前设备新建一个登录会话
+    pub async fn login_user(
+        &self,
+        user_request: &UserRequest,
+        client: SessionClient,
+    ) -> Result<LoginToken, E> {
         let check = user_request.check();
         if !check {
             return Err(E::error(
@@ -65,13 +101,25 @@ impl UserService {
             ));
         }
 
-        let user = user.unwrap();
+        let mut user = user.unwrap();
 
-        let check = user_request.check_user(&user).await;
+        // Argon2 校验比较耗时，放到阻塞线程中执行
+        let request = UserRequest {
+            username: user_request.username.to_owned(),
+            password: user_request.password.to_owned(),
+        };
+        let verify_user = user.clone();
+        let check = web::block(move || request.check_user(&verify_user))
+            .await
+            .unwrap_or(PasswordVerify::Invalid);
+
+        // 旧的 MD5 哈希或者参数过期的哈希在登录成功后重新计算
+        if check == PasswordVerify::NeedsRehash {
+            self.upgrade_password(&mut user, &user_request.password).await;
+        }
 
-        return if check {
-            let token = CONFIG.token.create_token(user.id, &user.username).await;
-            self.1.set_token(&user.username, &token);
+        return if check != PasswordVerify::Invalid {
+            let token = self.5.create_session(&user, client).await?;
             // 记录成功登录日志
             info!("用户登录成功：{}", user.username);
             Ok(token)
@@ -85,6 +133,27 @@ impl UserService {
         };
     }
 
+    async fn upgrade_password(&self, user: &mut UserVo, password: &String) {
+        let password = password.to_owned();
+        let hash = match web::block(move || CONFIG.password.hash(&password)).await {
+            Ok(Ok(hash)) => hash,
+            _ => return,
+        };
+        if self.0.update_password(user.id, &hash).await {
+            user.password = hash;
+            self.1.set_user(&user.username, &Some(user.clone()));
+            info!("用户密码哈希已升级：{}", user.username);
+        }
+    }
+
+    // 统计还在使用旧密码哈希的账号
+    pub async fn get_password_hash_report(&self) -> PasswordHashReport {
+        return self
+            .0
+            .get_password_hash_report(&CONFIG.password.hash_prefix())
+            .await;
+    }
+
     // 用户注册
     pub async fn registered_user(&self, user: &mut UserRegisteredRequest) -> Option<E> {
         let check_message = user.check();
@@ -93,7 +162,7 @@ impl UserService {
             return Some(E::error(Status::CHECK_DATA_ERROR, check_message.unwrap()));
         }
 
-        let redis_email_code = self.1.get_email_code(&user.email);
+        let redis_email_code = self.1.get_email_code(EMAIL_CODE_KEY, &user.email);
 
         if redis_email_code.is_err() || redis_email_code.unwrap() != user.code {
             return Some(E::error(Status::EMAIL_ERROR, String::from("错误的验证码")));
@@ -101,80 +170,367 @@ impl UserService {
 
         return match self.0.user_is_exists(&user.username, &user.email).await {
             None => {
-                let md5_password = format!("{:x}", md5::compute(&user.password));
-                user.password = md5_password;
-                self.0.insert_user(user).await
+                user.password = match hash_password(&user.password).await {
+                    Ok(hash) => hash,
+                    Err(e) => return Some(e),
+                };
+                if let Some(e) = self.0.insert_user(user).await {
+                    return Some(e);
+                }
+                // 新用户加入作者搜索索引
+                if let Ok(u) = self.0.get_user_by_username(&user.username).await {
+                    self.4.sync_entities(SearchEntity::Author, vec![u.id]);
+                }
+                None
             }
             Some(e) => Some(e),
         };
     }
 
-    // 发送邮件验证码
-    pub fn send_mail(&self, email: &String) -> Option<E> {
-        let is_email = is_valid_email(email);
+    // 发送邮件验证码，locale 为邮件模板的语言，邮件加入发送队列后即返回
+    pub async fn send_mail(&self, email: &String, locale: Option<&str>) -> Option<E> {
+        return self
+            .send_code_mail(
+                email,
+                locale,
+                VERIFICATION_CODE,
+                EMAIL_CODE_KEY,
+                EMAIL_CODE_KEY_EXPIRE,
+            )
+            .await;
+    }
 
-        if !is_email {
+    // 发送重置密码验证码，无论邮箱是否注册都返回相同的结果
+    pub async fn send_password_reset_mail(
+        &self,
+        email: &String,
+        locale: Option<&str>,
+    ) -> Option<E> {
+        if !is_valid_email(email) {
             return Some(E::error(
                 Status::EMAIL_ERROR,
                 String::from("错误的邮箱格式"),
             ));
         }
 
-        let random_code = &get_random_code_number();
+        if !self.1.lock_email_code_send(PASSWORD_RESET_CODE_KEY, email) {
+            return Some(E::error(
+                Status::EMAIL_ERROR,
+                String::from("请求太频繁，请稍后再试"),
+            ));
+        }
+
+        // 查询用户和发送邮件都在后台进行，邮箱是否注册的响应时间相同
+        let service = self.clone();
+        let email = email.clone();
+        let locale = locale.map(String::from);
+        actix_web::rt::spawn(async move {
+            if service.0.get_user_by_email(&email).await.is_none() {
+                info!("重置密码的邮箱未注册：{}", email);
+                return;
+            }
+
+            if let Some(e) = service
+                .send_code_mail(
+                    &email,
+                    locale.as_deref(),
+                    PASSWORD_RESET_CODE,
+                    PASSWORD_RESET_CODE_KEY,
+                    PASSWORD_RESET_CODE_EXPIRE,
+                )
+                .await
+            {
+                error!("发送重置密码验证码失败：{} {}", email, e.message);
+            }
+        });
+        return None;
+    }
+
+    // 通过邮件验证码重置密码，重置后用户所有设备都需要重新登录
+    pub async fn reset_password(&self, req: &PasswordResetRequest) -> Result<(), E> {
+        if let Some(e) = req.check() {
+            return Err(E::error(Status::CHECK_DATA_ERROR, e));
+        }
 
-        let req = EmailRequest {
-            to: email.to_owned(),
-            subject: String::from("Yuice 验证码"),
-            message: random_code.to_owned(),
+        let invalid = || E::error(Status::EMAIL_ERROR, String::from("验证码错误或已失效"));
+
+        if !self.verify_email_code(PASSWORD_RESET_CODE_KEY, &req.email, &req.code) {
+            return Err(invalid());
+        }
+
+        let user = match self.0.get_user_by_email(&req.email).await {
+            Some(u) => u,
+            None => return Err(invalid()),
         };
 
-        return if CONFIG.smtp.send_email(req, ContentType::TEXT_PLAIN) {
-            self.1.set_email_code(&email, &random_code);
-            // 记录成功发送验证码日志
-            info!("成功发送验证码至邮箱：{}", email);
-            None
-        } else {
-            // 记录发送验证码失败日志
-            error!("发送验证码至邮箱失败：{}", email);
-            Some(E::error(
+        let hash = hash_password(&req.password).await?;
+
+        if !self.0.update_password(user.id, &hash).await {
+            return Err(E::error(
+                Status::DATABASE_ERROR,
+                String::from("重置密码失败"),
+            ));
+        }
+
+        clear_user_info(&user.username);
+        let count = self.5.revoke_all_sessions(user.id).await;
+        info!("用户重置密码成功：{} 撤销会话 {} 个", user.username, count);
+        return Ok(());
+    }
+
+    // 修改昵称和头像
+    pub async fn update_profile(&self, user: &UserVo, req: &mut ProfileRequest) -> Result<(), E> {
+        if let Some(e) = req.check() {
+            return Err(E::error(Status::CHECK_DATA_ERROR, e));
+        }
+
+        if !self
+            .0
+            .update_profile(user.id, &req.nick_name, &req.icon)
+            .await
+        {
+            return Err(E::error(
+                Status::EDIT_ERROR,
+                String::from("修改个人资料失败"),
+            ));
+        }
+
+        clear_user_info(&user.username);
+        // 作者搜索索引使用昵称和头像，博客文档中也保存了作者昵称
+        self.4.sync_entities(SearchEntity::Author, vec![user.id]);
+        self.4.sync_blogs(self.0.get_user_blog_ids(user.id).await);
+        info!("用户修改个人资料：{}", user.username);
+        return Ok(());
+    }
+
+    // 修改密码，成功后除当前会话外的其他设备都需要重新登录
+    pub async fn change_password(
+        &self,
+        user: &UserVo,
+        session_id: &String,
+        req: &ChangePasswordRequest,
+    ) -> Result<(), E> {
+        if let Some(e) = req.check() {
+            return Err(E::error(Status::CHECK_DATA_ERROR, e));
+        }
+
+        let old_password = req.old_password.to_owned();
+        let hash = user.password.to_owned();
+        let check = web::block(move || CONFIG.password.verify(&old_password, &hash))
+            .await
+            .unwrap_or(PasswordVerify::Invalid);
+
+        if check == PasswordVerify::Invalid {
+            return Err(E::error(
+                Status::PASSWORD_VALIDATE_ERROR,
+                String::from("旧密码错误"),
+            ));
+        }
+
+        let hash = hash_password(&req.password).await?;
+
+        if !self.0.update_password(user.id, &hash).await {
+            return Err(E::error(
+                Status::DATABASE_ERROR,
+                String::from("修改密码失败"),
+            ));
+        }
+
+        clear_user_info(&user.username);
+        let count = self.5.revoke_other_sessions(user.id, session_id).await;
+        info!("用户修改密码成功：{} 撤销会话 {} 个", user.username, count);
+        return Ok(());
+    }
+
+    // 发送修改邮箱的验证码到新邮箱，验证码只能由发送请求的用户使用
+    pub async fn send_email_change_mail(
+        &self,
+        user: &UserVo,
+        email: &String,
+        locale: Option<&str>,
+    ) -> Option<E> {
+        if !is_valid_email(email) {
+            return Some(E::error(
                 Status::EMAIL_ERROR,
-                String::from("发送验证码失败"),
-            ))
-        };
+                String::from("错误的邮箱格式"),
+            ));
+        }
+
+        let key = email_change_key(user.id);
+
+        if !self.1.lock_email_code_send(&key, email) {
+            return Some(E::error(
+                Status::EMAIL_ERROR,
+                String::from("请求太频繁，请稍后再试"),
+            ));
+        }
+
+        if self.0.email_is_exists(email).await {
+            return Some(E::error(
+                Status::EMAIL_ERROR,
+                String::from("该邮箱已被使用"),
+            ));
+        }
+
+        return self
+            .send_code_mail(
+                email,
+                locale,
+                VERIFICATION_CODE,
+                &key,
+                EMAIL_CHANGE_CODE_EXPIRE,
+            )
+            .await;
     }
 
-    // 联系我
-    pub fn contact_me(&self, req: &ContactRequest) -> Option<E> {
+    // 使用当前密码和新邮箱收到的验证码修改邮箱，成功后通知旧邮箱，
+    // 除当前会话外的其他设备都需要重新登录
+    pub async fn change_email(
+        &self,
+        user: &UserVo,
+        session_id: &String,
+        req: &ChangeEmailRequest,
+        locale: Option<&str>,
+    ) -> Result<(), E> {
         if let Some(e) = req.check() {
-            return Some(E::error(Status::CHECK_DATA_ERROR, e));
+            return Err(E::error(Status::CHECK_DATA_ERROR, e));
+        }
+
+        let old_password = req.old_password.to_owned();
+        let hash = user.password.to_owned();
+        let check = web::block(move || CONFIG.password.verify(&old_password, &hash))
+            .await
+            .unwrap_or(PasswordVerify::Invalid);
+
+        if check == PasswordVerify::Invalid {
+            return Err(E::error(
+                Status::PASSWORD_VALIDATE_ERROR,
+                String::from("密码错误"),
+            ));
         }
 
-        let html_content = format!(
-            "<h3>{}</h3><p>对方名字: {}</p><p>对方邮箱: {}</p>留言内容:<p>{}</p>",
-            req.subject, req.name, req.email, req.content
+        if !self.verify_email_code(&email_change_key(user.id), &req.email, &req.code) {
+            return Err(E::error(
+                Status::EMAIL_ERROR,
+                String::from("验证码错误或已失效"),
+            ));
+        }
+
+        let old_email = self.0.get_user_email(user.id).await;
+
+        if !self.0.update_email(user.id, &req.email).await {
+            return Err(E::error(
+                Status::EMAIL_ERROR,
+                String::from("该邮箱已被使用"),
+            ));
+        }
+
+        clear_user_info(&user.username);
+        let count = self.5.revoke_other_sessions(user.id, session_id).await;
+        info!(
+            "用户修改邮箱成功：{} {} 撤销会话 {} 个",
+            user.username, req.email, count
         );
 
-        let email_request = EmailRequest {
-            to: CONFIG.my_email.to_owned(),
-            subject: req.subject.to_owned(),
-            message: html_content,
+        if let Some(old_email) = old_email.filter(|e| is_valid_email(e)) {
+            self.send_email_changed_mail(&old_email, &user.username, &req.email, locale)
+                .await;
+        }
+        return Ok(());
+    }
+
+    // 通知旧邮箱账号的邮箱已被修改，发送失败不影响修改结果
+    async fn send_email_changed_mail(
+        &self,
+        old_email: &String,
+        username: &String,
+        email: &String,
+        locale: Option<&str>,
+    ) {
+        let rendered = match render(
+            EMAIL_CHANGED,
+            &[("username", username), ("email", email)],
+            locale,
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                error!("渲染邮箱修改通知失败：{} {}", old_email, e.message);
+                return;
+            }
         };
 
-        let result = CONFIG
-            .smtp
-            .send_email(email_request, ContentType::TEXT_HTML);
+        if let Err(e) = self.3.enqueue(old_email, rendered).await {
+            error!("发送邮箱修改通知失败：{} {}", old_email, e.message);
+        }
+    }
 
-        return if result {
-            // 记录成功发送消息到邮箱的日志
-            info!("成功发送消息至我的邮箱：{}", CONFIG.my_email);
-            None
-        } else {
-            // 记录发送消息到邮箱失败的日志
-            error!("发送消息至我的邮箱失败：{}", CONFIG.my_email);
-            Some(E::error(
+    // 校验邮件验证码，输错次数过多后该邮箱被锁定，重新发送验证码也不能解除，
+    // 校验成功后验证码不能再次使用
+    fn verify_email_code(&self, key: &str, email: &String, code: &String) -> bool {
+        let saved = match self.1.get_email_code(key, email) {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+
+        if self.1.get_email_code_attempts(key, email) >= EMAIL_CODE_MAX_ATTEMPTS {
+            warn!("邮件验证码错误次数过多，拒绝校验：{}", email);
+            return false;
+        }
+
+        if !code.is_empty() && &saved == code {
+            self.1.remove_email_code(key, email);
+            return true;
+        }
+
+        if self.1.incr_email_code_attempts(key, email) >= EMAIL_CODE_MAX_ATTEMPTS {
+            warn!("邮件验证码错误次数过多，锁定邮箱：{}", email);
+        }
+        return false;
+    }
+
+    async fn send_code_mail(
+        &self,
+        email: &String,
+        locale: Option<&str>,
+        template: &str,
+        key: &str,
+        expire: usize,
+    ) -> Option<E> {
+        let is_email = is_valid_email(email);
+
+        if !is_email {
+            return Some(E::error(
                 Status::EMAIL_ERROR,
-                String::from("发送消息到我的邮箱失败"),
-            ))
+                String::from("错误的邮箱格式"),
+            ));
+        }
+
+        let random_code = &get_random_code_number();
+
+        let expire_minutes = (expire / MIN).to_string();
+
+        let rendered = match render(
+            template,
+            &[("code", random_code), ("expire", &expire_minutes)],
+            locale,
+        ) {
+            Ok(r) => r,
+            Err(e) => return Some(e),
+        };
+
+        // 验证码过期后邮件就没有意义了，超过有效期还没发出去就不再发送
+        return match self.3.enqueue_with_max_age(email, rendered, expire as i64).await {
+            Ok(_) => {
+                self.1.set_email_code(key, &email, &random_code, expire);
+                // 记录成功发送验证码日志
+                info!("验证码已加入发送队列：{}", email);
+                None
+            }
+            Err(e) => {
+                // 记录发送验证码失败日志
+                error!("发送验证码至邮箱失败：{}", email);
+                Some(e)
+            }
         };
     }
 
@@ -186,14 +542,17 @@ impl UserService {
             get_default_blog_config_info()
         };
     }
+}
 
-    // 获取用户的Token
-    pub fn get_user_token(&self, username: &String) -> Option<String> {
-        return self.1.get_token(username);
-    }
+// 注册、重置密码和修改密码共用的密码哈希，Argon2 计算比较耗时，放到阻塞线程中执行
+async fn hash_password(password: &String) -> Result<String, E> {
+    let password = password.to_owned();
+    return match web::block(move || CONFIG.password.hash(&password)).await {
+        Ok(result) => result,
+        Err(_) => Err(E::error(Status::SERVER_ERROR, String::from("密码加密失败"))),
+    };
+}
 
-    // 用户注销
-    pub async fn logout(&self, username: &String) -> bool {
-        return self.1.remove_user_token(username);
-    }
+fn email_change_key(user_id: i64) -> String {
+    return format!("{}{}:", EMAIL_CHANGE_CODE_KEY, user_id);
 }
@@ -0,0 +1,285 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{Pool, Postgres};
+use sqlx::types::chrono::Local;
+use url::Url;
+use uuid::Uuid;
+
+use crate::common::constants::WEBHOOK_DELIVERY_BATCH_SIZE;
+use crate::conf::config::CONFIG;
+use crate::error::custom_error::{E, Status};
+use crate::models::webhook::{PendingDelivery, WEBHOOK_EVENTS, WEBHOOK_PING, WebhookDeliveryVo, WebhookSecretVo, WebhookVo};
+use crate::repository::webhook_repository::WebhookRepository;
+use crate::request::admin_request::{WebhookDeliveryFilter, WebhookRequest};
+use crate::response::page_info::PageInfo;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 保存到投递记录中的响应内容最大长度
+const MAX_RESPONSE_BODY_LENGTH: usize = 1000;
+
+#[derive(Clone)]
+pub struct WebhookService(Arc<WebhookRepository>, Client);
+
+impl WebhookService {
+    pub fn new(db_conn: Pool<Postgres>) -> WebhookService {
+        let webhook_repository = WebhookRepository::new(db_conn);
+        let client = Client::builder()
+            .timeout(Duration::from_secs(CONFIG.webhook.timeout))
+            .build()
+            .unwrap();
+        WebhookService(Arc::new(webhook_repository), client)
+    }
+
+    // 密钥只在添加时返回一次，列表中只显示开头几位
+    pub async fn get_webhook_list(&self) -> Vec<WebhookVo> {
+        let mut webhooks = self.0.get_webhook_list().await;
+        for webhook in webhooks.iter_mut() {
+            webhook.secret = mask_secret(&webhook.secret);
+        }
+        return webhooks;
+    }
+
+    pub async fn add_webhook(&self, req: WebhookRequest) -> Result<WebhookSecretVo, E> {
+        check_webhook(&req)?;
+
+        let secret = match &req.secret {
+            Some(secret) => secret.to_owned(),
+            None => Uuid::new_v4().simple().to_string(),
+        };
+
+        let id = self.0.insert_webhook(&req, &secret).await.ok_or(E::error(
+            Status::ADD_ERROR,
+            String::from("添加 Webhook 失败"),
+        ))?;
+
+        return Ok(WebhookSecretVo { id, secret });
+    }
+
+    pub async fn update_webhook(&self, req: WebhookRequest) -> Result<i64, E> {
+        check_webhook(&req)?;
+
+        let id = req.id.ok_or(E::error(
+            Status::QUERY_OR_PARAMS_ERROR,
+            String::from("Webhook id 不能为空"),
+        ))?;
+
+        return Ok(self.0.update_webhook(id, &req).await);
+    }
+
+    pub async fn delete_webhook(&self, id: i64) -> i64 {
+        return self.0.delete_webhook(id).await;
+    }
+
+    pub async fn get_delivery_list(
+        &self,
+        req: &WebhookDeliveryFilter,
+    ) -> PageInfo<WebhookDeliveryVo> {
+        return self.0.get_delivery_list(req).await;
+    }
+
+    // 手动重新投递
+    pub async fn retry_delivery(&self, id: i64) -> i64 {
+        let result = self.0.retry_delivery(id).await;
+        if result > 0 {
+            self.spawn_process();
+        }
+        return result;
+    }
+
+    // 向指定的 Webhook 发送一个测试事件
+    pub async fn ping_webhook(&self, id: i64) -> i64 {
+        let payload = build_payload(WEBHOOK_PING, json!({ "webhookId": id }));
+        let result = self.0.create_deliveries(WEBHOOK_PING, &payload, Some(id)).await;
+        if result > 0 {
+            self.spawn_process();
+        }
+        return result;
+    }
+
+    // 触发事件，为订阅该事件的 Webhook 创建投递记录后在后台投递，不阻塞当前请求
+    pub fn dispatch(&self, event: &'static str, data: Value) {
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            let payload = build_payload(event, data);
+            if service.0.create_deliveries(event, &payload, None).await > 0 {
+                service.process_due().await;
+            }
+        });
+    }
+
+    fn spawn_process(&self) {
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            service.process_due().await;
+        });
+    }
+
+    // 投递所有到期的记录，返回投递成功的数量，由事件触发和定时任务调用
+    pub async fn process_due(&self) -> usize {
+        let lease = CONFIG.webhook.timeout as i64 + 5;
+
+        let mut count = 0;
+
+        // 逐条领取，每条记录的租期只覆盖它自己的投递时间，单次最多投递一批
+        for _ in 0..WEBHOOK_DELIVERY_BATCH_SIZE {
+            let delivery = match self.0.claim_due_delivery(lease).await {
+                Some(delivery) => delivery,
+                None => break,
+            };
+            if self.deliver(&delivery).await {
+                count += 1;
+            }
+        }
+
+        return count;
+    }
+
+    async fn deliver(&self, delivery: &PendingDelivery) -> bool {
+        let signature = sign_payload(&delivery.secret, &delivery.payload);
+
+        let result = self
+            .1
+            .post(&delivery.url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Event", &delivery.event)
+            .header("X-Webhook-Delivery", delivery.id.to_string())
+            .header("X-Webhook-Signature", format!("sha256={}", signature))
+            .body(delivery.payload.to_owned())
+            .send()
+            .await;
+
+        let (success, status, body, error) = match result {
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                (
+                    status.is_success(),
+                    Some(status.as_u16() as i32),
+                    Some(body.chars().take(MAX_RESPONSE_BODY_LENGTH).collect()),
+                    None,
+                )
+            }
+            Err(e) => (false, None, None, Some(e.to_string())),
+        };
+
+        // 失败后按 retry_interval * 2^(已尝试次数) 退避，达到最大次数后不再重试
+        let attempts = delivery.attempts + 1;
+        let retry_after = if success || attempts >= CONFIG.webhook.max_attempts {
+            None
+        } else {
+            Some(CONFIG.webhook.retry_interval * 2_i64.pow(delivery.attempts.min(20) as u32))
+        };
+
+        if success {
+            info!("Webhook 投递成功 id:{} event:{} url:{}", delivery.id, delivery.event, delivery.url);
+        } else {
+            warn!(
+                "Webhook 投递失败 id:{} event:{} url:{} status:{:?} error:{:?} 第{}次",
+                delivery.id, delivery.event, delivery.url, status, error, attempts
+            );
+        }
+
+        self.0
+            .update_delivery(delivery.id, success, status, body, error, retry_after)
+            .await;
+
+        return success;
+    }
+}
+
+fn check_webhook(req: &WebhookRequest) -> Result<(), E> {
+    let valid_url = Url::parse(&req.url)
+        .map(|u| u.scheme() == "http" || u.scheme() == "https")
+        .unwrap_or(false);
+
+    if !valid_url {
+        return Err(E::error(
+            Status::CHECK_DATA_ERROR,
+            String::from("Webhook 地址必须是 http(s) 链接"),
+        ));
+    }
+
+    if req.events.is_empty() {
+        return Err(E::error(
+            Status::CHECK_DATA_ERROR,
+            String::from("至少订阅一个事件"),
+        ));
+    }
+
+    if let Some(event) = req
+        .events
+        .iter()
+        .find(|e| !WEBHOOK_EVENTS.contains(&e.as_str()))
+    {
+        return Err(E::error(
+            Status::CHECK_DATA_ERROR,
+            format!("不支持的事件: {}", event),
+        ));
+    }
+
+    if let Some(secret) = &req.secret {
+        if secret.len() < 16 || secret.len() > 128 {
+            return Err(E::error(
+                Status::CHECK_DATA_ERROR,
+                String::from("密钥长度必须在16到128个字符之间"),
+            ));
+        }
+    }
+
+    return Ok(());
+}
+
+fn mask_secret(secret: &str) -> String {
+    let prefix: String = secret.chars().take(4).collect();
+    return format!("{}****", prefix);
+}
+
+fn build_payload(event: &str, data: Value) -> String {
+    let payload = json!({
+        "event": event,
+        "timestamp": Local::now().timestamp_millis(),
+        "data": data,
+    });
+    return payload.to_string();
+}
+
+// 使用 Webhook 密钥对请求体做 HMAC-SHA256 签名，结果为十六进制字符串
+fn sign_payload(secret: &str, payload: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    return hex::encode(mac.finalize().into_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_payload_hmac_sha256() {
+        // RFC 4231 测试用例 2
+        assert_eq!(
+            sign_payload("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn sign_payload_depends_on_secret_and_payload() {
+        let signature = sign_payload("secret-0123456789", "{\"event\":\"blog.created\"}");
+        assert_eq!(signature.len(), 64);
+        assert_ne!(signature, sign_payload("secret-0123456780", "{\"event\":\"blog.created\"}"));
+        assert_ne!(signature, sign_payload("secret-0123456789", "{\"event\":\"blog.updated\"}"));
+    }
+
+    #[test]
+    fn mask_secret_keeps_prefix() {
+        assert_eq!(mask_secret("0123456789abcdef"), "0123****");
+    }
+}
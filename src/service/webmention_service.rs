@@ -0,0 +1,300 @@
+use std::sync::Arc;
+
+use log::{info, warn};
+use sqlx::{Pool, Postgres};
+
+use crate::cache::incr_rate_limit;
+use crate::common::constants::WEBMENTION_RATE_LIMIT;
+use crate::common::get_url_domain;
+use crate::common::redis_keys::{WEBMENTION_RATE_EXPIRE, WEBMENTION_RATE_KEY};
+use crate::conf::config::CONFIG;
+use crate::error::custom_error::{E, Status};
+use crate::models::webmention::{
+    WEBMENTION_REJECTED, WEBMENTION_VERIFIED, WebmentionRequest, WebmentionVo,
+};
+use crate::repository::webmention_repository::WebmentionRepository;
+use crate::webmention::discovery::{discover_endpoint, find_outbound_links, find_title, links_to};
+use crate::webmention::fetcher::{check_url, HttpFetcher};
+
+#[derive(Clone)]
+pub struct WebmentionService(Arc<WebmentionRepository>, Arc<dyn HttpFetcher>);
+
+impl WebmentionService {
+    pub fn new(db_conn: Pool<Postgres>, fetcher: Arc<dyn HttpFetcher>) -> WebmentionService {
+        let webmention_repository = WebmentionRepository::new(db_conn);
+        WebmentionService(Arc::new(webmention_repository), fetcher)
+    }
+
+    // 接收 Webmention，检查目标是本站博客后保存为待验证，再异步抓取来源页面验证
+    pub async fn receive(&self, req: WebmentionRequest) -> Result<i64, E> {
+        let source = req.source.trim().to_string();
+
+        let target = req.target.trim().to_string();
+
+        if let Err(e) = check_url(&source).and(check_url(&target)) {
+            return Err(E::error(Status::QUERY_OR_PARAMS_ERROR, e));
+        }
+
+        if source == target {
+            return Err(E::error(
+                Status::QUERY_OR_PARAMS_ERROR,
+                String::from("source 和 target 不能相同"),
+            ));
+        }
+
+        let blog_id = match CONFIG.webmention.parse_blog_id(&target) {
+            Some(id) => id,
+            None => {
+                return Err(E::error(
+                    Status::QUERY_OR_PARAMS_ERROR,
+                    String::from("target 不是本站的博客地址"),
+                ))
+            }
+        };
+
+        if !self.0.blog_exists(blog_id).await {
+            return Err(E::error(
+                Status::BLOG_NOT_FOUND_ERROR,
+                String::from("博客不存在"),
+            ));
+        }
+
+        let id = match self.0.save_pending(blog_id, &source, &target).await {
+            Some(id) => id,
+            None => {
+                return Err(E::error(
+                    Status::DATABASE_ERROR,
+                    String::from("保存 Webmention 失败"),
+                ))
+            }
+        };
+
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            service.verify(id, &source, &target).await;
+        });
+
+        return Ok(id);
+    }
+
+    // 同一 IP 提交过于频繁时返回 false
+    pub fn check_rate_limit(&self, ip: &str) -> bool {
+        let count = incr_rate_limit(
+            format!("{}{}", WEBMENTION_RATE_KEY, ip),
+            WEBMENTION_RATE_EXPIRE,
+        );
+        return count <= WEBMENTION_RATE_LIMIT;
+    }
+
+    // 验证来源页面是否链接到了目标博客，来源页面不存在或不再链接时视为验证失败
+    pub async fn verify(&self, id: i64, source: &str, target: &str) -> bool {
+        return match verify_source(self.1.as_ref(), source, target).await {
+            Ok(title) => {
+                self.0.update_status(id, WEBMENTION_VERIFIED, title).await;
+                info!("Webmention 验证通过 source:{} target:{}", source, target);
+                true
+            }
+            Err(e) => {
+                info!(
+                    "Webmention 验证失败 source:{} target:{} error:{}",
+                    source, target, e
+                );
+                self.0.update_status(id, WEBMENTION_REJECTED, None).await;
+                false
+            }
+        };
+    }
+
+    // 获取博客已验证的 Webmention
+    pub async fn get_webmentions(&self, blog_id: i64) -> Vec<WebmentionVo> {
+        return self.0.get_verified_list(blog_id).await;
+    }
+
+    // 博客发布后异步通知内容中外部链接的 Webmention 端点
+    pub fn send_on_publish(&self, blog_id: i64, content: String) {
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            service.send(blog_id, &content).await;
+        });
+    }
+
+    // 返回成功通知的数量
+    pub async fn send(&self, blog_id: i64, content: &str) -> usize {
+        let source = CONFIG.webmention.get_blog_url(blog_id);
+
+        let mut exclude_domains: Vec<String> = CONFIG
+            .origin
+            .to_url_vec()
+            .iter()
+            .filter_map(|url| get_url_domain(url))
+            .collect();
+
+        if let Some(domain) = get_url_domain(&source) {
+            exclude_domains.push(domain);
+        }
+
+        let links = find_outbound_links(content, &exclude_domains, CONFIG.webmention.max_links);
+
+        let mut count = 0;
+
+        for link in links {
+            let endpoint = match find_endpoint(self.1.as_ref(), &link).await {
+                Some(endpoint) => endpoint,
+                None => continue,
+            };
+
+            let form = [("source", source.as_str()), ("target", link.as_str())];
+
+            match self.1.post_form(&endpoint, &form).await {
+                Ok(status) if status >= 200 && status < 300 => {
+                    count += 1;
+                    info!("Webmention 发送成功 target:{} endpoint:{}", link, endpoint);
+                }
+                Ok(status) => {
+                    warn!(
+                        "Webmention 发送失败 target:{} endpoint:{} status:{}",
+                        link, endpoint, status
+                    );
+                }
+                Err(e) => {
+                    warn!(
+                        "Webmention 发送失败 target:{} endpoint:{} error:{}",
+                        link, endpoint, e
+                    );
+                }
+            }
+        }
+
+        return count;
+    }
+}
+
+// 抓取来源页面并确认其中有指向目标的链接，验证通过时返回来源页面的标题
+pub async fn verify_source(
+    fetcher: &dyn HttpFetcher,
+    source: &str,
+    target: &str,
+) -> Result<Option<String>, String> {
+    let response = fetcher.get(source).await?;
+
+    if response.status < 200 || response.status >= 300 {
+        return Err(format!("来源页面状态码 {}", response.status));
+    }
+
+    if !links_to(&response, target) {
+        return Err(String::from("来源页面没有链接到目标地址"));
+    }
+
+    return Ok(find_title(&response.body));
+}
+
+// 抓取目标页面并发现它的 Webmention 端点，端点同样只能是公网的 http(s) 地址
+pub async fn find_endpoint(fetcher: &dyn HttpFetcher, target: &str) -> Option<String> {
+    let response = match fetcher.get(target).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Webmention 目标页面抓取失败 target:{} error:{}", target, e);
+            return None;
+        }
+    };
+
+    let endpoint = discover_endpoint(&response)?;
+
+    if let Err(e) = check_url(&endpoint) {
+        warn!("Webmention 端点不可用 target:{} error:{}", target, e);
+        return None;
+    }
+
+    return Some(endpoint);
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use futures::future::BoxFuture;
+
+    use crate::webmention::fetcher::FetchResponse;
+
+    use super::*;
+
+    // 按地址返回固定页面的本地抓取实现
+    struct StubFetcher(Vec<(&'static str, u16, &'static str)>);
+
+    impl HttpFetcher for StubFetcher {
+        fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, String>> {
+            let page = self.0.iter().find(|(u, _, _)| *u == url).map(|(u, status, body)| {
+                FetchResponse {
+                    status: *status,
+                    url: u.to_string(),
+                    content_type: String::from("text/html"),
+                    links: vec![],
+                    body: body.to_string(),
+                }
+            });
+            return Box::pin(async move { page.ok_or(String::from("not found")) });
+        }
+
+        fn post_form<'a>(
+            &'a self,
+            _url: &'a str,
+            _form: &'a [(&'a str, &'a str)],
+        ) -> BoxFuture<'a, Result<u16, String>> {
+            return Box::pin(async { Ok(202) });
+        }
+    }
+
+    fn fetcher() -> StubFetcher {
+        return StubFetcher(vec![
+            (
+                "https://source.com/reply",
+                200,
+                r#"<title>Re: post</title><a href="https://blog.com/blog/1">post</a>"#,
+            ),
+            ("https://source.com/other", 200, "<p>nothing here</p>"),
+            ("https://source.com/gone", 410, r#"<a href="https://blog.com/blog/1">post</a>"#),
+            (
+                "https://target.com/post",
+                200,
+                r#"<link rel="webmention" href="/webmention">"#,
+            ),
+            (
+                "https://evil.com/post",
+                200,
+                r#"<link rel="webmention" href="http://169.254.169.254/latest">"#,
+            ),
+        ]);
+    }
+
+    #[test]
+    fn verify_source_links_to_target() {
+        let title = block_on(verify_source(
+            &fetcher(),
+            "https://source.com/reply",
+            "https://blog.com/blog/1",
+        ));
+        assert_eq!(title, Ok(Some(String::from("Re: post"))));
+    }
+
+    #[test]
+    fn verify_source_rejects_missing_link() {
+        let f = fetcher();
+        let target = "https://blog.com/blog/1";
+        assert!(block_on(verify_source(&f, "https://source.com/other", target)).is_err());
+        assert!(block_on(verify_source(&f, "https://source.com/gone", target)).is_err());
+        assert!(block_on(verify_source(&f, "https://source.com/404", target)).is_err());
+        let other = "https://blog.com/blog/2";
+        assert!(block_on(verify_source(&f, "https://source.com/reply", other)).is_err());
+    }
+
+    #[test]
+    fn find_endpoint_resolves_relative_link() {
+        let endpoint = block_on(find_endpoint(&fetcher(), "https://target.com/post"));
+        assert_eq!(endpoint, Some(String::from("https://target.com/webmention")));
+    }
+
+    #[test]
+    fn find_endpoint_rejects_private_endpoint() {
+        assert_eq!(block_on(find_endpoint(&fetcher(), "https://evil.com/post")), None);
+        assert_eq!(block_on(find_endpoint(&fetcher(), "https://source.com/other")), None);
+    }
+}
@@ -0,0 +1,244 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use url::Url;
+
+use crate::webmention::fetcher::FetchResponse;
+
+lazy_static! {
+    // 博客内容（Markdown 或 HTML）中的外部链接
+    static ref OUTBOUND_LINK_REG: Regex = Regex::new(r#"https?://[^\s"'<>()\[\]{}]+"#).unwrap();
+    static ref TAG_REG: Regex = Regex::new(r"(?i)<(a|link)\b[^>]*>").unwrap();
+    static ref ATTR_REG: Regex =
+        Regex::new(r#"(?i)\b(rel|href)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap();
+    static ref TITLE_REG: Regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+}
+
+// 提取博客内容中的外部链接，去重并排除指定域名（本站）下的链接
+pub fn find_outbound_links(content: &str, exclude_domains: &Vec<String>, max: usize) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for m in OUTBOUND_LINK_REG.find_iter(content) {
+        let link = m.as_str().trim_end_matches(|c| ".,;:!?*_~`".contains(c));
+        let url = match Url::parse(link) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        let host = url.host_str().unwrap_or("").to_lowercase();
+        let host = host.trim_start_matches("www.");
+        if host.is_empty() || exclude_domains.iter().any(|d| d == host) {
+            continue;
+        }
+        let link = url.to_string();
+        if !links.contains(&link) {
+            links.push(link);
+        }
+        if links.len() >= max {
+            break;
+        }
+    }
+    return links;
+}
+
+// 从响应头 Link 或页面中 rel="webmention" 的 <link>/<a> 标签发现 Webmention 端点
+pub fn discover_endpoint(response: &FetchResponse) -> Option<String> {
+    let base = Url::parse(&response.url).ok()?;
+
+    for header in &response.links {
+        for part in header.split(',') {
+            let mut segments = part.split(';');
+            let href = segments.next()?.trim();
+            if !href.starts_with('<') || !href.ends_with('>') {
+                continue;
+            }
+            let is_webmention = segments.any(|s| {
+                let s = s.trim();
+                s.to_lowercase().starts_with("rel")
+                    && s.splitn(2, '=')
+                        .nth(1)
+                        .map(|v| has_webmention_rel(v.trim_matches(|c| c == '"' || c == '\'')))
+                        .unwrap_or(false)
+            });
+            if is_webmention {
+                if let Ok(url) = base.join(&href[1..href.len() - 1]) {
+                    return Some(url.to_string());
+                }
+            }
+        }
+    }
+
+    if !response.content_type.contains("html") {
+        return None;
+    }
+
+    for tag in TAG_REG.find_iter(&response.body) {
+        let (rel, href) = get_rel_and_href(tag.as_str());
+        if rel.map(|r| has_webmention_rel(&r)).unwrap_or(false) {
+            if let Some(href) = href {
+                if let Ok(url) = base.join(&href) {
+                    return Some(url.to_string());
+                }
+            }
+        }
+    }
+
+    return None;
+}
+
+// 判断来源页面中是否有指向目标地址的链接
+pub fn links_to(response: &FetchResponse, target: &str) -> bool {
+    let target = match Url::parse(target) {
+        Ok(url) => normalize_url(url),
+        Err(_) => return false,
+    };
+
+    if !response.content_type.contains("html") {
+        return response.body.contains(&target);
+    }
+
+    let base = match Url::parse(&response.url) {
+        Ok(url) => url,
+        Err(_) => return false,
+    };
+
+    return TAG_REG.find_iter(&response.body).any(|tag| {
+        let (_, href) = get_rel_and_href(tag.as_str());
+        href.and_then(|h| base.join(&h).ok())
+            .map(|url| normalize_url(url) == target)
+            .unwrap_or(false)
+    });
+}
+
+// 获取页面标题
+pub fn find_title(body: &str) -> Option<String> {
+    let title = TITLE_REG.captures(body)?.get(1)?.as_str();
+    let title = unescape_html(title.trim());
+    if title.is_empty() {
+        return None;
+    }
+    return Some(title.chars().take(200).collect());
+}
+
+fn get_rel_and_href(tag: &str) -> (Option<String>, Option<String>) {
+    let mut rel = None;
+    let mut href = None;
+    for cap in ATTR_REG.captures_iter(tag) {
+        let value = cap
+            .get(2)
+            .or(cap.get(3))
+            .or(cap.get(4))
+            .map(|v| unescape_html(v.as_str()));
+        match cap[1].to_lowercase().as_str() {
+            "rel" => rel = value,
+            _ => href = value,
+        }
+    }
+    return (rel, href);
+}
+
+fn has_webmention_rel(rel: &str) -> bool {
+    return rel
+        .split_whitespace()
+        .any(|r| r.eq_ignore_ascii_case("webmention"));
+}
+
+// 比较链接时忽略锚点和末尾的 /
+fn normalize_url(mut url: Url) -> String {
+    url.set_fragment(None);
+    return url.to_string().trim_end_matches('/').to_string();
+}
+
+fn unescape_html(s: &str) -> String {
+    return s
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(url: &str, body: &str) -> FetchResponse {
+        return FetchResponse {
+            status: 200,
+            url: url.to_string(),
+            content_type: String::from("text/html; charset=utf-8"),
+            links: vec![],
+            body: body.to_string(),
+        };
+    }
+
+    #[test]
+    fn discover_endpoint_from_link_header() {
+        let mut response = html("https://example.com/post/1", "");
+        response.links = vec![String::from(
+            r#"<https://example.com/style.css>; rel="stylesheet", </webmention>; rel="webmention""#,
+        )];
+        assert_eq!(
+            discover_endpoint(&response),
+            Some(String::from("https://example.com/webmention"))
+        );
+    }
+
+    #[test]
+    fn discover_endpoint_from_html() {
+        let response = html(
+            "https://example.com/post/1",
+            r#"<html><head><link rel="me authn" href="/me"><link href="endpoint?x=1&amp;y=2" rel="webmention"></head></html>"#,
+        );
+        assert_eq!(
+            discover_endpoint(&response),
+            Some(String::from("https://example.com/post/endpoint?x=1&y=2"))
+        );
+    }
+
+    #[test]
+    fn discover_endpoint_prefers_link_header() {
+        let mut response = html(
+            "https://example.com/post/1",
+            r#"<a rel="webmention" href="https://example.com/from-html">"#,
+        );
+        response.links = vec![String::from("<https://example.com/from-header>; rel=webmention")];
+        assert_eq!(
+            discover_endpoint(&response),
+            Some(String::from("https://example.com/from-header"))
+        );
+    }
+
+    #[test]
+    fn discover_endpoint_missing() {
+        let response = html("https://example.com/post/1", r#"<a href="/webmention">x</a>"#);
+        assert_eq!(discover_endpoint(&response), None);
+
+        let mut response = html("https://example.com/post/1", r#"<link rel="webmention" href="/wm">"#);
+        response.content_type = String::from("application/json");
+        assert_eq!(discover_endpoint(&response), None);
+    }
+
+    #[test]
+    fn links_to_target() {
+        let response = html(
+            "https://source.com/a",
+            r#"<p>see <a class="u-in-reply-to" href="https://blog.com/blog/1/#comments">this</a></p>"#,
+        );
+        assert!(links_to(&response, "https://blog.com/blog/1"));
+        assert!(!links_to(&response, "https://blog.com/blog/2"));
+    }
+
+    #[test]
+    fn links_to_ignores_plain_text_in_html() {
+        let response = html("https://source.com/a", "https://blog.com/blog/1");
+        assert!(!links_to(&response, "https://blog.com/blog/1"));
+    }
+
+    #[test]
+    fn find_outbound_links_excludes_own_domain() {
+        let links = find_outbound_links(
+            "[a](https://other.com/x). <https://www.blog.com/y> https://other.com/x",
+            &vec![String::from("blog.com")],
+            10,
+        );
+        assert_eq!(links, vec![String::from("https://other.com/x")]);
+    }
+}
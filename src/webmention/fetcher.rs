@@ -0,0 +1,280 @@
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt::task::spawn_blocking;
+use futures::future::BoxFuture;
+use hyper::client::connect::dns::Name;
+use reqwest::Client;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::LINK;
+use reqwest::redirect::Policy;
+use url::Url;
+
+use crate::common::constants::WEBMENTION_MAX_REDIRECTS;
+
+// 抓取页面的结果
+#[derive(Debug)]
+pub struct FetchResponse {
+    pub status: u16,
+    // 跟随重定向之后的最终地址，用于解析相对链接
+    pub url: String,
+    pub content_type: String,
+    // 响应头中所有的 Link 值
+    pub links: Vec<String>,
+    pub body: String,
+}
+
+// Webmention 使用的 HTTP 客户端，验证来源页面和发送通知都经过它，可以替换成本地实现
+pub trait HttpFetcher: Send + Sync {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, String>>;
+
+    // 提交表单，返回响应状态码
+    fn post_form<'a>(
+        &'a self,
+        url: &'a str,
+        form: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<u16, String>>;
+}
+
+pub struct ReqwestFetcher {
+    client: Client,
+    max_body_size: usize,
+}
+
+impl ReqwestFetcher {
+    // 只访问公网地址：域名解析后过滤掉内网地址，每次重定向都重新检查，不使用系统代理
+    pub fn new(timeout: u64, max_body_size: usize) -> ReqwestFetcher {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout))
+            .user_agent("rust-blog-api webmention")
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .redirect(Policy::custom(|attempt| {
+                if attempt.previous().len() >= WEBMENTION_MAX_REDIRECTS {
+                    return attempt.error("重定向次数过多");
+                }
+                return match check_url(attempt.url().as_str()) {
+                    Ok(_) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                };
+            }))
+            .build()
+            .unwrap();
+        ReqwestFetcher {
+            client,
+            max_body_size,
+        }
+    }
+}
+
+impl HttpFetcher for ReqwestFetcher {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, String>> {
+        Box::pin(async move {
+            check_url(url)?;
+
+            let mut response = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let status = response.status().as_u16();
+
+            let final_url = response.url().to_string();
+
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|h| h.to_str().ok())
+                .unwrap_or("")
+                .to_lowercase();
+
+            let links = response
+                .headers()
+                .get_all(LINK)
+                .iter()
+                .filter_map(|h| h.to_str().ok())
+                .map(|h| h.to_string())
+                .collect();
+
+            // 只读取限定大小的内容，避免抓取过大的页面
+            let mut bytes: Vec<u8> = Vec::new();
+            while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+                bytes.extend_from_slice(&chunk);
+                if bytes.len() >= self.max_body_size {
+                    bytes.truncate(self.max_body_size);
+                    break;
+                }
+            }
+
+            Ok(FetchResponse {
+                status,
+                url: final_url,
+                content_type,
+                links,
+                body: String::from_utf8_lossy(&bytes).to_string(),
+            })
+        })
+    }
+
+    fn post_form<'a>(
+        &'a self,
+        url: &'a str,
+        form: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<u16, String>> {
+        Box::pin(async move {
+            check_url(url)?;
+
+            let response = self
+                .client
+                .post(url)
+                .form(form)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(response.status().as_u16())
+        })
+    }
+}
+
+// 解析域名后只保留公网地址，防止域名指向内网地址绕过检查
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let lookup = host.clone();
+            let addrs: Vec<SocketAddr> =
+                spawn_blocking(move || (lookup.as_str(), 0).to_socket_addrs())
+                    .await??
+                    .filter(|addr| is_public_ip(&addr.ip()))
+                    .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} 没有可以访问的公网地址", host).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+// 只允许 http(s) 链接，主机是 IP 时必须是公网地址，域名在连接前由 PublicResolver 检查
+pub fn check_url(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| format!("无效的链接 {}: {}", url, e))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("不支持的协议 {}", url.scheme()));
+    }
+    return match url.host() {
+        Some(url::Host::Ipv4(ip)) if !is_public_ip(&IpAddr::V4(ip)) => {
+            Err(format!("不允许访问内网地址 {}", ip))
+        }
+        Some(url::Host::Ipv6(ip)) if !is_public_ip(&IpAddr::V6(ip)) => {
+            Err(format!("不允许访问内网地址 {}", ip))
+        }
+        Some(url::Host::Domain(domain)) if domain.eq_ignore_ascii_case("localhost") => {
+            Err(String::from("不允许访问 localhost"))
+        }
+        Some(_) => Ok(()),
+        None => Err(String::from("链接缺少主机名")),
+    };
+}
+
+// 排除回环、内网、链路本地、组播以及其他保留地址
+pub fn is_public_ip(ip: &IpAddr) -> bool {
+    return match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, _, _] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0)
+                || (a == 198 && (b == 18 || b == 19))
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(&IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first & 0xffc0) == 0xfec0
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+                || first == 0x0064)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_url_accepts_public_http() {
+        assert!(check_url("https://example.com/post/1").is_ok());
+        assert!(check_url("http://93.184.216.34/").is_ok());
+        assert!(check_url("https://[2606:2800:220:1::]/").is_ok());
+    }
+
+    #[test]
+    fn check_url_rejects_other_schemes() {
+        assert!(check_url("ftp://example.com/file").is_err());
+        assert!(check_url("file:///etc/passwd").is_err());
+        assert!(check_url("gopher://example.com/").is_err());
+        assert!(check_url("not a url").is_err());
+    }
+
+    #[test]
+    fn check_url_rejects_private_hosts() {
+        for url in [
+            "http://127.0.0.1/",
+            "http://localhost:8080/",
+            "http://10.0.0.1/",
+            "http://172.16.5.4/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(check_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn public_ip_ranges() {
+        let public = ["8.8.8.8", "1.1.1.1", "2001:4860:4860::8888"];
+        for ip in public {
+            assert!(is_public_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+        let reserved = [
+            "127.0.0.53",
+            "192.0.0.8",
+            "198.18.0.1",
+            "224.0.0.1",
+            "255.255.255.255",
+            "240.0.0.1",
+            "::",
+            "ff02::1",
+            "64:ff9b::7f00:1",
+        ];
+        for ip in reserved {
+            assert!(!is_public_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}